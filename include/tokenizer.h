@@ -0,0 +1,149 @@
+#ifndef TOKENIZER_H
+#define TOKENIZER_H
+
+/* Generated with `make header`, do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The kind of a token without its payload, for cheap matching and use as a map key.
+ */
+enum TokenKindTag
+#if __STDC_VERSION__ >= 202311L
+  : uint8_t
+#endif // __STDC_VERSION__ >= 202311L
+ {
+  TokenKindTag_StringLiteral,
+  TokenKindTag_Comment,
+  TokenKindTag_Identifier,
+  TokenKindTag_Symbol,
+  TokenKindTag_IntegerLiteral,
+  TokenKindTag_FloatLiteral,
+  TokenKindTag_DecimalLiteral,
+  TokenKindTag_PercentLiteral,
+  TokenKindTag_DateTimeLiteral,
+  TokenKindTag_ColorLiteral,
+  TokenKindTag_Attribute,
+  TokenKindTag_Variable,
+  TokenKindTag_Directive,
+  TokenKindTag_UnitLiteral,
+  TokenKindTag_Unknown,
+};
+#if __STDC_VERSION__ >= 202311L
+typedef enum TokenKindTag TokenKindTag;
+#else
+typedef uint8_t TokenKindTag;
+#endif // __STDC_VERSION__ >= 202311L
+
+/**
+ * The tokens, and the error if there was one, from tokenizing a buffer.
+ */
+typedef struct TokenizerResult TokenizerResult;
+
+/**
+ * A token as seen from C. `text` points at `text_len` bytes of UTF-8 that are not nul
+ * terminated and live as long as the result the token came from.
+ */
+typedef struct TokenizerToken {
+  TokenKindTag kind;
+  /**
+   * The byte offset of the start of the token in the source.
+   */
+  uintptr_t start;
+  /**
+   * The byte offset just past the end of the token in the source.
+   */
+  uintptr_t end;
+  /**
+   * The zero based line of the token.
+   */
+  uintptr_t line;
+  /**
+   * The zero based column of the token, in characters.
+   */
+  uintptr_t column;
+  const uint8_t *text;
+  uintptr_t text_len;
+  /**
+   * The value of an integer literal, saturated to fit. Zero for other kinds.
+   */
+  int64_t integer_value;
+  /**
+   * The value of a float, percent or unit literal. Zero for other kinds.
+   */
+  double float_value;
+} TokenizerToken;
+
+/**
+ * An error as seen from C. The strings are nul terminated and live as long as the result
+ * the error came from.
+ */
+typedef struct TokenizerError {
+  /**
+   * The error code, such as `T0001`.
+   */
+  const char *code;
+  const char *message;
+  /**
+   * The zero based line the error occurred on.
+   */
+  uintptr_t line;
+  /**
+   * The zero based column the error occurred at, in characters.
+   */
+  uintptr_t column;
+} TokenizerError;
+
+/**
+ * Tokenizes `len` bytes of UTF-8 at `source` with the default config. The result holds
+ * the tokens read before any error, and must be freed with `tokenizer_result_free`.
+ *
+ * Returns null if `source` is null or isn't valid UTF-8.
+ *
+ * # Safety
+ *
+ * `source` must point to at least `len` readable bytes.
+ */
+struct TokenizerResult *tokenizer_tokenize(const char *source, uintptr_t len);
+
+/**
+ * Returns the number of tokens in the result.
+ *
+ * # Safety
+ *
+ * `result` must be null or a result from `tokenizer_tokenize` that hasn't been freed.
+ */
+uintptr_t tokenizer_result_token_count(const struct TokenizerResult *result);
+
+/**
+ * Returns the token at the index, or null if the index is out of range.
+ *
+ * # Safety
+ *
+ * `result` must be null or a result from `tokenizer_tokenize` that hasn't been freed.
+ */
+const struct TokenizerToken *tokenizer_result_token(const struct TokenizerResult *result,
+                                                    uintptr_t index);
+
+/**
+ * Returns the error that stopped tokenizing, or null if there wasn't one.
+ *
+ * # Safety
+ *
+ * `result` must be null or a result from `tokenizer_tokenize` that hasn't been freed.
+ */
+const struct TokenizerError *tokenizer_result_error(const struct TokenizerResult *result);
+
+/**
+ * Frees a result along with its tokens and error.
+ *
+ * # Safety
+ *
+ * `result` must be null or a result from `tokenizer_tokenize` that hasn't been freed.
+ */
+void tokenizer_result_free(struct TokenizerResult *result);
+
+#endif  /* TOKENIZER_H */
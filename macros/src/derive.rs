@@ -0,0 +1,126 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Fields, Ident, LitStr, Variant};
+
+/// The kinds of token a variant can take all of, with the attribute that selects them.
+const CLASSES: [(&str, &str); 7] = [
+    ("identifier", "Identifier"),
+    ("symbol", "Symbol"),
+    ("string", "StringLiteral"),
+    ("comment", "Comment"),
+    ("integer", "IntegerLiteral"),
+    ("float", "FloatLiteral"),
+    ("unknown", "Unknown"),
+];
+
+pub fn derive_tokenize(input: DeriveInput) -> Result<TokenStream, Error> {
+    let Data::Enum(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input,
+            "Tokenize can only be derived for enums",
+        ));
+    };
+
+    let mut literals = Vec::new();
+    let mut classes: Vec<(&str, &Variant)> = Vec::new();
+    for variant in &data.variants {
+        for attr in &variant.attrs {
+            if attr.path().is_ident("token") {
+                if !matches!(variant.fields, Fields::Unit) {
+                    return Err(Error::new_spanned(
+                        variant,
+                        "#[token] variants can't have fields",
+                    ));
+                }
+                let literal: LitStr = attr.parse_args()?;
+                literals.push((literal, &variant.ident));
+            } else if let Some((_, kind)) =
+                CLASSES.iter().find(|(name, _)| attr.path().is_ident(name))
+            {
+                if classes.iter().any(|(existing, _)| existing == kind) {
+                    return Err(Error::new_spanned(
+                        attr,
+                        "another variant already takes this kind of token",
+                    ));
+                }
+                classes.push((kind, variant));
+            }
+        }
+    }
+
+    let literal_arms = literals.iter().map(|(literal, variant)| {
+        quote!(#literal => return ::core::option::Option::Some(Self::#variant),)
+    });
+    let class_arms = classes
+        .iter()
+        .map(|(kind, variant)| class_arm(kind, variant))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::tokenizer::Tokenize for #name #type_generics #where_clause {
+            fn from_token(token: &::tokenizer::Token) -> ::core::option::Option<Self> {
+                if ::core::matches!(
+                    token.kind,
+                    ::tokenizer::TokenKind::Identifier(_) | ::tokenizer::TokenKind::Symbol(_)
+                ) {
+                    match token.contents.as_str() {
+                        #(#literal_arms)*
+                        _ => {}
+                    }
+                }
+
+                #[allow(unreachable_patterns)]
+                match &token.kind {
+                    #(#class_arms)*
+                    _ => {}
+                }
+
+                ::core::option::Option::None
+            }
+        }
+    })
+}
+
+/// Returns the match arm that builds the variant from a token of the kind.
+fn class_arm(kind: &str, variant: &Variant) -> Result<TokenStream, Error> {
+    let ident = &variant.ident;
+    let unit = matches!(variant.fields, Fields::Unit);
+    let single = matches!(&variant.fields, Fields::Unnamed(fields) if fields.unnamed.len() == 1);
+
+    let name = kind;
+    let kind = Ident::new(name, proc_macro2::Span::call_site());
+    match name {
+        _ if unit => Ok(quote! {
+            ::tokenizer::TokenKind::#kind(_) => return ::core::option::Option::Some(Self::#ident),
+        }),
+        // Integers that don't fit the field aren't taken.
+        "IntegerLiteral" if single => Ok(quote! {
+            ::tokenizer::TokenKind::#kind(value) => {
+                if let ::core::result::Result::Ok(value) = ::core::convert::TryFrom::try_from(*value) {
+                    return ::core::option::Option::Some(Self::#ident(value));
+                }
+            }
+        }),
+        "FloatLiteral" if single => Ok(quote! {
+            ::tokenizer::TokenKind::#kind(value) => {
+                return ::core::option::Option::Some(Self::#ident(::core::convert::From::from(*value)));
+            }
+        }),
+        "Comment" if single => Ok(quote! {
+            ::tokenizer::TokenKind::#kind(comment) => {
+                return ::core::option::Option::Some(Self::#ident(::core::convert::From::from(comment.text.as_str())));
+            }
+        }),
+        _ if single => Ok(quote! {
+            ::tokenizer::TokenKind::#kind(value) => {
+                return ::core::option::Option::Some(Self::#ident(::core::convert::From::from(value.as_str())));
+            }
+        }),
+        _ => Err(Error::new_spanned(
+            variant,
+            "variants taking a kind of token must be a unit or have a single field",
+        )),
+    }
+}
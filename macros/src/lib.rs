@@ -0,0 +1,186 @@
+use proc_macro::TokenStream;
+use proc_macro2::{Literal, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{parse_macro_input, DeriveInput, LitStr};
+
+mod derive;
+
+// The lexer is compiled into the macro from the main crate's sources, as the main crate
+// can't depend on this one and have this one depend on it.
+#[allow(dead_code)]
+#[path = "../../src/class.rs"]
+mod class;
+#[allow(dead_code)]
+#[path = "../../src/compiled.rs"]
+mod compiled;
+#[allow(dead_code)]
+#[path = "../../src/config.rs"]
+mod config;
+#[allow(dead_code)]
+#[path = "../../src/dfa.rs"]
+mod dfa;
+#[allow(dead_code)]
+#[path = "../../src/error.rs"]
+mod error;
+#[allow(dead_code)]
+#[path = "../../src/location.rs"]
+mod location;
+#[allow(dead_code)]
+#[path = "../../src/span.rs"]
+mod span;
+#[allow(dead_code)]
+#[path = "../../src/text.rs"]
+mod text;
+#[allow(dead_code)]
+#[path = "../../src/token.rs"]
+mod token;
+#[allow(dead_code)]
+#[path = "../../src/tokenizer.rs"]
+mod tokenizer;
+#[allow(dead_code)]
+#[path = "../../src/warning.rs"]
+mod warning;
+
+use token::{CommentStyle, Token, TokenKind};
+
+/// Tokenizes a string literal at compile time, expanding to a
+/// `&'static [tokenizer::StaticToken]`. Tokenizing errors become compile errors.
+#[proc_macro]
+pub fn tokenize(input: TokenStream) -> TokenStream {
+    let literal = parse_macro_input!(input as LitStr);
+    let source = literal.value();
+
+    match tokenizer::Tokenizer::tokenize(&source, (0, 0).into()) {
+        Ok(tokens) => {
+            let tokens = tokens.iter().map(static_token);
+            quote! {
+                {
+                    const TOKENS: &[::tokenizer::StaticToken] = &[#(#tokens),*];
+                    TOKENS
+                }
+            }
+            .into()
+        }
+        Err(error) => syn::Error::new(literal.span(), error.render(&source))
+            .to_compile_error()
+            .into(),
+    }
+}
+
+/// Derives `tokenizer::Tokenize` for an enum, mapping tokens to its variants.
+///
+/// Unit variants marked `#[token("...")]` match identifiers and symbols with that exact
+/// text, and take priority. A variant marked `#[identifier]`, `#[symbol]`, `#[string]`,
+/// `#[comment]`, `#[integer]`, `#[float]` or `#[unknown]` takes every other token of that
+/// kind, converting the payload into its single field if it has one.
+#[proc_macro_derive(
+    Tokenize,
+    attributes(token, identifier, symbol, string, comment, integer, float, unknown)
+)]
+pub fn derive_tokenize(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    derive::derive_tokenize(input)
+        .unwrap_or_else(|error| error.to_compile_error())
+        .into()
+}
+
+fn static_token(token: &Token) -> TokenStream2 {
+    let row = token.location.row;
+    let column = token.location.column;
+    let start = token.span.start;
+    let end = token.span.end;
+    let contents = token.contents.as_str();
+    let kind = match &token.kind {
+        TokenKind::StringLiteral(s) => {
+            let s = s.as_str();
+            quote!(StringLiteral(#s))
+        }
+        TokenKind::Comment(comment) => {
+            let style = match comment.style {
+                CommentStyle::Line => quote!(Line),
+                CommentStyle::Doc => quote!(Doc),
+                CommentStyle::Block => quote!(Block),
+            };
+            let raw = comment.raw.as_str();
+            quote!(Comment { style: ::tokenizer::CommentStyle::#style, raw: #raw })
+        }
+        TokenKind::Identifier(s) => {
+            let s = s.as_str();
+            quote!(Identifier(#s))
+        }
+        TokenKind::Symbol(s) => {
+            let s = s.as_str();
+            quote!(Symbol(#s))
+        }
+        TokenKind::IntegerLiteral(i) => {
+            let i = Literal::i128_suffixed(*i);
+            quote!(IntegerLiteral(#i))
+        }
+        TokenKind::FloatLiteral(f) => {
+            let f = float(f);
+            quote!(FloatLiteral(#f))
+        }
+        TokenKind::DecimalLiteral(s) => {
+            let s = s.as_str();
+            quote!(DecimalLiteral(#s))
+        }
+        TokenKind::PercentLiteral(s) => {
+            let s = s.as_str();
+            quote!(PercentLiteral(#s))
+        }
+        TokenKind::DateTimeLiteral(s) => {
+            let s = s.as_str();
+            quote!(DateTimeLiteral(#s))
+        }
+        TokenKind::ColorLiteral(s) => {
+            let s = s.as_str();
+            quote!(ColorLiteral(#s))
+        }
+        TokenKind::Attribute(s) => {
+            let s = s.as_str();
+            quote!(Attribute(#s))
+        }
+        TokenKind::Variable { sigil, name } => {
+            let name = name.as_str();
+            quote!(Variable { sigil: #sigil, name: #name })
+        }
+        TokenKind::Directive(directive) => {
+            let name = directive.name.as_str();
+            let arguments = directive.arguments.as_str();
+            quote!(Directive { name: #name, arguments: #arguments })
+        }
+        TokenKind::UnitLiteral { value, unit } => {
+            let value = value.as_str();
+            let unit = &**unit;
+            quote!(UnitLiteral { value: #value, unit: #unit })
+        }
+        TokenKind::Unknown(s) => {
+            let s = s.as_str();
+            quote!(Unknown(#s))
+        }
+    };
+
+    quote! {
+        ::tokenizer::StaticToken {
+            location: ::tokenizer::Location { row: #row, column: #column, path: None },
+            span: ::tokenizer::Span { start: #start, end: #end },
+            contents: #contents,
+            kind: ::tokenizer::StaticTokenKind::#kind,
+        }
+    }
+}
+
+/// A float as an expression, naming the constant for infinities and NaN, which have no
+/// literal and which a float too long for an `f64` reads as.
+fn float(f: &f64) -> TokenStream2 {
+    if f.is_nan() {
+        quote!(::core::f64::NAN)
+    } else if *f == f64::INFINITY {
+        quote!(::core::f64::INFINITY)
+    } else if *f == f64::NEG_INFINITY {
+        quote!(::core::f64::NEG_INFINITY)
+    } else {
+        let f = Literal::f64_suffixed(*f);
+        quote!(#f)
+    }
+}
@@ -0,0 +1,202 @@
+use ::arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::{
+    location::Location,
+    span::Span,
+    token::{Comment, CommentStyle, Directive, Provenance, Token, TokenKind},
+};
+
+/// Symbols that read as a single token with the default config.
+const SYMBOLS: [&str; 21] = [
+    "*", "/", "=", "==", "!=", "<", ">", "<=", ">=", "->", "=>", "(", ")", "[", "]", "{", "}", ",",
+    ";", ":", "..",
+];
+const UNITS: [&str; 4] = ["px", "ms", "em", "kg"];
+
+impl<'a> Arbitrary<'a> for TokenKind {
+    /// Generates a kind with a payload the tokenizer could have produced, such as a
+    /// well-formed identifier or date-time, rather than arbitrary bytes.
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let kind = match u.int_in_range(0..=15)? {
+            0 => TokenKind::StringLiteral(text(u)?.into()),
+            1 => {
+                let style =
+                    *u.choose(&[CommentStyle::Line, CommentStyle::Doc, CommentStyle::Block])?;
+                TokenKind::Comment(Box::new(Comment::new(style, &format!(" {}", text(u)?))))
+            }
+            2 => TokenKind::Identifier(identifier(u)?.into()),
+            3 => TokenKind::Symbol((*u.choose(&SYMBOLS)?).into()),
+            4 => TokenKind::IntegerLiteral(u.arbitrary::<i64>()?.into()),
+            5 => TokenKind::FloatLiteral(fraction(u)?.parse().unwrap()),
+            6 => TokenKind::DecimalLiteral(fraction(u)?.into()),
+            7 => TokenKind::PercentLiteral(fraction(u)?.into()),
+            8 => TokenKind::DateTimeLiteral(datetime(u)?.into()),
+            9 => {
+                let color: String = (0..6)
+                    .map(|_| Ok(char::from(*u.choose(b"0123456789abcdef")?)))
+                    .collect::<Result<_>>()?;
+                TokenKind::ColorLiteral(color.into())
+            }
+            10 => TokenKind::Attribute(identifier(u)?.into()),
+            11 => TokenKind::Variable {
+                sigil: *u.choose(&['$', '@', '%'])?,
+                name: identifier(u)?.into(),
+            },
+            12 => TokenKind::Directive(Box::new(Directive {
+                name: identifier(u)?.into(),
+                arguments: text(u)?.trim().into(),
+            })),
+            13 => TokenKind::UnitLiteral {
+                value: fraction(u)?.into(),
+                unit: (*u.choose(&UNITS)?).into(),
+            },
+            #[cfg(feature = "bigint")]
+            14 => {
+                let digits: String = (0..u.int_in_range(40..=60)?)
+                    .map(|_| Ok(char::from(b'0' + u.int_in_range(0..=9)?)))
+                    .collect::<Result<_>>()?;
+                TokenKind::BigIntegerLiteral(digits.parse().unwrap())
+            }
+            _ => TokenKind::Unknown(u.choose(&["`", "\\", "\u{2603}"])?.to_string().into()),
+        };
+        Ok(kind)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Token {
+    /// Generates a token whose contents are a lexeme for its kind and whose span covers
+    /// them.
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let kind = TokenKind::arbitrary(u)?;
+        let contents = lexeme(&kind);
+        let start = u.int_in_range(0..=1 << 20)?;
+        Ok(Token {
+            location: Location::new(u.int_in_range(0..=200)?, u.int_in_range(0..=10_000)?, None),
+            span: Span::new(start, start + contents.len()),
+            contents: contents.into(),
+            kind,
+            provenance: Provenance::Original,
+        })
+    }
+}
+
+/// Source that tokenizes without errors under the default config, for fuzzing parsers
+/// with realistic input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArbitrarySource(pub String);
+impl<'a> Arbitrary<'a> for ArbitrarySource {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut source = String::new();
+        u.arbitrary_loop(None, Some(200), |u| {
+            match u.int_in_range(0..=5)? {
+                0 => source.push_str(&identifier(u)?),
+                1 => source.push_str(u.choose(&SYMBOLS)?),
+                2 => source.push_str(&u.int_in_range(0..=1_000_000u32)?.to_string()),
+                3 => source.push_str(&fraction(u)?),
+                4 => source.push_str(&format!("\"{}\"", text(u)?)),
+                _ => source.push_str(&format!("# {}\n", text(u)?)),
+            }
+            source.push_str(u.choose(&[" ", "\n", "\t", "  "])?);
+            Ok(std::ops::ControlFlow::Continue(()))
+        })?;
+        Ok(Self(source))
+    }
+}
+
+/// Returns source text that reads as the kind.
+fn lexeme(kind: &TokenKind) -> String {
+    match kind {
+        TokenKind::StringLiteral(s) => format!("\"{}\"", s),
+        TokenKind::Comment(comment) => match comment.style {
+            CommentStyle::Line => format!("#{}", comment.raw),
+            CommentStyle::Doc => format!("##{}", comment.raw),
+            CommentStyle::Block => format!("#|{}|#", comment.raw),
+        },
+        TokenKind::ColorLiteral(s) => format!("#{}", s),
+        TokenKind::Attribute(s) => format!("@{}", s),
+        TokenKind::Variable { sigil, name } => format!("{}{}", sigil, name),
+        TokenKind::Directive(directive) => format!("#{} {}", directive.name, directive.arguments)
+            .trim_end()
+            .to_string(),
+        TokenKind::UnitLiteral { value, unit } => format!("{}{}", value, unit),
+        TokenKind::PercentLiteral(f) => format!("{}%", f),
+        TokenKind::FloatLiteral(f) => format!("{:?}", f),
+        TokenKind::IntegerLiteral(i) => i.to_string(),
+        #[cfg(feature = "bigint")]
+        TokenKind::BigIntegerLiteral(i) => i.to_string(),
+        TokenKind::Identifier(s)
+        | TokenKind::Symbol(s)
+        | TokenKind::DecimalLiteral(s)
+        | TokenKind::DateTimeLiteral(s)
+        | TokenKind::Unknown(s) => s.to_string(),
+    }
+}
+
+fn identifier(u: &mut Unstructured) -> Result<String> {
+    const START: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ_";
+    let mut identifier = String::from(char::from(*u.choose(START)?));
+    for _ in 0..u.int_in_range(0..=12)? {
+        identifier.push(char::from(
+            *u.choose(b"abcdefghijklmnopqrstuvwxyz_0123456789")?,
+        ));
+    }
+    Ok(identifier)
+}
+
+/// Returns text without quotes, backslashes or line breaks.
+fn text(u: &mut Unstructured) -> Result<String> {
+    (0..u.int_in_range(0..=24)?)
+        .map(|_| {
+            Ok(char::from(
+                *u.choose(b"abcdefghijklmnopqrstuvwxyz0123456789 .,!?")?,
+            ))
+        })
+        .collect()
+}
+
+/// Returns a number with a fractional part, such as `12.5`.
+fn fraction(u: &mut Unstructured) -> Result<String> {
+    Ok(format!(
+        "{}.{}",
+        u.int_in_range(0..=100_000u32)?,
+        u.int_in_range(0..=999u32)?
+    ))
+}
+
+fn datetime(u: &mut Unstructured) -> Result<String> {
+    Ok(format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        u.int_in_range(1970..=2100u32)?,
+        u.int_in_range(1..=12u32)?,
+        u.int_in_range(1..=28u32)?,
+        u.int_in_range(0..=23u32)?,
+        u.int_in_range(0..=59u32)?,
+        u.int_in_range(0..=59u32)?
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Tokenizer;
+
+    #[test]
+    fn arbitrary_source_tokenizes() {
+        for seed in 0..200u32 {
+            let bytes: Vec<u8> = (0..512u32)
+                .map(|i| (i.wrapping_mul(2654435761).wrapping_add(seed * 97) >> 13) as u8)
+                .collect();
+            let mut u = Unstructured::new(&bytes);
+            let ArbitrarySource(source) = ArbitrarySource::arbitrary(&mut u).unwrap();
+            assert!(
+                Tokenizer::tokenize(&source, (0, 0).into()).is_ok(),
+                "{:?}",
+                source
+            );
+
+            let mut u = Unstructured::new(&bytes);
+            let token = Token::arbitrary(&mut u).unwrap();
+            assert_eq!(token.span.end - token.span.start, token.contents.len());
+        }
+    }
+}
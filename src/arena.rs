@@ -0,0 +1,187 @@
+use bumpalo::Bump;
+
+use crate::{
+    config::TokenizerConfig,
+    error::Error,
+    location::Location,
+    span::Span,
+    token::{CommentStyle, Token, TokenKind},
+    tokenizer::Tokenizer,
+};
+
+/// A token whose text is allocated in a bump arena.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArenaToken<'a> {
+    pub location: Location,
+    pub span: Span,
+    pub contents: &'a str,
+    pub kind: ArenaTokenKind<'a>,
+}
+
+/// The kind of an `ArenaToken`, mirroring `TokenKind` with arena allocated strings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArenaTokenKind<'a> {
+    StringLiteral(&'a str),
+    Comment {
+        style: CommentStyle,
+        raw: &'a str,
+        /// `raw` trimmed, borrowed from it.
+        text: &'a str,
+    },
+    Identifier(&'a str),
+    Symbol(&'a str),
+    IntegerLiteral(i128),
+    /// The digits of a `TokenKind::BigIntegerLiteral`.
+    #[cfg(feature = "bigint")]
+    BigIntegerLiteral(&'a str),
+    FloatLiteral(f64),
+    DecimalLiteral(&'a str),
+    PercentLiteral(&'a str),
+    DateTimeLiteral(&'a str),
+    ColorLiteral(&'a str),
+    Attribute(&'a str),
+    Variable {
+        sigil: char,
+        name: &'a str,
+    },
+    Directive {
+        name: &'a str,
+        arguments: &'a str,
+    },
+    UnitLiteral {
+        value: &'a str,
+        unit: &'a str,
+    },
+    Unknown(&'a str),
+}
+
+/// Tokenizes the contents, allocating the text of every token in `bump` so that a whole
+/// file's tokens can be freed at once by resetting or dropping the arena.
+///
+/// The contents are copied into the arena once, and token text written there as is, such
+/// as identifiers and symbols, is sliced from that copy. Only text the tokenizer changed,
+/// such as a string with escapes, is allocated on its own. Tokens are read one at a time,
+/// so there's never a second, owned copy of them all.
+pub fn tokenize_in<'a>(
+    bump: &'a Bump,
+    contents: &str,
+    location: Location,
+    config: &TokenizerConfig,
+) -> Result<Vec<ArenaToken<'a>>, Error> {
+    let source: &'a str = bump.alloc_str(contents);
+    let mut tokenizer = Tokenizer::with_config(config.clone());
+    tokenizer.reset(contents, location);
+
+    let mut tokens = Vec::new();
+    while let Some(token) = tokenizer.next_token() {
+        let token = token?;
+        let span = token.span;
+        tokens.push(ArenaToken::convert(token, |text| {
+            slice_or_alloc(bump, source, span, text)
+        }));
+    }
+    Ok(tokens)
+}
+
+/// Returns `text` sliced from where it's written in the span of `source`, or a copy of it in
+/// the arena if it isn't written there.
+fn slice_or_alloc<'a>(bump: &'a Bump, source: &'a str, span: Span, text: &str) -> &'a str {
+    let written = source.get(span.start..span.end).unwrap_or_default();
+    match written.find(text) {
+        Some(i) => &written[i..i + text.len()],
+        None => bump.alloc_str(text),
+    }
+}
+
+impl<'a> ArenaToken<'a> {
+    /// Moves the token's text into the arena.
+    pub fn new_in(bump: &'a Bump, token: Token) -> Self {
+        Self::convert(token, |text| bump.alloc_str(text))
+    }
+
+    /// Converts the token, getting each piece of its text from `text`.
+    fn convert(token: Token, mut text: impl FnMut(&str) -> &'a str) -> Self {
+        let kind = match &token.kind {
+            TokenKind::StringLiteral(s) => ArenaTokenKind::StringLiteral(text(s)),
+            TokenKind::Comment(comment) => {
+                let raw = text(&comment.raw);
+                ArenaTokenKind::Comment {
+                    style: comment.style,
+                    raw,
+                    text: raw.trim(),
+                }
+            }
+            TokenKind::Identifier(s) => ArenaTokenKind::Identifier(text(s)),
+            TokenKind::Symbol(s) => ArenaTokenKind::Symbol(text(s)),
+            TokenKind::IntegerLiteral(i) => ArenaTokenKind::IntegerLiteral(*i),
+            #[cfg(feature = "bigint")]
+            TokenKind::BigIntegerLiteral(i) => {
+                ArenaTokenKind::BigIntegerLiteral(text(&i.to_string()))
+            }
+            TokenKind::FloatLiteral(f) => ArenaTokenKind::FloatLiteral(*f),
+            TokenKind::DecimalLiteral(s) => ArenaTokenKind::DecimalLiteral(text(s)),
+            TokenKind::PercentLiteral(s) => ArenaTokenKind::PercentLiteral(text(s)),
+            TokenKind::DateTimeLiteral(s) => ArenaTokenKind::DateTimeLiteral(text(s)),
+            TokenKind::ColorLiteral(s) => ArenaTokenKind::ColorLiteral(text(s)),
+            TokenKind::Attribute(s) => ArenaTokenKind::Attribute(text(s)),
+            TokenKind::Variable { sigil, name } => ArenaTokenKind::Variable {
+                sigil: *sigil,
+                name: text(name),
+            },
+            TokenKind::Directive(directive) => ArenaTokenKind::Directive {
+                name: text(&directive.name),
+                arguments: text(&directive.arguments),
+            },
+            TokenKind::UnitLiteral { value, unit } => ArenaTokenKind::UnitLiteral {
+                value: text(value),
+                unit: text(unit),
+            },
+            TokenKind::Unknown(s) => ArenaTokenKind::Unknown(text(s)),
+        };
+
+        Self {
+            contents: text(&token.contents),
+            location: token.location,
+            span: token.span,
+            kind,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenize_in_arena() {
+        let bump = Bump::new();
+        let tokens =
+            tokenize_in(&bump, "let x = \"hi\"", (0, 0).into(), &Default::default()).unwrap();
+        assert_eq!(tokens.len(), 4);
+        assert_eq!(tokens[0].kind, ArenaTokenKind::Identifier("let"));
+        assert_eq!(tokens[3].kind, ArenaTokenKind::StringLiteral("hi"));
+        assert_eq!(tokens[3].contents, "\"hi\"");
+        assert_eq!(tokens[3].location, (0, 8).into());
+        assert!(bump.allocated_bytes() > 0);
+    }
+
+    #[test]
+    fn text_written_as_is_is_sliced_from_the_source() {
+        let bump = Bump::new();
+        let source = "name = \"a\\tb\" # note";
+        let tokens = tokenize_in(&bump, source, (0, 0).into(), &Default::default()).unwrap();
+        let base = tokens[0].contents.as_ptr();
+        let in_source = |text: &str| {
+            let offset = (text.as_ptr() as usize).wrapping_sub(base as usize);
+            offset < source.len() && source[offset..].starts_with(text)
+        };
+
+        assert_eq!(tokens[0].kind, ArenaTokenKind::Identifier("name"));
+        assert!(matches!(tokens[0].kind, ArenaTokenKind::Identifier(s) if in_source(s)));
+        assert!(in_source(tokens[1].contents));
+        assert!(in_source(tokens[2].contents));
+        assert_eq!(tokens[2].kind, ArenaTokenKind::StringLiteral("a\tb"));
+        assert!(matches!(tokens[2].kind, ArenaTokenKind::StringLiteral(s) if !in_source(s)));
+        assert!(matches!(tokens[3].kind, ArenaTokenKind::Comment { raw, .. } if in_source(raw)));
+    }
+}
@@ -0,0 +1,83 @@
+use std::ops::Range;
+
+use chumsky::input::{Input, MappedInput};
+
+use crate::{
+    location::Location,
+    span::Span,
+    token::{Token, TokenKind},
+};
+
+impl chumsky::span::Span for Span {
+    type Context = ();
+    type Offset = usize;
+
+    fn new(_: (), range: Range<usize>) -> Self {
+        Self::new(range.start, range.end)
+    }
+
+    fn context(&self) {}
+
+    fn start(&self) -> usize {
+        self.start
+    }
+
+    fn end(&self) -> usize {
+        self.end
+    }
+}
+
+/// A chumsky input that yields the kinds of the tokens in a slice, spanned by their
+/// byte ranges in the source.
+pub type TokenInput<'a> =
+    MappedInput<'a, TokenKind, Span, &'a [Token], fn(&'a Token) -> (&'a TokenKind, &'a Span)>;
+
+/// Wraps the tokens as a chumsky input. Spans past the last token are empty and sit at
+/// its end.
+pub fn token_input(tokens: &[Token]) -> TokenInput<'_> {
+    let end = tokens.last().map_or(0, |token| token.span.end);
+    tokens.map(Span::new(end, end), |token| (&token.kind, &token.span))
+}
+
+/// Returns the location of the token a span from a chumsky error starts at, or `None`
+/// if the span starts past the last token.
+pub fn location_of<'a>(tokens: &'a [Token], span: &Span) -> Option<&'a Location> {
+    tokens
+        .iter()
+        .find(|token| token.span.end > span.start)
+        .map(|token| &token.location)
+}
+
+#[cfg(test)]
+mod tests {
+    use chumsky::{error::Rich, prelude::*};
+
+    use super::*;
+    use crate::tokenizer::Tokenizer;
+
+    fn list<'a>(
+    ) -> impl Parser<'a, TokenInput<'a>, Vec<i128>, extra::Err<Rich<'a, TokenKind, Span>>> {
+        let integer = select! { TokenKind::IntegerLiteral(i) => i };
+        let symbol = |s: &'static str| just(TokenKind::Symbol(s.into()));
+        integer
+            .separated_by(symbol(","))
+            .collect()
+            .delimited_by(symbol("("), symbol(")"))
+    }
+
+    #[test]
+    fn parse_tokens_with_chumsky() {
+        let tokens = Tokenizer::tokenize("(1, 2, 3)", (0, 0).into()).unwrap();
+        let result = list().parse(token_input(&tokens)).into_result();
+        assert_eq!(result, Ok(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn errors_map_to_locations() {
+        let tokens = Tokenizer::tokenize("(1,\n  x)", (0, 0).into()).unwrap();
+        let errors = list().parse(token_input(&tokens)).into_errors();
+        let span = errors[0].span();
+        assert_eq!(*span, Span::new(6, 7));
+        assert_eq!(location_of(&tokens, span), Some(&(1, 2).into()));
+    }
+}
@@ -0,0 +1,183 @@
+use std::ops::BitOr;
+
+use unicode_xid::UnicodeXID;
+
+use crate::config::{
+    IdentifierRules, InvisibleCharacters, SmartQuotes, TokenizerConfig, Whitespace,
+};
+
+/// The roles a character can play, as a set of flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CharClass(u8);
+impl CharClass {
+    pub const NONE: Self = Self(0);
+    pub const SYMBOL: Self = Self(1);
+    pub const IDENTIFIER_START: Self = Self(1 << 1);
+    pub const IDENTIFIER_CONTINUE: Self = Self(1 << 2);
+    pub const DIGIT: Self = Self(1 << 3);
+    pub const WHITESPACE: Self = Self(1 << 4);
+    pub const QUOTE: Self = Self(1 << 5);
+    pub const COMMENT_START: Self = Self(1 << 6);
+    pub const INVISIBLE: Self = Self(1 << 7);
+
+    /// Returns true if every flag in `other` is set.
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Classifies the character under the given config.
+    pub fn of(c: char, config: &TokenizerConfig) -> Self {
+        let flags = [
+            (is_symbol(c), Self::SYMBOL),
+            (is_identifier_start(c, config), Self::IDENTIFIER_START),
+            (is_identifier_continue(c, config), Self::IDENTIFIER_CONTINUE),
+            (c.is_ascii_digit(), Self::DIGIT),
+            (is_whitespace(c, config), Self::WHITESPACE),
+            (c == '"', Self::QUOTE),
+            (c == '#', Self::COMMENT_START),
+            (is_invisible(c, config), Self::INVISIBLE),
+        ];
+        flags
+            .into_iter()
+            .filter(|(is_set, _)| *is_set)
+            .fold(Self::NONE, |class, (_, flag)| class | flag)
+    }
+}
+impl BitOr for CharClass {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// The class of every byte, built from a config so ASCII characters can be classified
+/// with a single lookup. Bytes outside ASCII are never classified by the table.
+#[derive(Debug, Clone)]
+pub struct CharTable {
+    classes: [CharClass; 256],
+}
+impl CharTable {
+    pub fn new(config: &TokenizerConfig) -> Self {
+        let mut classes = [CharClass::NONE; 256];
+        for byte in 0..128u8 {
+            classes[byte as usize] = CharClass::of(byte as char, config);
+        }
+        Self { classes }
+    }
+
+    /// Returns the class of the character if it's ASCII.
+    pub fn get(&self, c: char) -> Option<CharClass> {
+        c.is_ascii().then(|| self.classes[c as usize])
+    }
+}
+impl Default for CharTable {
+    fn default() -> Self {
+        Self::new(&TokenizerConfig::default())
+    }
+}
+
+pub(crate) fn is_symbol(c: char) -> bool {
+    matches!(
+        c,
+        '+' | '-'
+            | '*'
+            | '/'
+            | '='
+            | '>'
+            | '<'
+            | '!'
+            | '?'
+            | '.'
+            | ','
+            | ';'
+            | ':'
+            | '('
+            | ')'
+            | '['
+            | ']'
+            | '{'
+            | '}'
+            | '&'
+            | '|'
+            | '^'
+            | '%'
+            | '~'
+            | '#'
+            | '@'
+    )
+}
+
+pub(crate) fn is_smart_quote(c: char) -> bool {
+    c == '\u{201C}' || c == '\u{201D}'
+}
+
+pub(crate) fn is_identifier_start(c: char, config: &TokenizerConfig) -> bool {
+    match config.identifiers {
+        IdentifierRules::Permissive => true,
+        IdentifierRules::UnicodeXid => c == '_' || c.is_xid_start(),
+        IdentifierRules::Ascii => c == '_' || c.is_ascii_alphabetic(),
+        IdentifierRules::Custom { start, .. } => start(c),
+    }
+}
+
+pub(crate) fn is_identifier_continue(c: char, config: &TokenizerConfig) -> bool {
+    if is_invisible(c, config) || (is_smart_quote(c) && config.smart_quotes != SmartQuotes::Allow) {
+        return false;
+    }
+
+    match config.identifiers {
+        IdentifierRules::Permissive => !is_whitespace(c, config) && !is_symbol(c),
+        IdentifierRules::UnicodeXid => c.is_xid_continue(),
+        IdentifierRules::Ascii => c == '_' || c.is_ascii_alphanumeric(),
+        IdentifierRules::Custom { continuation, .. } => continuation(c),
+    }
+}
+
+/// Returns true if `c` is a control or zero-width character that isn't allowed.
+pub(crate) fn is_invisible(c: char, config: &TokenizerConfig) -> bool {
+    config.invisible_characters != InvisibleCharacters::Allow
+        && (c.is_control()
+            || matches!(
+                c,
+                '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{2060}' | '\u{FEFF}'
+            ))
+}
+
+pub(crate) fn is_whitespace(c: char, config: &TokenizerConfig) -> bool {
+    let is_whitespace = match config.whitespace {
+        Whitespace::Unicode => c.is_whitespace(),
+        Whitespace::Ascii => c.is_ascii_whitespace(),
+    };
+
+    is_whitespace || config.additional_whitespace.contains(&c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_matches_config() {
+        let table = CharTable::default();
+        assert!(table.get('+').unwrap().contains(CharClass::SYMBOL));
+        assert!(table
+            .get('a')
+            .unwrap()
+            .contains(CharClass::IDENTIFIER_START));
+        assert!(table.get('7').unwrap().contains(CharClass::DIGIT));
+        assert!(table.get('#').unwrap().contains(CharClass::COMMENT_START));
+        assert_eq!(table.get('\u{e9}'), None);
+
+        let config = TokenizerConfig {
+            additional_whitespace: vec!['_'],
+            ..Default::default()
+        };
+        let table = CharTable::new(&config);
+        assert!(table.get('_').unwrap().contains(CharClass::WHITESPACE));
+        assert!(!table
+            .get('_')
+            .unwrap()
+            .contains(CharClass::IDENTIFIER_CONTINUE));
+    }
+}
@@ -0,0 +1,67 @@
+use codespan_reporting::diagnostic::{Diagnostic, Label};
+
+use crate::{error::Error, warning::Warning};
+
+impl Error {
+    /// Converts the error into a codespan-reporting diagnostic for the given file.
+    pub fn to_diagnostic<FileId: Clone>(
+        &self,
+        file_id: FileId,
+        source: &str,
+    ) -> Diagnostic<FileId> {
+        Diagnostic::error()
+            .with_code(self.kind.code())
+            .with_message(&self.message)
+            .with_labels(
+                std::iter::once(Label::primary(file_id.clone(), self.byte_range(source)))
+                    .chain(self.label.iter().map(|label| {
+                        Label::secondary(file_id.clone(), label.span.start..label.span.end)
+                            .with_message(&label.message)
+                    }))
+                    .collect(),
+            )
+    }
+}
+
+impl Warning {
+    /// Converts the warning into a codespan-reporting diagnostic for the given file.
+    pub fn to_diagnostic<FileId>(&self, file_id: FileId, source: &str) -> Diagnostic<FileId> {
+        Diagnostic::warning()
+            .with_message(&self.message)
+            .with_labels(vec![Label::primary(
+                file_id,
+                self.location.byte_range(source),
+            )])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use codespan_reporting::diagnostic::Severity;
+
+    use super::*;
+    use crate::tokenizer::Tokenizer;
+
+    #[test]
+    fn error_to_diagnostic() {
+        let source = "x = 1\ny = \"abc";
+        let error = Tokenizer::tokenize(source, (0, 0).into()).unwrap_err();
+        let diagnostic = error.to_diagnostic(7, source);
+        assert_eq!(diagnostic.severity, Severity::Error);
+        assert_eq!(diagnostic.message, "Unclosed string");
+        assert_eq!(diagnostic.code, Some("T0001".to_string()));
+        assert_eq!(diagnostic.labels[0].file_id, 7);
+        assert_eq!(diagnostic.labels[0].range, 10..14);
+    }
+
+    #[test]
+    fn warning_to_diagnostic() {
+        let warning = Warning {
+            message: "Careful".to_string(),
+            location: (0, 9).into(),
+        };
+        let diagnostic = warning.to_diagnostic((), "short");
+        assert_eq!(diagnostic.severity, Severity::Warning);
+        assert_eq!(diagnostic.labels[0].range, 5..5);
+    }
+}
@@ -0,0 +1,203 @@
+use std::io::{self, IsTerminal};
+
+use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+
+use crate::{
+    error::{label_line, position, underline, Error, Label},
+    location::Location,
+    warning::Warning,
+};
+
+/// Returns whether to color output to the stream: never if `NO_COLOR` is set to anything
+/// but an empty string or the stream isn't a terminal, and automatically otherwise, which
+/// leaves out color on terminals that can't show it.
+pub fn color_choice(stream: &impl IsTerminal) -> ColorChoice {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    if no_color || !stream.is_terminal() {
+        ColorChoice::Never
+    } else {
+        ColorChoice::Auto
+    }
+}
+
+impl Error {
+    /// Writes the error like `render`, with the heading in red, the carets in bold red, any
+    /// label in blue and a cyan note with what was expected for errors from checking a
+    /// token's kind.
+    pub fn write_colored(&self, source: &str, out: &mut impl WriteColor) -> io::Result<()> {
+        let line = source
+            .lines()
+            .nth(self.location.row)
+            .or(self.source_line.as_deref());
+        let notes: Vec<String> = self
+            .mismatch
+            .iter()
+            .map(|mismatch| format!("expected {}", mismatch.expected))
+            .collect();
+        write_diagnostic(
+            out,
+            Heading {
+                level: "error",
+                code: Some(self.kind.code()),
+                color: Color::Red,
+                message: &self.message,
+            },
+            &self.location,
+            self.span.len(),
+            line,
+            self.label.as_deref(),
+            &notes,
+        )
+    }
+
+    /// Writes the colored error to stderr, choosing whether to color with `color_choice`.
+    pub fn eprint_colored(&self, source: &str) -> io::Result<()> {
+        let mut stderr = StandardStream::stderr(color_choice(&io::stderr()));
+        self.write_colored(source, &mut stderr)
+    }
+}
+
+impl Warning {
+    /// Writes the warning in the same shape as a rendered error, with the heading and
+    /// carets in yellow.
+    pub fn write_colored(&self, source: &str, out: &mut impl WriteColor) -> io::Result<()> {
+        write_diagnostic(
+            out,
+            Heading {
+                level: "warning",
+                code: None,
+                color: Color::Yellow,
+                message: &self.message,
+            },
+            &self.location,
+            0,
+            source.lines().nth(self.location.row),
+            None,
+            &[],
+        )
+    }
+
+    /// Writes the colored warning to stderr, choosing whether to color with `color_choice`.
+    pub fn eprint_colored(&self, source: &str) -> io::Result<()> {
+        let mut stderr = StandardStream::stderr(color_choice(&io::stderr()));
+        self.write_colored(source, &mut stderr)
+    }
+}
+
+/// The first line of a diagnostic, such as `error[T0001]: Unclosed string`.
+struct Heading<'a> {
+    level: &'a str,
+    code: Option<&'a str>,
+    color: Color,
+    message: &'a str,
+}
+
+fn write_diagnostic(
+    out: &mut impl WriteColor,
+    heading: Heading,
+    location: &Location,
+    len: usize,
+    line: Option<&str>,
+    label: Option<&Label>,
+    notes: &[String],
+) -> io::Result<()> {
+    let mut level = ColorSpec::new();
+    level.set_fg(Some(heading.color)).set_bold(true);
+    let mut bold = ColorSpec::new();
+    bold.set_bold(true);
+    let mut margin = ColorSpec::new();
+    margin.set_fg(Some(Color::Blue)).set_bold(true);
+    let mut note = ColorSpec::new();
+    note.set_fg(Some(Color::Cyan)).set_bold(true);
+
+    out.set_color(&level)?;
+    match heading.code {
+        Some(code) => write!(out, "{}[{}]", heading.level, code)?,
+        None => write!(out, "{}", heading.level)?,
+    }
+    out.set_color(&bold)?;
+    writeln!(out, ": {}", heading.message)?;
+
+    let row = location.row + 1;
+    let gutter = " ".repeat(row.to_string().len());
+    out.set_color(&margin)?;
+    write!(out, "{}--> ", gutter)?;
+    out.reset()?;
+    writeln!(out, "{}", position(location))?;
+
+    if let Some(line) = line {
+        let (padding, width) = underline(line, location.column, len);
+        out.set_color(&margin)?;
+        writeln!(out, "{} |", gutter)?;
+        write!(out, "{} | ", row)?;
+        out.reset()?;
+        writeln!(out, "{}", line)?;
+        out.set_color(&margin)?;
+        write!(out, "{} | ", gutter)?;
+        out.set_color(&level)?;
+        writeln!(out, "{}{}", padding, "^".repeat(width))?;
+        if let Some(label) = label {
+            out.set_color(&margin)?;
+            writeln!(
+                out,
+                "{} | {}",
+                gutter,
+                label_line(line, location.row, label)
+            )?;
+        }
+    }
+
+    for text in notes {
+        out.set_color(&margin)?;
+        write!(out, "{} = ", gutter)?;
+        out.set_color(&note)?;
+        write!(out, "note")?;
+        out.reset()?;
+        writeln!(out, ": {}", text)?;
+    }
+    out.reset()
+}
+
+#[cfg(test)]
+mod tests {
+    use termcolor::{Ansi, NoColor};
+
+    use super::*;
+    use crate::tokenizer::Tokenizer;
+
+    #[test]
+    fn colored_diagnostics_match_rendered_ones() {
+        let source = "x = 1\ny = \"abc";
+        let error = Tokenizer::tokenize(source, (0, 0).into()).unwrap_err();
+        let mut plain = NoColor::new(Vec::new());
+        error.write_colored(source, &mut plain).unwrap();
+        assert_eq!(
+            String::from_utf8(plain.into_inner()).unwrap(),
+            error.render(source)
+        );
+
+        let mut colored = Ansi::new(Vec::new());
+        error.write_colored(source, &mut colored).unwrap();
+        let colored = String::from_utf8(colored.into_inner()).unwrap();
+        assert!(colored.starts_with("\x1b[0m\x1b[1m\x1b[31merror[T0001]"));
+        assert!(colored.contains("\x1b[31m    ^^^^\n"));
+
+        let tokens = Tokenizer::tokenize("f(", (0, 0).into()).unwrap();
+        let error = tokens[1].mismatch("identifier");
+        let mut plain = NoColor::new(Vec::new());
+        error.write_colored("f(", &mut plain).unwrap();
+        let plain = String::from_utf8(plain.into_inner()).unwrap();
+        assert!(plain.ends_with("  |  ^\n  = note: expected identifier\n"));
+
+        let warning = Warning {
+            message: "Careful".to_string(),
+            location: (1, 2).into(),
+        };
+        let mut plain = NoColor::new(Vec::new());
+        warning.write_colored("a\nbcd", &mut plain).unwrap();
+        assert_eq!(
+            String::from_utf8(plain.into_inner()).unwrap(),
+            "warning: Careful\n --> 2:3\n  |\n2 | bcd\n  |   ^\n"
+        );
+    }
+}
@@ -0,0 +1,84 @@
+use crate::{
+    config::TokenizerConfig,
+    error::Error,
+    location::Location,
+    span::Span,
+    token::{Token, TokenKindTag},
+    tokenizer::Tokenizer,
+};
+
+/// A token that only stores its kind and span, with the text recovered from the source
+/// on demand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CompactToken {
+    pub kind: TokenKindTag,
+    pub span: Span,
+}
+impl CompactToken {
+    /// Returns the token's text as it appears in the source, including any quotes.
+    pub fn text<'a>(&self, source: &'a str) -> &'a str {
+        &source[self.span.start..self.span.end]
+    }
+}
+
+impl From<&Token> for CompactToken {
+    fn from(token: &Token) -> Self {
+        Self {
+            kind: token.kind.tag(),
+            span: token.span,
+        }
+    }
+}
+
+/// Tokenizes the contents into compact tokens. Tokens are read one at a time, and each
+/// one's text is dropped as soon as it's compacted.
+pub fn tokenize_compact(
+    contents: &str,
+    location: Location,
+    config: &TokenizerConfig,
+) -> Result<Vec<CompactToken>, Error> {
+    let mut tokenizer = Tokenizer::with_config(config.clone());
+    tokenizer.reset(contents, location);
+
+    let mut tokens = Vec::new();
+    while let Some(token) = tokenizer.next_token() {
+        tokens.push(CompactToken::from(&token?));
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compact_tokens_recover_text() {
+        let source = "let s = \"hi\" # done";
+        let tokens = tokenize_compact(source, (0, 0).into(), &Default::default()).unwrap();
+        assert_eq!(tokens.len(), 5);
+        assert_eq!(tokens[0].kind, TokenKindTag::Identifier);
+        assert_eq!(tokens[0].text(source), "let");
+        assert_eq!(tokens[3].kind, TokenKindTag::StringLiteral);
+        assert_eq!(tokens[3].text(source), "\"hi\"");
+        assert_eq!(tokens[4].text(source), "# done");
+        assert_eq!(std::mem::size_of::<CompactToken>(), 24);
+    }
+
+    #[test]
+    fn compact_tokens_match_owned_tokens() {
+        let config = TokenizerConfig::default();
+        let source = "f(x, 2.5)\r\n\"a\\nb\" #| c |#\n$d";
+        let owned = Tokenizer::tokenize_with_config(source, (3, 1).into(), &config).unwrap();
+        let compact = tokenize_compact(source, (3, 1).into(), &config).unwrap();
+        assert_eq!(
+            compact,
+            owned.iter().map(CompactToken::from).collect::<Vec<_>>()
+        );
+
+        let error = Tokenizer::tokenize_with_config("a \"b", (0, 0).into(), &config).unwrap_err();
+        assert_eq!(
+            tokenize_compact("a \"b", (0, 0).into(), &config),
+            Err(error)
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use std::{ops::Deref, sync::Arc};
+
+use crate::{
+    class::CharTable,
+    config::{Backend, TokenizerConfig},
+    dfa::Dfa,
+};
+
+/// A config compiled into the tables the tokenizer reads it through: the classes of ASCII
+/// characters and, under `Backend::Dfa`, the automaton. Compiling once and passing the spec
+/// to `tokenize_with_spec` or `Tokenizer::with_spec` saves redoing that work for every
+/// input, which adds up when tokenizing many small inputs in the same language.
+///
+/// A spec can't be changed once compiled, and clones share its tables, so it's cheap to
+/// hand to other threads. It derefs to the config it was compiled from.
+#[derive(Debug, Clone)]
+pub struct CompiledSpec(Arc<Compiled>);
+
+#[derive(Debug)]
+struct Compiled {
+    config: TokenizerConfig,
+    table: CharTable,
+    dfa: Option<Dfa>,
+}
+
+impl TokenizerConfig {
+    /// Compiles the config into a spec to share between tokenizers.
+    pub fn compile(&self) -> CompiledSpec {
+        let table = CharTable::new(self);
+        let dfa = (self.backend == Backend::Dfa).then(|| Dfa::new(self, &table));
+        CompiledSpec(Arc::new(Compiled {
+            config: self.clone(),
+            table,
+            dfa,
+        }))
+    }
+}
+
+impl CompiledSpec {
+    pub fn config(&self) -> &TokenizerConfig {
+        &self.0.config
+    }
+
+    /// The classes of ASCII characters under the config.
+    pub fn table(&self) -> &CharTable {
+        &self.0.table
+    }
+
+    pub(crate) fn dfa(&self) -> Option<&Dfa> {
+        self.0.dfa.as_ref()
+    }
+}
+impl Deref for CompiledSpec {
+    type Target = TokenizerConfig;
+
+    fn deref(&self) -> &TokenizerConfig {
+        self.config()
+    }
+}
+impl Default for CompiledSpec {
+    fn default() -> Self {
+        TokenizerConfig::default().compile()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::{tokenize_with_config, tokenize_with_spec, Tokenizer};
+
+    #[test]
+    fn compiled_specs_are_shared_between_inputs() {
+        let config = TokenizerConfig {
+            backend: Backend::Dfa,
+            ..Default::default()
+        };
+        let spec = config.compile();
+        assert_eq!(spec.backend, Backend::Dfa);
+        assert!(spec.dfa().is_some());
+
+        let inputs = ["a = 1", "f(x, \"y\") # z", "-2.5..b"];
+        let threads: Vec<_> = inputs
+            .into_iter()
+            .map(|input| {
+                let spec = spec.clone();
+                std::thread::spawn(move || tokenize_with_spec(input, (0, 0).into(), &spec))
+            })
+            .collect();
+        for (input, thread) in inputs.into_iter().zip(threads) {
+            let expected = tokenize_with_config(input, (0, 0).into(), &config);
+            assert_eq!(thread.join().unwrap(), expected);
+        }
+
+        let mut tokenizer = Tokenizer::with_spec(spec);
+        for input in inputs {
+            tokenizer.reset(input, (0, 0).into());
+            let expected = tokenize_with_config(input, (0, 0).into(), &config).unwrap();
+            assert_eq!(tokenizer.tokens().unwrap(), expected);
+        }
+    }
+}
@@ -0,0 +1,536 @@
+use crate::token::Token;
+
+/// Options that control how source text is split into tokens.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct TokenizerConfig {
+    /// The rules used to decide where identifiers start and end.
+    pub identifiers: IdentifierRules,
+    /// What to do with a character that can't begin any token.
+    pub unexpected_characters: UnexpectedCharacters,
+    /// Which characters are treated as digits in numeric literals.
+    pub digits: Digits,
+    /// When a `-` before a digit is the sign of a number rather than a symbol.
+    pub signs: Signs,
+    /// Whether `+` can be the sign of a number too, under the same `signs` policy, so
+    /// `+42` is read as a single token.
+    pub plus_signs: bool,
+    /// When a `.` before digits starts a float, as in `.5`.
+    pub leading_dots: LeadingDots,
+    /// Whether a `.` after digits with no digits following is part of the number, as in
+    /// `5.`. When false, `5.max` is read as `5`, `.` and `max`.
+    pub trailing_dots: bool,
+    /// Whether `inf` and `nan`, in any case, are read as float literals rather than
+    /// identifiers. A sign before them is read under the `signs` policy, as with numbers.
+    pub special_floats: bool,
+    /// How numbers with a fractional part are read.
+    pub fractions: Fractions,
+    /// Suffixes read as the unit of a number directly before them, such as `ms` in
+    /// `250ms`, producing a `UnitLiteral`. The longest matching unit is used, and only if
+    /// no identifier characters follow it.
+    pub units: Vec<String>,
+    /// Whether a number directly followed by `%`, such as `12.5%`, is read as a
+    /// `PercentLiteral`. `10%3` is then read as `10%` and `3`, so modulo needs spaces.
+    pub percents: bool,
+    /// Whether ISO 8601 dates, date-times and times, such as `2024-05-01T12:30:00Z`, are
+    /// read as a single `DateTimeLiteral` rather than numbers and symbols.
+    pub datetimes: bool,
+    /// Whether a `#` followed by 3, 4, 6 or 8 hex digits, such as `#fff` or `#aabbcc`, is
+    /// read as a `ColorLiteral` instead of starting a comment. Comments that start with a
+    /// word like `#add` need a space after the `#`.
+    pub colors: bool,
+    /// Whether a comment that starts with `##` is a `CommentStyle::Doc` comment rather than
+    /// a line comment whose text starts with `#`.
+    pub doc_comments: bool,
+    /// Whether `#|` starts a `CommentStyle::Block` comment that runs to `|#`, and whether
+    /// such comments nest.
+    pub block_comments: BlockComments,
+    /// What to do with a block comment that's still open at the end of the input.
+    pub unclosed_block_comments: UnclosedBlockComments,
+    /// Which attribute forms, `@name` or `#[...]`, are read as a single `Attribute`.
+    pub attributes: Attributes,
+    /// Characters that mark a directly following identifier as a `Variable`, such as `$`
+    /// in `$foo`.
+    pub sigils: Vec<char>,
+    /// Separators that join the identifiers around them into a single `Identifier`, such
+    /// as `::` in `foo::bar::baz`, so qualified names keep one location and span.
+    pub path_separators: Vec<String>,
+    /// Opening and closing delimiters around identifiers that can contain any character,
+    /// such as `` ` `` or `[` and `]`. The token's contents are the name without them, and
+    /// a doubled closing delimiter stands for itself, as in SQL. Including `"` reads
+    /// double-quoted text as identifiers rather than strings. Quoted names aren't
+    /// normalized or case folded.
+    pub identifier_quotes: Vec<(char, char)>,
+    /// Characters that start a `Directive` when they begin a line and are followed by a
+    /// name, as in `#include <stdio.h>` or `%pragma once`. A `#` that isn't followed by a
+    /// name still starts a comment.
+    pub directives: Vec<char>,
+    /// The name of a directive, such as `line`, that sets the row and optionally the file
+    /// of the lines after it, as in `#line 42 "orig.file"`. Its character must be in
+    /// `directives`.
+    pub line_directive: Option<String>,
+    /// Which characters separate tokens.
+    pub whitespace: Whitespace,
+    /// Characters treated as whitespace in addition to `whitespace`, such as `,` for
+    /// Clojure-style lists.
+    pub additional_whitespace: Vec<char>,
+    /// What to do with whitespace characters outside of ASCII, such as a non-breaking space.
+    pub unusual_whitespace: UnusualWhitespace,
+    /// Whether and where Unicode NFC normalization is applied.
+    pub normalization: Normalization,
+    /// The case identifier payloads are folded to, leaving `Token::contents` as written.
+    pub identifier_case: IdentifierCase,
+    /// What to do with bidirectional control characters, which can make the rendered
+    /// source differ from what is tokenized.
+    pub bidi_controls: BidiControls,
+    /// What to do with control and zero-width characters outside of strings and comments.
+    pub invisible_characters: InvisibleCharacters,
+    /// What to do with curly double quotes (`“` and `”`).
+    pub smart_quotes: SmartQuotes,
+    /// What to do with line breaks inside strings.
+    pub string_newlines: StringNewlines,
+    /// Lexemes that produce a warning when they appear as an identifier or symbol.
+    pub deprecated: Vec<Deprecated>,
+    /// The maximum number of characters to read before giving up with
+    /// `ErrorKind::OutOfFuel`, bounding the time spent on adversarial input.
+    pub fuel: Option<usize>,
+    /// How deep nested block comments, included files and the groups built by
+    /// `tokens_to_token_stream_with_config` may nest before `ErrorKind::TooDeep`, bounding
+    /// the memory and stack used on adversarial input.
+    pub max_depth: Option<usize>,
+    /// How tokens are scanned. Both backends read the same tokens.
+    pub backend: Backend,
+}
+impl TokenizerConfig {
+    /// The default config, usable in constants.
+    pub const DEFAULT: Self = Self {
+        identifiers: IdentifierRules::Permissive,
+        unexpected_characters: UnexpectedCharacters::Error,
+        digits: Digits::Ascii,
+        signs: Signs::BeforeDigit,
+        plus_signs: false,
+        leading_dots: LeadingDots::Always,
+        trailing_dots: true,
+        special_floats: false,
+        fractions: Fractions::Float,
+        units: Vec::new(),
+        percents: false,
+        datetimes: false,
+        colors: false,
+        doc_comments: false,
+        block_comments: BlockComments::None,
+        unclosed_block_comments: UnclosedBlockComments::Error,
+        attributes: Attributes::None,
+        sigils: Vec::new(),
+        path_separators: Vec::new(),
+        identifier_quotes: Vec::new(),
+        directives: Vec::new(),
+        line_directive: None,
+        whitespace: Whitespace::Unicode,
+        additional_whitespace: Vec::new(),
+        unusual_whitespace: UnusualWhitespace::Allow,
+        normalization: Normalization::None,
+        identifier_case: IdentifierCase::AsWritten,
+        bidi_controls: BidiControls::Allow,
+        invisible_characters: InvisibleCharacters::Allow,
+        smart_quotes: SmartQuotes::Allow,
+        string_newlines: StringNewlines::Allow,
+        deprecated: Vec::new(),
+        fuel: None,
+        max_depth: None,
+        backend: Backend::DecisionTree,
+    };
+
+    /// The default config, but stopping with an error on input that's likely a mistake or
+    /// an attack: unexpected, invisible and bidirectional control characters, unusual
+    /// whitespace, curly quotes and nesting deeper than 256.
+    pub const fn strict() -> Self {
+        let mut config = Self::DEFAULT;
+        config.unexpected_characters = UnexpectedCharacters::Error;
+        config.unusual_whitespace = UnusualWhitespace::Error;
+        config.bidi_controls = BidiControls::Error;
+        config.invisible_characters = InvisibleCharacters::Error;
+        config.smart_quotes = SmartQuotes::Error;
+        config.max_depth = Some(256);
+        config
+    }
+
+    /// The default config, but reading whatever it can and warning rather than stopping:
+    /// unexpected and invisible characters become `TokenKind::Unknown` tokens, unusual
+    /// whitespace and bidirectional control characters are warned about and curly quotes
+    /// delimit strings. Use it with `Tokenizer::tokenize_recovering` to recover from
+    /// unclosed strings too.
+    pub const fn lenient() -> Self {
+        let mut config = Self::DEFAULT;
+        config.unexpected_characters = UnexpectedCharacters::Token;
+        config.unusual_whitespace = UnusualWhitespace::Warn;
+        config.bidi_controls = BidiControls::Warn;
+        config.invisible_characters = InvisibleCharacters::Token;
+        config.smart_quotes = SmartQuotes::Delimit;
+        config
+    }
+
+    /// Returns an error describing the first pair of options that contradict each other,
+    /// where one would silently have no effect.
+    pub const fn validate(&self) -> Result<(), &'static str> {
+        if matches!(self.whitespace, Whitespace::Ascii)
+            && !matches!(self.unusual_whitespace, UnusualWhitespace::Allow)
+        {
+            return Err("unusual_whitespace has no effect when whitespace is Ascii");
+        }
+        if matches!(self.identifiers, IdentifierRules::Ascii)
+            && matches!(self.normalization, Normalization::Identifiers)
+        {
+            return Err("Identifiers normalization has no effect when identifiers are Ascii");
+        }
+        if matches!(self.block_comments, BlockComments::None)
+            && !matches!(self.unclosed_block_comments, UnclosedBlockComments::Error)
+        {
+            return Err("unclosed_block_comments has no effect when block_comments is None");
+        }
+        if self.plus_signs && matches!(self.signs, Signs::Operator) {
+            return Err("plus_signs has no effect when signs is Operator");
+        }
+        if self.line_directive.is_some() && self.directives.is_empty() {
+            return Err("line_directive has no effect when directives is empty");
+        }
+        if matches!(self.fuel, Some(0)) {
+            return Err("fuel must be greater than zero");
+        }
+        if matches!(self.max_depth, Some(0)) {
+            return Err("max_depth must be greater than zero");
+        }
+        Ok(())
+    }
+}
+impl Default for TokenizerConfig {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// The rules used to decide where identifiers start and end.
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum IdentifierRules {
+    /// Anything that isn't whitespace or a symbol is part of an identifier.
+    #[default]
+    Permissive,
+    /// Identifiers follow UAX #31: they start with an `XID_Start` character or `_`
+    /// and continue with `XID_Continue` characters.
+    UnicodeXid,
+    /// Identifiers must match `[A-Za-z_][A-Za-z0-9_]*`.
+    Ascii,
+    /// Identifiers start with a character `start` accepts and continue with characters
+    /// `continuation` accepts. Symbols never start an identifier but can continue one, so
+    /// a `continuation` accepting `-` allows `kebab-case`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom {
+        start: fn(char) -> bool,
+        continuation: fn(char) -> bool,
+    },
+}
+/// Custom predicates are compared by address.
+impl PartialEq for IdentifierRules {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                IdentifierRules::Custom {
+                    start,
+                    continuation,
+                },
+                IdentifierRules::Custom {
+                    start: other_start,
+                    continuation: other_continuation,
+                },
+            ) => {
+                std::ptr::fn_addr_eq(*start, *other_start)
+                    && std::ptr::fn_addr_eq(*continuation, *other_continuation)
+            }
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+impl Eq for IdentifierRules {}
+
+/// What to do with a character that can't begin any token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum UnexpectedCharacters {
+    /// Stop tokenizing and return an error.
+    #[default]
+    Error,
+    /// Emit the character as a `TokenKind::Unknown` token and keep going.
+    Token,
+}
+
+/// Which characters are treated as digits in numeric literals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Digits {
+    /// Only `0` through `9`.
+    #[default]
+    Ascii,
+    /// ASCII digits plus the decimal digits of common scripts (Arabic-Indic,
+    /// Devanagari, fullwidth, etc.), which are mapped to their ASCII values.
+    Unicode,
+}
+
+/// When a `-` before a digit is the sign of a number rather than a symbol.
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Signs {
+    /// Always a sign, so `a-1` is read as `a` and `-1`.
+    #[default]
+    BeforeDigit,
+    /// Never a sign, so `-1` is read as `-` and `1`.
+    Operator,
+    /// A sign when the function returns true for the previous token, which is `None` at
+    /// the start of the input.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Context(fn(Option<&Token>) -> bool),
+}
+/// Context functions are compared by address.
+impl PartialEq for Signs {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Signs::Context(a), Signs::Context(b)) => std::ptr::fn_addr_eq(*a, *b),
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+impl Eq for Signs {}
+impl Signs {
+    /// Returns true where an operand is expected in an expression: at the start of the
+    /// input or after a symbol other than a closing bracket. Use it as
+    /// `Signs::Context(Signs::unary_position)` so `a-1` is read as `a`, `-` and `1` while
+    /// `(-1)` keeps its negative literal.
+    pub fn unary_position(previous: Option<&Token>) -> bool {
+        match previous {
+            None => true,
+            Some(token) => {
+                token.kind.is_symbol() && !matches!(token.contents.as_str(), ")" | "]" | "}")
+            }
+        }
+    }
+}
+
+/// When a `.` before digits starts a float, as in `.5`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum LeadingDots {
+    /// Always a float, so `x.5` is read as `x` and `.5`.
+    #[default]
+    Always,
+    /// A float unless the `.` directly follows an identifier, literal or closing bracket,
+    /// where it's member access: `x.5` is read as `x`, `.` and `5`. A number directly after
+    /// such a `.` has no fractional part, so `t.0.1` is `t`, `.`, `0`, `.` and `1`.
+    NotAfterOperand,
+    /// Never a float, so `.5` is read as `.` and `5`.
+    Never,
+}
+
+/// How numbers with a fractional part, such as `19.99`, are read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Fractions {
+    /// As a `FloatLiteral`, which may round the value. The exact text is still in
+    /// `Token::contents`.
+    #[default]
+    Float,
+    /// As a `DecimalLiteral` holding the exact digits, for values such as money where
+    /// rounding isn't acceptable.
+    Decimal,
+}
+
+/// Which attribute forms are read as a single `Attribute` token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Attributes {
+    /// Neither, so `@name` is read as the symbol `@` and an identifier.
+    #[default]
+    None,
+    /// `@` followed by an identifier, as in `@deprecated`.
+    At,
+    /// `#[` up to its matching `]` on the same line, as in `#[derive(Debug)]`. Without a
+    /// matching `]` the `#` starts a comment as usual.
+    HashBracket,
+    /// Both `@name` and `#[...]`.
+    All,
+}
+
+/// Which characters separate tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Whitespace {
+    /// Anything `char::is_whitespace` accepts.
+    #[default]
+    Unicode,
+    /// Only ASCII whitespace: space, tab, newline, form feed and carriage return.
+    Ascii,
+}
+
+/// What to do with whitespace characters outside of ASCII.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum UnusualWhitespace {
+    /// Treat them like any other whitespace.
+    #[default]
+    Allow,
+    /// Keep tokenizing but report each one as a warning.
+    Warn,
+    /// Stop tokenizing and return an error.
+    Error,
+}
+
+/// Whether and where Unicode NFC normalization is applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Normalization {
+    /// Leave the input as written.
+    #[default]
+    None,
+    /// Normalize identifier payloads, leaving `Token::contents` as written.
+    Identifiers,
+    /// Normalize the whole input before tokenizing. Locations refer to the normalized text.
+    Input,
+}
+
+/// The case identifier payloads are folded to, for case-insensitive languages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum IdentifierCase {
+    /// Keep the case as written.
+    #[default]
+    AsWritten,
+    /// Fold to lowercase, so `Foo` and `FOO` are both `foo`.
+    Lower,
+    /// Fold to uppercase, so `Foo` and `foo` are both `FOO`.
+    Upper,
+}
+
+/// What to do with bidirectional control characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum BidiControls {
+    /// Treat them like any other character.
+    #[default]
+    Allow,
+    /// Keep tokenizing but report each one as a warning.
+    Warn,
+    /// Stop tokenizing and return an error.
+    Error,
+}
+
+/// What to do with control and zero-width characters outside of strings and comments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum InvisibleCharacters {
+    /// Treat them like any other character, usually making them part of an identifier.
+    #[default]
+    Allow,
+    /// Stop tokenizing and return an error.
+    Error,
+    /// Drop them from the token stream.
+    Skip,
+    /// Emit each one as a `TokenKind::Unknown` token.
+    Token,
+}
+
+/// What to do with curly double quotes (`“` and `”`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum SmartQuotes {
+    /// Treat them like any other character, usually making them part of an identifier.
+    #[default]
+    Allow,
+    /// Treat them as string delimiters: a string opened by a curly quote is closed by `”`.
+    Delimit,
+    /// Stop tokenizing and return an error suggesting a straight quote.
+    Error,
+}
+
+/// Whether `#| ... |#` block comments are read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum BlockComments {
+    /// `#|` starts a line comment like any other `#`.
+    #[default]
+    None,
+    /// A block comment ends at the first `|#`.
+    Flat,
+    /// A `#|` inside a block comment opens a nested one, which must close before the outer
+    /// comment does, so code that already has comments can be commented out.
+    Nested,
+}
+
+/// What to do with a block comment that's still open at the end of the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum UnclosedBlockComments {
+    /// Stop tokenizing and return an error pointing at the comment's `#|`.
+    #[default]
+    Error,
+    /// Warn at the comment's `#|` and read the rest of the input as the comment.
+    Warn,
+    /// Emit the rest of the input as a `TokenKind::Unknown` token and keep going, with the
+    /// error collected by `Tokenizer::tokenize_recovering`.
+    Recover,
+}
+
+/// What to do with line breaks inside strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum StringNewlines {
+    /// Keep them in the string, which then spans lines.
+    #[default]
+    Allow,
+    /// Stop tokenizing and return an error, so a string missing its closing quote fails on
+    /// the line it opens on rather than swallowing the lines after it.
+    Error,
+}
+
+/// How tokens are scanned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Backend {
+    /// Work out each token from its first character and the ones after it.
+    #[default]
+    DecisionTree,
+    /// Compile the rules into a table-driven automaton over byte classes when the tokenizer
+    /// is created, and read each token with a lookup per byte, which is faster on mostly
+    /// ASCII input. Tokens the automaton can't decide on its own, such as those with
+    /// non-ASCII text or the forms added by options like `units` or `colors`, are read by
+    /// the decision tree, so any config can use it.
+    Dfa,
+}
+
+/// A lexeme that produces a warning when it appears as an identifier or symbol.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Deprecated {
+    /// The exact text of the token, such as `<>`.
+    pub lexeme: String,
+    /// The warning message to report.
+    pub message: String,
+}
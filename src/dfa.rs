@@ -0,0 +1,487 @@
+use crate::{
+    class::{CharClass, CharTable},
+    config::{
+        Attributes, BlockComments, Digits, IdentifierRules, LeadingDots, Signs, StringNewlines,
+        TokenizerConfig,
+    },
+};
+
+/// What a run of bytes accepted by the automaton is read as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Rule {
+    Whitespace,
+    Comment,
+    String,
+    Identifier,
+    Integer,
+    Float,
+    Symbol,
+}
+
+/// The states of the automaton. `Dead` ends the token at the last accepting state and
+/// `Bail` gives up on it, leaving it to the decision tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+enum State {
+    Start,
+    Dead,
+    Bail,
+    Whitespace,
+    Comment,
+    String,
+    StringEscape,
+    StringEnd,
+    Identifier,
+    Integer,
+    /// Digits and a `.`, which is only part of the number under some configs.
+    IntegerDot,
+    Fraction,
+    /// A fraction and another `.`, which is either a range or an error.
+    FractionDot,
+    Minus,
+    Plus,
+    Dot,
+    DotDot,
+    /// A symbol that may be followed by `=`, such as `<` or `!`.
+    Comparison,
+    Equals,
+    SymbolEnd,
+}
+impl State {
+    const ALL: [Self; 20] = [
+        Self::Start,
+        Self::Dead,
+        Self::Bail,
+        Self::Whitespace,
+        Self::Comment,
+        Self::String,
+        Self::StringEscape,
+        Self::StringEnd,
+        Self::Identifier,
+        Self::Integer,
+        Self::IntegerDot,
+        Self::Fraction,
+        Self::FractionDot,
+        Self::Minus,
+        Self::Plus,
+        Self::Dot,
+        Self::DotDot,
+        Self::Comparison,
+        Self::Equals,
+        Self::SymbolEnd,
+    ];
+
+    /// Returns what the bytes read so far are read as if the token ends here.
+    fn rule(self, config: &TokenizerConfig) -> Option<Rule> {
+        match self {
+            Self::Whitespace => Some(Rule::Whitespace),
+            Self::Comment => Some(Rule::Comment),
+            Self::StringEnd => Some(Rule::String),
+            Self::Identifier => Some(Rule::Identifier),
+            Self::Integer => Some(Rule::Integer),
+            Self::IntegerDot if config.trailing_dots => Some(Rule::Float),
+            Self::Fraction => Some(Rule::Float),
+            Self::Minus
+            | Self::Plus
+            | Self::Dot
+            | Self::DotDot
+            | Self::Comparison
+            | Self::Equals
+            | Self::SymbolEnd => Some(Rule::Symbol),
+            _ => None,
+        }
+    }
+}
+
+/// The input after the last byte, given its own column in the transition table.
+const END: usize = 256;
+
+/// The lexical rules of a config compiled into a deterministic automaton over byte classes,
+/// which reads the common tokens (ASCII identifiers, decimal numbers, symbols, strings,
+/// line comments and whitespace) with a table lookup per byte.
+///
+/// Anything the config reads with more context than the automaton has, such as non-ASCII
+/// text, ranges after numbers or the options that add token forms, makes `scan` return
+/// `None` so the decision tree reads that token instead. Both read the same tokens.
+#[derive(Debug, Clone)]
+pub(crate) struct Dfa {
+    /// The class of each byte, and of the end of the input at `END`.
+    classes: [u8; 257],
+    class_count: usize,
+    /// The next state for each state and class, indexed by `state * class_count + class`.
+    transitions: Box<[State]>,
+    /// What each state accepts, indexed by state.
+    rules: [Option<Rule>; State::ALL.len()],
+}
+impl Dfa {
+    pub(crate) fn new(config: &TokenizerConfig, table: &CharTable) -> Self {
+        let rules = Rules::new(config, table);
+
+        // Bytes that lead to the same state from every state share a class.
+        let mut columns: Vec<[State; State::ALL.len()]> = Vec::new();
+        let mut classes = [0; 257];
+        for (input, class) in classes.iter_mut().enumerate() {
+            let byte = (input != END).then_some(input as u8);
+            let column = State::ALL.map(|state| rules.next(state, byte));
+            *class = match columns.iter().position(|existing| *existing == column) {
+                Some(i) => i as u8,
+                None => {
+                    columns.push(column);
+                    (columns.len() - 1) as u8
+                }
+            };
+        }
+
+        let class_count = columns.len();
+        let mut transitions = vec![State::Dead; State::ALL.len() * class_count];
+        for (class, column) in columns.iter().enumerate() {
+            for (state, next) in column.iter().enumerate() {
+                transitions[state * class_count + class] = *next;
+            }
+        }
+
+        Self {
+            classes,
+            class_count,
+            transitions: transitions.into(),
+            rules: State::ALL.map(|state| state.rule(config)),
+        }
+    }
+
+    /// Returns the rule and length in bytes of the longest token at the start of `bytes`,
+    /// or `None` if the decision tree has to read it.
+    pub(crate) fn scan(&self, bytes: &[u8]) -> Option<(Rule, usize)> {
+        let mut state = State::Start;
+        let mut accepted = None;
+        for (i, byte) in bytes.iter().enumerate() {
+            state = self.next(state, *byte as usize);
+            match state {
+                State::Dead => return accepted,
+                State::Bail => return None,
+                _ => {
+                    if let Some(rule) = self.rules[state as usize] {
+                        accepted = Some((rule, i + 1));
+                    }
+                }
+            }
+        }
+
+        match self.next(state, END) {
+            State::Bail => None,
+            _ => accepted,
+        }
+    }
+
+    fn next(&self, state: State, input: usize) -> State {
+        self.transitions[state as usize * self.class_count + self.classes[input] as usize]
+    }
+}
+
+/// The transitions of the automaton, worked out from the config one byte at a time while
+/// compiling it.
+struct Rules<'a> {
+    config: &'a TokenizerConfig,
+    table: &'a CharTable,
+    /// Whether `#` starts a line comment and nothing else.
+    comments: bool,
+    /// Whether numbers are plain decimal literals, with nothing read around them.
+    numbers: bool,
+    identifiers: bool,
+}
+impl<'a> Rules<'a> {
+    fn new(config: &'a TokenizerConfig, table: &'a CharTable) -> Self {
+        Self {
+            config,
+            table,
+            comments: !config.colors
+                && !config.doc_comments
+                && config.block_comments == BlockComments::None
+                && !matches!(config.attributes, Attributes::HashBracket | Attributes::All),
+            numbers: !config.datetimes
+                && !config.percents
+                && config.units.is_empty()
+                && config.leading_dots != LeadingDots::NotAfterOperand,
+            identifiers: config.path_separators.is_empty(),
+        }
+    }
+
+    /// Returns the state after reading `byte`, or the end of the input if it's `None`.
+    fn next(&self, state: State, byte: Option<u8>) -> State {
+        let Some(byte) = byte else {
+            return match state {
+                State::FractionDot if self.config.trailing_dots => State::Bail,
+                _ => State::Dead,
+            };
+        };
+        let c = byte as char;
+        let is_digit = byte.is_ascii_digit();
+
+        match state {
+            State::Start => self.start(byte),
+            State::Dead | State::Bail => state,
+            State::Whitespace if self.is_whitespace(byte) => State::Whitespace,
+            State::Comment if byte != b'\n' => State::Comment,
+            State::String => match byte {
+                b'"' => State::StringEnd,
+                b'\\' => State::StringEscape,
+                b'\n' if self.config.string_newlines == StringNewlines::Error => State::Bail,
+                _ => State::String,
+            },
+            State::StringEscape => match byte {
+                b'\n' if self.config.string_newlines == StringNewlines::Error => State::Bail,
+                _ => State::String,
+            },
+            State::Identifier if !c.is_ascii() => match self.config.identifiers {
+                IdentifierRules::Ascii => State::Dead,
+                _ => State::Bail,
+            },
+            State::Identifier if self.class(byte).contains(CharClass::IDENTIFIER_CONTINUE) => {
+                State::Identifier
+            }
+            State::Integer
+            | State::IntegerDot
+            | State::Fraction
+            | State::FractionDot
+            | State::Minus
+            | State::Plus
+            | State::Dot
+                if !c.is_ascii() && self.config.digits == Digits::Unicode =>
+            {
+                State::Bail
+            }
+            State::Integer if is_digit => State::Integer,
+            State::Integer if byte == b'.' => State::IntegerDot,
+            State::IntegerDot if is_digit => State::Fraction,
+            // `1..2` is a range and `1.2.3` an error, both left to the decision tree.
+            State::IntegerDot if byte == b'.' && self.config.trailing_dots => State::Bail,
+            State::Fraction if is_digit => State::Fraction,
+            State::Fraction if byte == b'.' => State::FractionDot,
+            State::FractionDot => match (byte, self.config.trailing_dots) {
+                (b'.', _) => State::Dead,
+                (_, true) => State::Bail,
+                (_, false) if is_digit => State::Bail,
+                (_, false) => State::Dead,
+            },
+            State::Minus | State::Plus if is_digit => self.sign(state),
+            State::Minus | State::Plus
+                if self.config.special_floats && matches!(byte, b'i' | b'I' | b'n' | b'N') =>
+            {
+                State::Bail
+            }
+            State::Minus if matches!(byte, b'=' | b'>') => State::SymbolEnd,
+            State::Plus | State::Comparison if byte == b'=' => State::SymbolEnd,
+            State::Equals if matches!(byte, b'=' | b'>') => State::SymbolEnd,
+            State::Dot if byte == b'.' => State::DotDot,
+            State::Dot if is_digit => match self.config.leading_dots {
+                LeadingDots::Never => State::Dead,
+                LeadingDots::Always | LeadingDots::NotAfterOperand => State::Bail,
+            },
+            State::DotDot if byte == b'=' => State::SymbolEnd,
+            _ => State::Dead,
+        }
+    }
+
+    /// Returns the state a token starting with `byte` begins in, checking the forms in the
+    /// order the decision tree does.
+    fn start(&self, byte: u8) -> State {
+        let c = byte as char;
+        let class = self.class(byte);
+        let config = self.config;
+        if !c.is_ascii()
+            || config.directives.contains(&c)
+            || config.identifier_quotes.iter().any(|(open, _)| *open == c)
+        {
+            State::Bail
+        } else if class.contains(CharClass::COMMENT_START) {
+            match self.comments {
+                true => State::Comment,
+                false => State::Bail,
+            }
+        } else if class.contains(CharClass::QUOTE) {
+            State::String
+        } else if class.contains(CharClass::WHITESPACE) {
+            match self.is_whitespace(byte) {
+                true => State::Whitespace,
+                // Unusual whitespace is warned about or rejected under some configs.
+                false => State::Bail,
+            }
+        } else if class.contains(CharClass::INVISIBLE)
+            || config.sigils.contains(&c)
+            || (c == '@' && matches!(config.attributes, Attributes::At | Attributes::All))
+            || (config.special_floats && matches!(c, 'i' | 'I' | 'n' | 'N'))
+        {
+            State::Bail
+        } else if c.is_ascii_digit() {
+            match self.numbers {
+                true => State::Integer,
+                false => State::Bail,
+            }
+        } else if c == '-' {
+            State::Minus
+        } else if c == '+' {
+            State::Plus
+        } else if c == '.' {
+            State::Dot
+        } else if c == '=' {
+            State::Equals
+        } else if matches!(c, '!' | '<' | '>' | '*' | '/') {
+            State::Comparison
+        } else if class.contains(CharClass::SYMBOL) {
+            State::SymbolEnd
+        } else if self.identifiers
+            && class.contains(CharClass::IDENTIFIER_START)
+            && class.contains(CharClass::IDENTIFIER_CONTINUE)
+        {
+            State::Identifier
+        } else {
+            State::Bail
+        }
+    }
+
+    /// Returns the state after a digit that follows a `-` or `+`.
+    fn sign(&self, state: State) -> State {
+        if state == State::Plus && !self.config.plus_signs {
+            return State::Dead;
+        }
+        match self.config.signs {
+            Signs::BeforeDigit if self.numbers => State::Integer,
+            Signs::Operator => State::Dead,
+            Signs::BeforeDigit | Signs::Context(_) => State::Bail,
+        }
+    }
+
+    fn is_whitespace(&self, byte: u8) -> bool {
+        self.class(byte).contains(CharClass::WHITESPACE)
+            && (byte.is_ascii_whitespace()
+                || self.config.additional_whitespace.contains(&(byte as char)))
+    }
+
+    fn class(&self, byte: u8) -> CharClass {
+        self.table.get(byte as char).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{Backend, Deprecated, Fractions, InvisibleCharacters, Whitespace},
+        tokenizer::Tokenizer,
+    };
+
+    #[test]
+    fn bytes_are_grouped_into_classes() {
+        let config = TokenizerConfig::default();
+        let dfa = Dfa::new(&config, &CharTable::new(&config));
+        assert!(dfa.class_count < 32);
+        assert_eq!(dfa.classes[b'a' as usize], dfa.classes[b'z' as usize]);
+        assert_eq!(dfa.classes[b'1' as usize], dfa.classes[b'9' as usize]);
+
+        assert_eq!(dfa.scan(b"foo bar"), Some((Rule::Identifier, 3)));
+        assert_eq!(dfa.scan(b"-12.5+"), Some((Rule::Float, 5)));
+        assert_eq!(dfa.scan(b"..=x"), Some((Rule::Symbol, 3)));
+        assert_eq!(dfa.scan(b"\"a\\\"b\" c"), Some((Rule::String, 6)));
+        assert_eq!(dfa.scan(b"# note\nx"), Some((Rule::Comment, 6)));
+        assert_eq!(dfa.scan(b" \t\nx"), Some((Rule::Whitespace, 3)));
+        // Ranges, unclosed strings and non-ASCII text are left to the decision tree.
+        assert_eq!(dfa.scan(b"1..2"), None);
+        assert_eq!(dfa.scan(b"\"abc"), None);
+        assert_eq!(dfa.scan("héllo".as_bytes()), None);
+    }
+
+    #[test]
+    fn tokens_match_the_decision_tree() {
+        let sources = [
+            "x = 1\ny = -2.5 # note\nz = \"a\\\"b\\n\" + 3.",
+            "a..b 1..2 1.2..3 1.2.3 5.max .5 x.5 t.0.1 a-1 (-1) +1 a+=2",
+            "f(x, y) -> z => w == v != u >= t <= s ..= r *= q /= p",
+            "é = \"ü\" # ñ\n名前 ٣ ١٢ 1٢",
+            "\"unclosed\nnext line",
+            "\"line\nbreak\" x",
+            "99999999999999999999999999999999999999999 1",
+            "`quoted` $var @attr #[derive(x)] #fff ## doc\n#include <x>",
+            "inf -inf nan_ish 10ms 50% 2024-05-01 a::b::c <> x",
+            "a\u{0B}b\u{A0}c\u{200B}d \u{202E}e \u{201C}q\u{201D}",
+            "a,b, c ; d",
+            "",
+        ];
+        let configs = [
+            TokenizerConfig::default(),
+            TokenizerConfig::strict(),
+            TokenizerConfig::lenient(),
+            TokenizerConfig {
+                identifiers: IdentifierRules::Ascii,
+                unexpected_characters: crate::config::UnexpectedCharacters::Token,
+                trailing_dots: false,
+                leading_dots: LeadingDots::Never,
+                signs: Signs::Operator,
+                plus_signs: true,
+                fractions: Fractions::Decimal,
+                string_newlines: StringNewlines::Error,
+                ..Default::default()
+            },
+            TokenizerConfig {
+                identifiers: IdentifierRules::UnicodeXid,
+                unexpected_characters: crate::config::UnexpectedCharacters::Token,
+                digits: Digits::Unicode,
+                leading_dots: LeadingDots::NotAfterOperand,
+                signs: Signs::Context(Signs::unary_position),
+                plus_signs: true,
+                ..Default::default()
+            },
+            TokenizerConfig {
+                identifier_quotes: vec![('`', '`')],
+                sigils: vec!['$'],
+                attributes: Attributes::All,
+                colors: true,
+                doc_comments: true,
+                directives: vec!['#'],
+                special_floats: true,
+                units: vec!["ms".to_string()],
+                percents: true,
+                datetimes: true,
+                path_separators: vec!["::".to_string()],
+                deprecated: vec![Deprecated {
+                    lexeme: "<>".to_string(),
+                    message: "Use != instead".to_string(),
+                }],
+                ..Default::default()
+            },
+            TokenizerConfig {
+                whitespace: Whitespace::Ascii,
+                additional_whitespace: vec![','],
+                invisible_characters: InvisibleCharacters::Skip,
+                block_comments: BlockComments::Nested,
+                ..Default::default()
+            },
+            TokenizerConfig {
+                fuel: Some(7),
+                ..Default::default()
+            },
+        ];
+
+        for config in configs {
+            let dfa_config = TokenizerConfig {
+                backend: Backend::Dfa,
+                ..config.clone()
+            };
+            for source in sources {
+                let location = (0, 0).into();
+                assert_eq!(
+                    Tokenizer::tokenize_with_warnings(source, location, &dfa_config),
+                    Tokenizer::tokenize_with_warnings(source, (0, 0).into(), &config),
+                    "{:?} with {:?}",
+                    source,
+                    config,
+                );
+                assert_eq!(
+                    Tokenizer::tokenize_recovering(source, (0, 0).into(), &dfa_config),
+                    Tokenizer::tokenize_recovering(source, (0, 0).into(), &config),
+                    "{:?} with {:?}",
+                    source,
+                    config,
+                );
+            }
+        }
+    }
+}
@@ -1,7 +1,587 @@
-use crate::location::Location;
+use std::{collections::HashMap, fmt::Display, ops::Range};
+
+use crate::{
+    location::Location,
+    span::Span,
+    token::{Token, TokenKind},
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Error {
+    pub kind: ErrorKind,
     pub message: String,
+    /// Where the problem starts.
     pub location: Location,
+    /// The byte range of the problem in the source, such as the whole of an unclosed
+    /// string. Empty when only the start is known.
+    pub span: Span,
+    /// The line the problem starts on, captured by the tokenizer so the error can be
+    /// rendered without the source.
+    pub source_line: Option<Box<str>>,
+    /// What was expected and found instead, for errors from checking a token's kind.
+    pub mismatch: Option<Box<Mismatch>>,
+    /// Another place that helps explain the problem, such as where an unclosed string opens.
+    pub label: Option<Box<Label>>,
+    /// The values substituted into the message, by the names used in
+    /// `ErrorKind::template`, such as `char` in "Unexpected character '{char}'".
+    pub args: Box<[(&'static str, String)]>,
+}
+impl Error {
+    /// Returns the value of the named message argument.
+    pub fn arg(&self, name: &str) -> Option<&str> {
+        self.args
+            .iter()
+            .find(|(arg, _)| *arg == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the message from the catalog's template for this kind, falling back to the
+    /// English message if there's no template or it names an argument the error lacks.
+    pub fn localized_message(&self, catalog: &dyn MessageCatalog) -> String {
+        catalog
+            .template(self.kind)
+            .and_then(|template| fill(template, &self.args))
+            .unwrap_or_else(|| self.message.clone())
+    }
+
+    /// Renders the error rustc style, with the offending line from `source` and carets
+    /// under the problem. Falls back to the captured line if `source` doesn't have it.
+    pub fn render(&self, source: &str) -> String {
+        let line = source
+            .lines()
+            .nth(self.location.row)
+            .or(self.source_line.as_deref());
+        self.render_line(line)
+    }
+
+    /// Renders the error like `render`, using the captured line rather than the source.
+    pub fn render_captured(&self) -> String {
+        self.render_line(self.source_line.as_deref())
+    }
+
+    /// Returns the byte range of the problem in `source`, which is the span if it isn't
+    /// empty and the character at the location otherwise.
+    pub fn byte_range(&self, source: &str) -> Range<usize> {
+        if self.span.is_empty() {
+            self.location.byte_range(source)
+        } else {
+            self.span.start..self.span.end
+        }
+    }
+
+    /// Returns the error for something nested more than `limit` deep, starting at the
+    /// location.
+    pub(crate) fn too_deep(limit: usize, location: Location, span: Span) -> Self {
+        Self {
+            kind: ErrorKind::TooDeep,
+            message: format!("Nested more than {} deep", limit),
+            location,
+            span,
+            source_line: None,
+            mismatch: None,
+            label: None,
+            args: Box::new([("limit", limit.to_string())]),
+        }
+    }
+
+    fn render_line(&self, line: Option<&str>) -> String {
+        let row = self.location.row + 1;
+        let gutter = " ".repeat(row.to_string().len());
+        let mut rendered = format!(
+            "error[{}]: {}\n{}--> {}\n",
+            self.kind.code(),
+            self.message,
+            gutter,
+            position(&self.location)
+        );
+
+        if let Some(line) = line {
+            let (padding, width) = underline(line, self.location.column, self.span.len());
+            rendered.push_str(&format!("{} |\n", gutter));
+            rendered.push_str(&format!("{} | {}\n", row, line));
+            rendered.push_str(&format!("{} | {}{}\n", gutter, padding, "^".repeat(width)));
+            if let Some(label) = &self.label {
+                rendered.push_str(&format!(
+                    "{} | {}\n",
+                    gutter,
+                    label_line(line, self.location.row, label)
+                ));
+            }
+        }
+
+        rendered
+    }
+}
+
+/// Returns the one based position of the location, with its path if it has one, such as
+/// `src/a.cfg:3:7`.
+pub(crate) fn position(location: &Location) -> String {
+    let row = location.row + 1;
+    let column = location.column + 1;
+    match &location.path {
+        Some(path) => format!("{}:{}:{}", path.display(), row, column),
+        None => format!("{}:{}", row, column),
+    }
+}
+
+/// Returns the text marking a label under the source line at `row`, such as
+/// `    - string starts here`. A label on another row is given by its position instead.
+pub(crate) fn label_line(line: &str, row: usize, label: &Label) -> String {
+    if label.location.row != row {
+        return format!("{}: {}", position(&label.location), label.message);
+    }
+    let (padding, width) = underline(line, label.location.column, label.span.len());
+    format!("{}{} {}", padding, "-".repeat(width), label.message)
+}
+
+/// Returns the padding before the carets under a problem at the column of the line, and the
+/// number of carets, which cover `len` bytes up to the end of the line, or one character if
+/// `len` is zero.
+pub(crate) fn underline(line: &str, column: usize, len: usize) -> (String, usize) {
+    // Keep tabs so the carets line up with the source line.
+    let padding: String = line
+        .chars()
+        .take(column)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    let width = line
+        .chars()
+        .skip(column)
+        .scan(0, |len, c| {
+            *len += c.len_utf8();
+            Some(*len)
+        })
+        .take_while(|end| *end <= len)
+        .count()
+        .max(1);
+    (padding, width)
+}
+
+/// A secondary place in the source that an error points to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub location: Location,
+    pub span: Span,
+    pub message: String,
+}
+
+/// What a parser expected and the token it found instead, so tools can phrase their own
+/// messages and suggest fixes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    /// A description of what was expected, such as `identifier`.
+    pub expected: String,
+    pub found: TokenKind,
+}
+
+/// The category of an error, each with a stable code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    UnclosedString,
+    MultipleDecimalPoints,
+    InvalidNumber,
+    UnexpectedCharacter,
+    UnusualWhitespace,
+    BidiControl,
+    InvisibleCharacter,
+    SmartQuote,
+    UnexpectedToken,
+    OutOfFuel,
+    UnclosedIdentifier,
+    InvalidDirective,
+    UnclosedConditional,
+    FailedInclude,
+    IncludeCycle,
+    FailedRead,
+    NewlineInString,
+    UnclosedComment,
+    TooDeep,
+    Stopped,
+}
+impl ErrorKind {
+    pub const ALL: [ErrorKind; 20] = [
+        ErrorKind::UnclosedString,
+        ErrorKind::MultipleDecimalPoints,
+        ErrorKind::InvalidNumber,
+        ErrorKind::UnexpectedCharacter,
+        ErrorKind::UnusualWhitespace,
+        ErrorKind::BidiControl,
+        ErrorKind::InvisibleCharacter,
+        ErrorKind::SmartQuote,
+        ErrorKind::UnexpectedToken,
+        ErrorKind::OutOfFuel,
+        ErrorKind::UnclosedIdentifier,
+        ErrorKind::InvalidDirective,
+        ErrorKind::UnclosedConditional,
+        ErrorKind::FailedInclude,
+        ErrorKind::IncludeCycle,
+        ErrorKind::FailedRead,
+        ErrorKind::NewlineInString,
+        ErrorKind::UnclosedComment,
+        ErrorKind::TooDeep,
+        ErrorKind::Stopped,
+    ];
+
+    /// Returns the stable code for this kind, such as `T0001`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorKind::UnclosedString => "T0001",
+            ErrorKind::MultipleDecimalPoints => "T0002",
+            ErrorKind::InvalidNumber => "T0003",
+            ErrorKind::UnexpectedCharacter => "T0004",
+            ErrorKind::UnusualWhitespace => "T0005",
+            ErrorKind::BidiControl => "T0006",
+            ErrorKind::InvisibleCharacter => "T0007",
+            ErrorKind::SmartQuote => "T0008",
+            ErrorKind::UnexpectedToken => "T0009",
+            ErrorKind::OutOfFuel => "T0010",
+            ErrorKind::UnclosedIdentifier => "T0011",
+            ErrorKind::InvalidDirective => "T0012",
+            ErrorKind::UnclosedConditional => "T0013",
+            ErrorKind::FailedInclude => "T0014",
+            ErrorKind::IncludeCycle => "T0015",
+            ErrorKind::FailedRead => "T0016",
+            ErrorKind::NewlineInString => "T0017",
+            ErrorKind::UnclosedComment => "T0018",
+            ErrorKind::TooDeep => "T0019",
+            ErrorKind::Stopped => "T0020",
+        }
+    }
+
+    /// Returns the kind with the given code.
+    pub fn from_code(code: &str) -> Option<ErrorKind> {
+        Self::ALL.into_iter().find(|kind| kind.code() == code)
+    }
+
+    /// Returns the English message template for this kind, naming the error's `args` in
+    /// braces. Translations in a `MessageCatalog` use the same names.
+    pub fn template(&self) -> &'static str {
+        match self {
+            ErrorKind::UnclosedString => "Unclosed string",
+            ErrorKind::MultipleDecimalPoints => "Float literal cannot have multiple decimal points",
+            ErrorKind::InvalidNumber => "Invalid {type} literal '{literal}'",
+            ErrorKind::UnexpectedCharacter => "Unexpected character '{char}'",
+            ErrorKind::UnusualWhitespace => "Unusual whitespace character {code}",
+            ErrorKind::BidiControl => "Bidirectional control character {code}",
+            ErrorKind::InvisibleCharacter => "Invisible character {code}",
+            ErrorKind::SmartQuote => "Unexpected smart quote '{char}', use '\"' instead",
+            ErrorKind::UnexpectedToken => "Expected {expected}, got {found}",
+            ErrorKind::OutOfFuel => "Ran out of fuel",
+            ErrorKind::UnclosedIdentifier => "Unclosed identifier",
+            ErrorKind::InvalidDirective => "Invalid {directive} directive",
+            ErrorKind::UnclosedConditional => "Unclosed {directive} directive",
+            ErrorKind::FailedInclude => "Couldn't include '{path}': {reason}",
+            ErrorKind::IncludeCycle => "'{path}' includes itself",
+            ErrorKind::FailedRead => "Couldn't read line {line}: {reason}",
+            ErrorKind::NewlineInString => "Newline in string",
+            ErrorKind::UnclosedComment => "Unclosed block comment",
+            ErrorKind::TooDeep => "Nested more than {limit} deep",
+            ErrorKind::Stopped => "Stopped by a hook",
+        }
+    }
+
+    /// Returns a short description of this kind.
+    pub fn description(&self) -> &'static str {
+        match self {
+            ErrorKind::UnclosedString => "unterminated string",
+            ErrorKind::MultipleDecimalPoints => "multiple decimal points in a number",
+            ErrorKind::InvalidNumber => "invalid numeric literal",
+            ErrorKind::UnexpectedCharacter => "unexpected character",
+            ErrorKind::UnusualWhitespace => "unusual whitespace character",
+            ErrorKind::BidiControl => "bidirectional control character",
+            ErrorKind::InvisibleCharacter => "invisible character",
+            ErrorKind::SmartQuote => "smart quote",
+            ErrorKind::UnexpectedToken => "unexpected token",
+            ErrorKind::OutOfFuel => "ran out of fuel",
+            ErrorKind::UnclosedIdentifier => "unterminated quoted identifier",
+            ErrorKind::InvalidDirective => "misplaced or malformed directive",
+            ErrorKind::UnclosedConditional => "conditional directive without an `endif`",
+            ErrorKind::FailedInclude => "included file couldn't be loaded",
+            ErrorKind::IncludeCycle => "file includes itself",
+            ErrorKind::FailedRead => "input couldn't be read",
+            ErrorKind::NewlineInString => "line break inside a string",
+            ErrorKind::UnclosedComment => "unterminated block comment",
+            ErrorKind::TooDeep => "nesting deeper than the limit",
+            ErrorKind::Stopped => "tokenizing stopped by a hook",
+        }
+    }
+
+    /// Returns a longer explanation of this kind, with examples.
+    pub fn explanation(&self) -> &'static str {
+        match self {
+            ErrorKind::UnclosedString => {
+                "A string literal was opened but never closed.\n\n\
+                 Example:\n\n    greeting = \"hello\n\n\
+                 Add the closing quote: `greeting = \"hello\"`."
+            }
+            ErrorKind::MultipleDecimalPoints => {
+                "A number contains more than one decimal point.\n\n\
+                 Example:\n\n    version = 1.2.3\n\n\
+                 Use a string for values like versions: `version = \"1.2.3\"`."
+            }
+            ErrorKind::InvalidNumber => {
+                "A numeric literal couldn't be parsed, usually because it's too large.\n\n\
+                 Example:\n\n    x = 1000000000000000000000000000000000000000000\n\n\
+                 Integers must fit in an `i128`."
+            }
+            ErrorKind::UnexpectedCharacter => {
+                "A character can't start any token under the configured identifier rules.\n\n\
+                 Example, with `IdentifierRules::UnicodeXid`:\n\n    price$ = 5\n\n\
+                 Remove the character or use `UnexpectedCharacters::Token` to keep going."
+            }
+            ErrorKind::UnusualWhitespace => {
+                "A whitespace character outside of ASCII, such as a non-breaking space, was found \
+                 while `UnusualWhitespace::Error` is set.\n\n\
+                 Replace it with a regular space."
+            }
+            ErrorKind::BidiControl => {
+                "A bidirectional control character was found while `BidiControls::Error` is set. \
+                 These can make the rendered source differ from what is tokenized.\n\n\
+                 Remove the character, or escape it inside strings."
+            }
+            ErrorKind::InvisibleCharacter => {
+                "A control or zero-width character was found while \
+                 `InvisibleCharacters::Error` is set.\n\n\
+                 Example:\n\n    foo\u{200B}bar\n\n\
+                 Remove the character."
+            }
+            ErrorKind::SmartQuote => {
+                "A curly quote was found while `SmartQuotes::Error` is set. These are usually \
+                 inserted by word processors.\n\n\
+                 Example:\n\n    name = \u{201C}value\u{201D}\n\n\
+                 Use straight quotes: `name = \"value\"`."
+            }
+            ErrorKind::UnexpectedToken => {
+                "A token wasn't the kind that was expected, for example when `Token::assert_int` \
+                 is called on an identifier."
+            }
+            ErrorKind::OutOfFuel => {
+                "Tokenizing stopped because `TokenizerConfig::fuel` was used up before the end \
+                 of the input.\n\n\
+                 Raise the fuel limit, or treat the input as too expensive to tokenize."
+            }
+            ErrorKind::UnclosedIdentifier => {
+                "A quoted identifier was opened but never closed.\n\n\
+                 Example, with `[` and `]` in `TokenizerConfig::identifier_quotes`:\n\n    \
+                 select [order from items\n\n\
+                 Add the closing delimiter: `select [order] from items`."
+            }
+            ErrorKind::InvalidDirective => {
+                "A directive was out of place or its arguments couldn't be read.\n\n\
+                 Example:\n\n    \
+                 #else\n    \
+                 #if DEBUG &&\n    \
+                 #line x\n\n\
+                 Every `elif`, `else` and `endif` needs an open `if`, an `if` can only have one \
+                 `else`, and conditions are built from names, `defined(NAME)`, `==`, `!=`, `!`, \
+                 `&&`, `||` and parentheses. A line directive takes a line number and an \
+                 optional quoted file name, as in `#line 42 \"orig.file\"`."
+            }
+            ErrorKind::UnclosedConditional => {
+                "A conditional directive was opened but never closed.\n\n\
+                 Example:\n\n    \
+                 #ifdef DEBUG\n    \
+                 log_level = 3\n\n\
+                 Add an `#endif` after the conditional section."
+            }
+            ErrorKind::FailedInclude => {
+                "An include directive named a file that couldn't be loaded.\n\n\
+                 Example:\n\n    \
+                 #include \"missing.cfg\"\n\n\
+                 Check the name, which is resolved relative to the including file."
+            }
+            ErrorKind::IncludeCycle => {
+                "A file includes itself, directly or through other files.\n\n\
+                 Example, in `a.cfg`:\n\n    \
+                 #include \"b.cfg\"\n\n\
+                 with `b.cfg` including `a.cfg`. Move what both files need into a third file."
+            }
+            ErrorKind::FailedRead => {
+                "Reading the input failed partway through, such as on a line that isn't \
+                 valid UTF-8 or a stream that was closed.\n\n\
+                 Check that the input is UTF-8 text and still readable."
+            }
+            ErrorKind::NewlineInString => {
+                "A string continues past the end of the line it opens on while \
+                 `StringNewlines::Error` is set. This usually means the closing quote is \
+                 missing.\n\n\
+                 Example:\n\n    greeting = \"hello\n    name = \"world\"\n\n\
+                 Close the string on the same line, or write the line break as `\\n`."
+            }
+            ErrorKind::UnclosedComment => {
+                "A block comment was opened with `#|` but never closed with `|#`. With \
+                 `BlockComments::Nested`, every `#|` inside the comment needs its own `|#`.\n\n\
+                 Example:\n\n    #| first #| second |#\n    x = 1\n\n\
+                 Add the missing `|#`, or set `unclosed_block_comments` to `Warn` or \
+                 `Recover` to read the rest of the input as the comment."
+            }
+            ErrorKind::TooDeep => {
+                "Block comments, included files or bracket groups are nested deeper than \
+                 `TokenizerConfig::max_depth` allows. The limit guards against input built \
+                 to exhaust memory or the stack.\n\n\
+                 Example, with a `max_depth` of 2:\n\n    #| one #| two #| three |# |# |#\n\n\
+                 Flatten the nesting, or raise `max_depth` if the input is trusted."
+            }
+            ErrorKind::Stopped => {
+                "A hook set with `Tokenizer::on_token` returned `ControlFlow::Break`, asking \
+                 for tokenizing to stop. The program embedding the tokenizer decides when, \
+                 such as when a request is cancelled or a file has more tokens than it \
+                 wants, so this isn't a problem with the input.\n\n\
+                 Check the condition the hook stops on."
+            }
+        }
+    }
+}
+
+/// A source of translated message templates, such as one loaded from a locale's
+/// resource file.
+pub trait MessageCatalog {
+    /// Returns the template for the kind, or `None` to use the English message.
+    fn template(&self, kind: ErrorKind) -> Option<&str>;
+}
+impl MessageCatalog for HashMap<ErrorKind, String> {
+    fn template(&self, kind: ErrorKind) -> Option<&str> {
+        self.get(&kind).map(String::as_str)
+    }
+}
+
+/// Substitutes the arguments into the template, or returns `None` if it names an argument
+/// that isn't there.
+fn fill(template: &str, args: &[(&'static str, String)]) -> Option<String> {
+    let mut filled = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        let close = open + rest[open..].find('}')?;
+        let name = &rest[open + 1..close];
+        let (_, value) = args.iter().find(|(arg, _)| *arg == name)?;
+        filled.push_str(&rest[..open]);
+        filled.push_str(value);
+        rest = &rest[close + 1..];
+    }
+    filled.push_str(rest);
+    Some(filled)
+}
+
+/// Returns the explanation for the error code, such as `T0001`.
+pub fn explain(code: &str) -> Option<&'static str> {
+    ErrorKind::from_code(code).map(|kind| kind.explanation())
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+impl std::error::Error for Error {}
+
+/// An error along with the tokens that were produced before it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorWithPartial {
+    pub error: Error,
+    pub tokens: Vec<Token>,
+}
+impl Display for ErrorWithPartial {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+impl std::error::Error for ErrorWithPartial {}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn codes_are_unique_and_explained() {
+        for kind in ErrorKind::ALL {
+            assert_eq!(Some(kind), ErrorKind::from_code(kind.code()));
+            assert_eq!(Some(kind.explanation()), explain(kind.code()));
+        }
+        assert_eq!(None, explain("T9999"));
+    }
+
+    #[test]
+    fn render_points_at_location() {
+        let error = Error {
+            kind: ErrorKind::UnclosedString,
+            message: "Unclosed string".to_string(),
+            location: Location::new(8, 1, Some(PathBuf::from("test.txt"))),
+            span: Span::new(19, 23),
+            source_line: None,
+            mismatch: None,
+            label: None,
+            args: Box::default(),
+        };
+        let source = "let x = 1;\nlet y = \"abc";
+        let expected =
+            "error[T0001]: Unclosed string\n --> test.txt:2:9\n  |\n2 | let y = \"abc\n  |         ^^^^\n";
+        assert_eq!(expected, error.render(source));
+    }
+
+    #[test]
+    fn render_captured_line() {
+        let source = "x = 1\ny = \"abc";
+        let error = crate::tokenizer::Tokenizer::tokenize(source, (0, 0).into()).unwrap_err();
+        assert_eq!(error.span, Span::new(10, 14));
+        assert_eq!(error.source_line.as_deref(), Some("y = \"abc"));
+        assert_eq!(error.render_captured(), error.render(source));
+        assert_eq!(
+            "error[T0001]: Unclosed string\n --> 2:5\n  |\n2 | y = \"abc\n  |     ^^^^\n",
+            error.render_captured()
+        );
+    }
+
+    #[test]
+    fn render_without_line() {
+        let error = Error {
+            kind: ErrorKind::UnexpectedCharacter,
+            message: "Oops".to_string(),
+            location: (3, 0).into(),
+            span: Span::default(),
+            source_line: None,
+            mismatch: None,
+            label: None,
+            args: Box::default(),
+        };
+        assert_eq!("error[T0004]: Oops\n --> 4:1\n", error.render("a"));
+    }
+
+    #[test]
+    fn messages_are_localized_from_templates() {
+        let error = crate::tokenizer::Tokenizer::tokenize_with_config(
+            "a $",
+            (0, 0).into(),
+            &crate::config::TokenizerConfig {
+                identifiers: crate::config::IdentifierRules::Ascii,
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        assert_eq!(error.arg("char"), Some("$"));
+        assert_eq!(
+            fill(error.kind.template(), &error.args),
+            Some(error.message.clone())
+        );
+
+        let mut catalog = HashMap::new();
+        assert_eq!(
+            error.localized_message(&catalog),
+            "Unexpected character '$'"
+        );
+        catalog.insert(
+            ErrorKind::UnexpectedCharacter,
+            "Caract\u{e8}re inattendu \u{ab} {char} \u{bb}".to_string(),
+        );
+        assert_eq!(
+            error.localized_message(&catalog),
+            "Caract\u{e8}re inattendu \u{ab} $ \u{bb}"
+        );
+        catalog.insert(ErrorKind::UnexpectedCharacter, "{missing}".to_string());
+        assert_eq!(
+            error.localized_message(&catalog),
+            "Unexpected character '$'"
+        );
+    }
 }
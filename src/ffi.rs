@@ -0,0 +1,252 @@
+use std::{
+    ffi::{c_char, CString},
+    ptr,
+};
+
+use crate::{
+    config::TokenizerConfig,
+    error::Error,
+    token::{Token, TokenKind, TokenKindTag},
+    tokenizer::Tokenizer,
+};
+
+/// A token as seen from C. `text` points at `text_len` bytes of UTF-8 that are not nul
+/// terminated and live as long as the result the token came from.
+#[repr(C)]
+#[derive(Debug)]
+pub struct TokenizerToken {
+    pub kind: TokenKindTag,
+    /// The byte offset of the start of the token in the source.
+    pub start: usize,
+    /// The byte offset just past the end of the token in the source.
+    pub end: usize,
+    /// The zero based line of the token.
+    pub line: usize,
+    /// The zero based column of the token, in characters.
+    pub column: usize,
+    pub text: *const u8,
+    pub text_len: usize,
+    /// The value of an integer literal, saturated to fit. Zero for other kinds.
+    pub integer_value: i64,
+    /// The value of a float, percent or unit literal. Zero for other kinds.
+    pub float_value: f64,
+}
+
+/// An error as seen from C. The strings are nul terminated and live as long as the result
+/// the error came from.
+#[repr(C)]
+#[derive(Debug)]
+pub struct TokenizerError {
+    /// The error code, such as `T0001`.
+    pub code: *const c_char,
+    pub message: *const c_char,
+    /// The zero based line the error occurred on.
+    pub line: usize,
+    /// The zero based column the error occurred at, in characters.
+    pub column: usize,
+}
+
+/// The tokens, and the error if there was one, from tokenizing a buffer.
+pub struct TokenizerResult {
+    /// Owns the text the `TokenizerToken`s point into.
+    _tokens: Vec<Token>,
+    c_tokens: Vec<TokenizerToken>,
+    /// Owns the strings `c_error` points to.
+    _error_strings: Option<(CString, CString)>,
+    c_error: Option<TokenizerError>,
+}
+impl TokenizerResult {
+    fn new(tokens: Vec<Token>, error: Option<Error>) -> Self {
+        let c_tokens = tokens.iter().map(to_c_token).collect();
+        let error_strings = error.as_ref().map(|error| {
+            (
+                CString::new(error.kind.code()).unwrap_or_default(),
+                CString::new(error.message.replace('\0', "")).unwrap_or_default(),
+            )
+        });
+        let c_error = error
+            .as_ref()
+            .zip(error_strings.as_ref())
+            .map(|(error, (code, message))| TokenizerError {
+                code: code.as_ptr(),
+                message: message.as_ptr(),
+                line: error.location.row,
+                column: error.location.column,
+            });
+
+        Self {
+            _tokens: tokens,
+            c_tokens,
+            _error_strings: error_strings,
+            c_error,
+        }
+    }
+}
+
+fn to_c_token(token: &Token) -> TokenizerToken {
+    let (integer_value, float_value) = match token.kind {
+        TokenKind::IntegerLiteral(i) => (i.clamp(i64::MIN as i128, i64::MAX as i128) as i64, 0.0),
+        #[cfg(feature = "bigint")]
+        TokenKind::BigIntegerLiteral(ref i) => match i.sign() {
+            num_bigint::Sign::Minus => (i64::MIN, 0.0),
+            _ => (i64::MAX, 0.0),
+        },
+        TokenKind::FloatLiteral(f) => (0, f),
+        // The nearest float, as C gets the exact number from the token's text.
+        TokenKind::PercentLiteral(ref s) | TokenKind::UnitLiteral { value: ref s, .. } => {
+            (0, s.parse().unwrap_or(0.0))
+        }
+        _ => (0, 0.0),
+    };
+
+    TokenizerToken {
+        kind: token.kind.tag(),
+        start: token.span.start,
+        end: token.span.end,
+        line: token.location.row,
+        column: token.location.column,
+        text: token.contents.as_ptr(),
+        text_len: token.contents.len(),
+        integer_value,
+        float_value,
+    }
+}
+
+/// Tokenizes `len` bytes of UTF-8 at `source` with the default config. The result holds
+/// the tokens read before any error, and must be freed with `tokenizer_result_free`.
+///
+/// Returns null if `source` is null or isn't valid UTF-8.
+///
+/// # Safety
+///
+/// `source` must point to at least `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn tokenizer_tokenize(
+    source: *const c_char,
+    len: usize,
+) -> *mut TokenizerResult {
+    if source.is_null() {
+        return ptr::null_mut();
+    }
+    let bytes = std::slice::from_raw_parts(source.cast::<u8>(), len);
+    let Ok(source) = std::str::from_utf8(bytes) else {
+        return ptr::null_mut();
+    };
+
+    let result =
+        match Tokenizer::tokenize_partial(source, (0, 0).into(), &TokenizerConfig::default()) {
+            Ok(tokens) => TokenizerResult::new(tokens, None),
+            Err(partial) => TokenizerResult::new(partial.tokens, Some(partial.error)),
+        };
+    Box::into_raw(Box::new(result))
+}
+
+/// Returns the number of tokens in the result.
+///
+/// # Safety
+///
+/// `result` must be null or a result from `tokenizer_tokenize` that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn tokenizer_result_token_count(result: *const TokenizerResult) -> usize {
+    result.as_ref().map_or(0, |result| result.c_tokens.len())
+}
+
+/// Returns the token at the index, or null if the index is out of range.
+///
+/// # Safety
+///
+/// `result` must be null or a result from `tokenizer_tokenize` that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn tokenizer_result_token(
+    result: *const TokenizerResult,
+    index: usize,
+) -> *const TokenizerToken {
+    result
+        .as_ref()
+        .and_then(|result| result.c_tokens.get(index))
+        .map_or(ptr::null(), |token| token as *const TokenizerToken)
+}
+
+/// Returns the error that stopped tokenizing, or null if there wasn't one.
+///
+/// # Safety
+///
+/// `result` must be null or a result from `tokenizer_tokenize` that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn tokenizer_result_error(
+    result: *const TokenizerResult,
+) -> *const TokenizerError {
+    result
+        .as_ref()
+        .and_then(|result| result.c_error.as_ref())
+        .map_or(ptr::null(), |error| error as *const TokenizerError)
+}
+
+/// Frees a result along with its tokens and error.
+///
+/// # Safety
+///
+/// `result` must be null or a result from `tokenizer_tokenize` that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn tokenizer_result_free(result: *mut TokenizerResult) {
+    if !result.is_null() {
+        drop(Box::from_raw(result));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CStr;
+
+    use super::*;
+
+    #[test]
+    fn tokenize_through_c_api() {
+        let source = "x = 12 \"unclosed";
+        unsafe {
+            let result = tokenizer_tokenize(source.as_ptr().cast(), source.len());
+            assert_eq!(tokenizer_result_token_count(result), 3);
+
+            let token = &*tokenizer_result_token(result, 2);
+            assert_eq!(token.kind, TokenKindTag::IntegerLiteral);
+            assert_eq!(token.integer_value, 12);
+            assert_eq!((token.start, token.end), (4, 6));
+            let text = std::slice::from_raw_parts(token.text, token.text_len);
+            assert_eq!(text, b"12");
+            assert!(tokenizer_result_token(result, 3).is_null());
+
+            let error = &*tokenizer_result_error(result);
+            assert_eq!(CStr::from_ptr(error.code).to_str(), Ok("T0001"));
+            assert_eq!(
+                CStr::from_ptr(error.message).to_str(),
+                Ok("Unclosed string")
+            );
+            assert_eq!((error.line, error.column), (0, 7));
+
+            tokenizer_result_free(result);
+        }
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn big_integers_saturate() {
+        let source =
+            "170141183460469231731687303715884105728 -170141183460469231731687303715884105729";
+        unsafe {
+            let result = tokenizer_tokenize(source.as_ptr().cast(), source.len());
+            assert_eq!(tokenizer_result_token_count(result), 2);
+            assert_eq!((*tokenizer_result_token(result, 0)).integer_value, i64::MAX);
+            assert_eq!((*tokenizer_result_token(result, 1)).integer_value, i64::MIN);
+            tokenizer_result_free(result);
+        }
+    }
+
+    #[test]
+    fn invalid_utf8_returns_null() {
+        let source = [b'a', 0xff];
+        unsafe {
+            assert!(tokenizer_tokenize(source.as_ptr().cast(), source.len()).is_null());
+            assert!(tokenizer_tokenize(ptr::null(), 0).is_null());
+        }
+    }
+}
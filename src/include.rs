@@ -0,0 +1,337 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    config::TokenizerConfig,
+    error::{Error, ErrorKind},
+    location::Location,
+    source_map::SourceMap,
+    token::{Origin, Provenance, Token, TokenKind},
+    tokenizer::Tokenizer,
+};
+
+/// Finds and reads the files named by include directives.
+pub trait FileLoader {
+    /// Returns the path that `name` refers to when included from the file at `from`. By
+    /// default it's relative to the directory of `from`.
+    fn resolve(&self, name: &str, from: Option<&Path>) -> PathBuf {
+        match from.and_then(Path::parent) {
+            Some(directory) => directory.join(name),
+            None => PathBuf::from(name),
+        }
+    }
+
+    /// Returns the contents of the file.
+    fn load(&self, path: &Path) -> std::io::Result<String>;
+}
+
+/// Loads files from the file system.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FsLoader;
+impl FileLoader for FsLoader {
+    fn resolve(&self, name: &str, from: Option<&Path>) -> PathBuf {
+        let path = match from.and_then(Path::parent) {
+            Some(directory) => directory.join(name),
+            None => PathBuf::from(name),
+        };
+        // Canonical paths let a cycle be found however the files name each other.
+        path.canonicalize().unwrap_or(path)
+    }
+
+    fn load(&self, path: &Path) -> std::io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+}
+
+/// Loads files from memory, keyed by path.
+impl FileLoader for HashMap<PathBuf, String> {
+    fn load(&self, path: &Path) -> std::io::Result<String> {
+        self.get(path)
+            .cloned()
+            .ok_or_else(|| std::io::ErrorKind::NotFound.into())
+    }
+}
+
+/// A tokenized file along with the files it included.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IncludedFile {
+    pub path: Option<Arc<Path>>,
+    /// The file's tokens, include directives included.
+    pub tokens: Vec<Token>,
+    /// Each included file with the index in `tokens` of the directive that included it.
+    pub includes: Vec<(usize, IncludedFile)>,
+}
+impl IncludedFile {
+    /// Returns the tokens with every include directive replaced by the included file's
+    /// tokens, which are marked as synthesized from the directive.
+    pub fn splice(self) -> Vec<Token> {
+        let mut tokens = Vec::with_capacity(self.tokens.len());
+        self.splice_into(&mut tokens);
+        tokens
+    }
+
+    /// Splices the tokens, along with a map of the file and offset each came from.
+    pub fn splice_with_map(self) -> (Vec<Token>, SourceMap) {
+        let tokens = self.splice();
+        let map = SourceMap::from_tokens(&tokens);
+        (tokens, map)
+    }
+
+    fn splice_into(self, output: &mut Vec<Token>) {
+        let mut includes = self.includes.into_iter().peekable();
+        for (index, token) in self.tokens.into_iter().enumerate() {
+            let Some((_, file)) = includes.next_if(|(directive, _)| *directive == index) else {
+                output.push(token);
+                continue;
+            };
+
+            let start = output.len();
+            file.splice_into(output);
+            for included in &mut output[start..] {
+                if included.provenance == Provenance::Original {
+                    included.provenance = Provenance::Synthesized {
+                        reason: "include expansion",
+                        derived_from: Some(Box::new(Origin::from(&token))),
+                    };
+                }
+            }
+        }
+    }
+}
+
+/// Tokenizes a file and, recursively, every file it includes.
+///
+/// Includes are `Directive`s with the include directive's name, so the config needs a
+/// directive character such as `#` in `TokenizerConfig::directives`. The argument is the
+/// file's name, optionally in double quotes or angle brackets, as in `#include "base.cfg"`.
+pub struct IncludeResolver<L> {
+    loader: L,
+    config: TokenizerConfig,
+    directive: String,
+}
+impl<L: FileLoader> IncludeResolver<L> {
+    /// Creates a resolver for `include` directives.
+    pub fn new(loader: L, config: &TokenizerConfig) -> Self {
+        Self {
+            loader,
+            config: config.clone(),
+            directive: "include".to_string(),
+        }
+    }
+
+    /// Changes the name of the include directive, such as to `import`.
+    pub fn set_directive(&mut self, name: &str) {
+        self.directive = name.to_string();
+    }
+
+    /// Loads and tokenizes the file at `path` and everything it includes.
+    pub fn tokenize_file(&self, path: &Path) -> Result<IncludedFile, Error> {
+        let contents = self.loader.load(path).map_err(|error| Error {
+            kind: ErrorKind::FailedInclude,
+            message: format!("Couldn't include '{}': {}", path.display(), error),
+            location: Location::new(0, 0, Some(path.to_path_buf())),
+            span: Default::default(),
+            source_line: None,
+            mismatch: None,
+            label: None,
+            args: Box::new([
+                ("path", path.display().to_string()),
+                ("reason", error.to_string()),
+            ]),
+        })?;
+        self.tokenize(&contents, Location::new(0, 0, Some(path.to_path_buf())))
+    }
+
+    /// Tokenizes the contents and everything they include, resolving names relative to
+    /// the location's path.
+    pub fn tokenize(&self, contents: &str, location: Location) -> Result<IncludedFile, Error> {
+        let mut stack = Vec::new();
+        self.tokenize_nested(contents, location, &mut stack, 0)
+    }
+
+    /// Tokenizes a file, where `stack` holds the paths of the files currently including it
+    /// and `depth` is the number of files doing so.
+    fn tokenize_nested(
+        &self,
+        contents: &str,
+        location: Location,
+        stack: &mut Vec<Arc<Path>>,
+        depth: usize,
+    ) -> Result<IncludedFile, Error> {
+        let path = location.path.clone();
+        let tokens = Tokenizer::tokenize_with_config(contents, location, &self.config)?;
+        stack.extend(path.clone());
+
+        let mut includes = Vec::new();
+        for (index, token) in tokens.iter().enumerate() {
+            let name = match &token.kind {
+                TokenKind::Directive(directive) if directive.name == self.directive.as_str() => {
+                    unquote(&directive.arguments)
+                }
+                _ => continue,
+            };
+
+            let included = self.loader.resolve(name, path.as_deref());
+            if stack.iter().any(|path| **path == *included) {
+                return Err(include_error(
+                    token,
+                    ErrorKind::IncludeCycle,
+                    format!("'{}' includes itself", included.display()),
+                    vec![("path", included.display().to_string())],
+                ));
+            }
+            if let Some(limit) = self.config.max_depth.filter(|limit| depth >= *limit) {
+                return Err(Error::too_deep(limit, token.location.clone(), token.span));
+            }
+            let contents = self.loader.load(&included).map_err(|error| {
+                include_error(
+                    token,
+                    ErrorKind::FailedInclude,
+                    format!("Couldn't include '{}': {}", included.display(), error),
+                    vec![
+                        ("path", included.display().to_string()),
+                        ("reason", error.to_string()),
+                    ],
+                )
+            })?;
+
+            let location = Location::new(0, 0, Some(included));
+            includes.push((
+                index,
+                self.tokenize_nested(&contents, location, stack, depth + 1)?,
+            ));
+        }
+
+        if path.is_some() {
+            stack.pop();
+        }
+        Ok(IncludedFile {
+            path,
+            tokens,
+            includes,
+        })
+    }
+}
+
+/// Strips the double quotes or angle brackets from around an included file's name.
+fn unquote(name: &str) -> &str {
+    name.strip_prefix('"')
+        .and_then(|name| name.strip_suffix('"'))
+        .or_else(|| {
+            name.strip_prefix('<')
+                .and_then(|name| name.strip_suffix('>'))
+        })
+        .unwrap_or(name)
+}
+
+fn include_error(
+    token: &Token,
+    kind: ErrorKind,
+    message: String,
+    args: Vec<(&'static str, String)>,
+) -> Error {
+    Error {
+        kind,
+        message,
+        location: token.location.clone(),
+        span: token.span,
+        source_line: None,
+        mismatch: None,
+        label: None,
+        args: args.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::span::Span;
+
+    fn resolver(files: &[(&str, &str)]) -> IncludeResolver<HashMap<PathBuf, String>> {
+        let files = files
+            .iter()
+            .map(|(path, contents)| (PathBuf::from(path), contents.to_string()))
+            .collect();
+        let config = TokenizerConfig {
+            directives: vec!['#'],
+            ..Default::default()
+        };
+        IncludeResolver::new(files, &config)
+    }
+
+    #[test]
+    fn includes_are_nested_and_spliced() {
+        let resolver = resolver(&[
+            ("conf/main.cfg", "a\n#include \"common.cfg\"\nd"),
+            ("conf/common.cfg", "b\n#include <lib/c.cfg>"),
+            ("conf/lib/c.cfg", "c"),
+        ]);
+        let file = resolver.tokenize_file(Path::new("conf/main.cfg")).unwrap();
+        assert_eq!(file.tokens.len(), 3);
+        assert_eq!(file.includes.len(), 1);
+        assert_eq!(file.includes[0].0, 1);
+        assert_eq!(
+            file.includes[0].1.path.as_deref(),
+            Some(Path::new("conf/common.cfg"))
+        );
+
+        let tokens = file.splice();
+        assert!(!tokens[0].is_synthesized());
+        assert_eq!(
+            tokens[2].provenance,
+            Provenance::Synthesized {
+                reason: "include expansion",
+                derived_from: Some(Box::new(Origin {
+                    location: Location::new(0, 1, Some("conf/common.cfg".into())),
+                    span: Span::new(2, 22),
+                })),
+            }
+        );
+        let contents: Vec<_> = tokens.iter().map(|token| &*token.contents).collect();
+        assert_eq!(contents, ["a", "b", "c", "d"]);
+        assert_eq!(
+            tokens[2].location,
+            Location::new(0, 0, Some("conf/lib/c.cfg".into()))
+        );
+        assert_eq!(
+            tokens[3].location,
+            Location::new(0, 2, Some("conf/main.cfg".into()))
+        );
+    }
+
+    #[test]
+    fn cycles_and_missing_files_return_errors() {
+        let resolver = resolver(&[
+            ("a.cfg", "#include \"b.cfg\""),
+            ("b.cfg", "x\n#include \"a.cfg\""),
+            ("c.cfg", "#include \"missing.cfg\""),
+        ]);
+
+        let error = resolver.tokenize_file(Path::new("a.cfg")).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::IncludeCycle);
+        assert_eq!(error.message, "'a.cfg' includes itself");
+        assert_eq!(error.location, Location::new(0, 1, Some("b.cfg".into())));
+
+        let error = resolver.tokenize_file(Path::new("c.cfg")).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::FailedInclude);
+        assert_eq!(error.arg("path"), Some("missing.cfg"));
+    }
+
+    #[test]
+    fn includes_nested_too_deep_are_errors() {
+        let mut resolver = resolver(&[
+            ("a.cfg", "#include \"b.cfg\""),
+            ("b.cfg", "#include \"c.cfg\""),
+            ("c.cfg", "c"),
+        ]);
+        assert!(resolver.tokenize_file(Path::new("a.cfg")).is_ok());
+
+        resolver.config.max_depth = Some(1);
+        let error = resolver.tokenize_file(Path::new("a.cfg")).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::TooDeep);
+        assert_eq!(error.location, Location::new(0, 0, Some("b.cfg".into())));
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+
+use crate::{
+    location::Location,
+    span::Span,
+    token::{Token, TokenKind},
+};
+
+/// A handle to a string stored in an `Interner`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SymbolId(pub u32);
+
+/// Stores each distinct string once, handing out `SymbolId`s that compare in O(1).
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    ids: HashMap<Box<str>, SymbolId>,
+    strings: Vec<Box<str>>,
+}
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the id for the string, storing it if it hasn't been seen before.
+    pub fn intern(&mut self, s: &str) -> SymbolId {
+        if let Some(id) = self.ids.get(s) {
+            return *id;
+        }
+
+        let id = SymbolId(self.strings.len() as u32);
+        self.strings.push(s.into());
+        self.ids.insert(s.into(), id);
+        id
+    }
+
+    /// Returns the id for the string if it has been interned.
+    pub fn get(&self, s: &str) -> Option<SymbolId> {
+        self.ids.get(s).copied()
+    }
+
+    /// Returns the string for the id.
+    ///
+    /// Panics if the id came from a different interner.
+    pub fn resolve(&self, id: SymbolId) -> &str {
+        &self.strings[id.0 as usize]
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Converts tokens so that identifiers and symbols carry a `SymbolId` instead of a string.
+    pub fn intern_tokens(&mut self, tokens: Vec<Token>) -> Vec<InternedToken> {
+        tokens
+            .into_iter()
+            .map(|token| {
+                let kind = match token.kind {
+                    TokenKind::Identifier(s) => InternedTokenKind::Identifier(self.intern(&s)),
+                    TokenKind::Symbol(s) => InternedTokenKind::Symbol(self.intern(&s)),
+                    kind => InternedTokenKind::Other(kind),
+                };
+                InternedToken {
+                    location: token.location,
+                    span: token.span,
+                    kind,
+                }
+            })
+            .collect()
+    }
+}
+
+/// A token whose identifier or symbol text lives in an `Interner`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InternedToken {
+    pub location: Location,
+    pub span: Span,
+    pub kind: InternedTokenKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InternedTokenKind {
+    Identifier(SymbolId),
+    Symbol(SymbolId),
+    /// Any other kind of token, which keeps its original payload.
+    Other(TokenKind),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Tokenizer;
+
+    #[test]
+    fn intern_returns_same_id() {
+        let mut interner = Interner::new();
+        let foo = interner.intern("foo");
+        let bar = interner.intern("bar");
+        assert_ne!(foo, bar);
+        assert_eq!(foo, interner.intern("foo"));
+        assert_eq!(Some(bar), interner.get("bar"));
+        assert_eq!(None, interner.get("baz"));
+        assert_eq!("foo", interner.resolve(foo));
+        assert_eq!(2, interner.len());
+    }
+
+    #[test]
+    fn intern_tokens_shares_identifiers() {
+        let tokens = Tokenizer::tokenize("x = x + 1", (0, 0).into()).unwrap();
+        let mut interner = Interner::new();
+        let tokens = interner.intern_tokens(tokens);
+
+        let x = interner.get("x").unwrap();
+        assert_eq!(tokens[0].kind, InternedTokenKind::Identifier(x));
+        assert_eq!(tokens[2].kind, InternedTokenKind::Identifier(x));
+        assert_eq!(tokens[2].location, (0, 4).into());
+        assert_eq!(
+            tokens[4].kind,
+            InternedTokenKind::Other(TokenKind::IntegerLiteral(1))
+        );
+        assert_eq!(interner.len(), 3);
+    }
+}
@@ -0,0 +1,222 @@
+use crate::{
+    error::Error,
+    location::Location,
+    token::{Token, TokenKind},
+    warning::Warning,
+};
+
+impl Error {
+    /// Serializes the error as a JSON diagnostic object.
+    pub fn to_json(&self) -> String {
+        diagnostic_json(
+            "error",
+            Some(self.kind.code()),
+            &self.message,
+            &self.location,
+        )
+    }
+}
+
+impl Warning {
+    /// Serializes the warning as a JSON diagnostic object.
+    pub fn to_json(&self) -> String {
+        diagnostic_json("warning", None, &self.message, &self.location)
+    }
+}
+
+impl Token {
+    /// Serializes the token as a JSON object with its `kind`, `contents`, `value`, `file`,
+    /// one based `line` and `column`, and byte `span`.
+    pub fn to_json(&self) -> String {
+        let value = match &self.kind {
+            TokenKind::StringLiteral(s)
+            | TokenKind::Identifier(s)
+            | TokenKind::Symbol(s)
+            | TokenKind::DateTimeLiteral(s)
+            | TokenKind::ColorLiteral(s)
+            | TokenKind::Attribute(s)
+            | TokenKind::Variable { name: s, .. }
+            | TokenKind::Unknown(s) => escape(s),
+            TokenKind::Comment(comment) => escape(&comment.text),
+            TokenKind::Directive(directive) => escape(&directive.name),
+            TokenKind::IntegerLiteral(i) => i.to_string(),
+            #[cfg(feature = "bigint")]
+            TokenKind::BigIntegerLiteral(i) => i.to_string(),
+            TokenKind::FloatLiteral(f) if f.is_finite() => f.to_string(),
+            // JSON has no infinities or NaN, so they're written as the words for them.
+            TokenKind::FloatLiteral(f) if f.is_nan() => escape("nan"),
+            TokenKind::FloatLiteral(f) if *f > 0.0 => escape("inf"),
+            TokenKind::FloatLiteral(_) => escape("-inf"),
+            // A string, as the number may be written with a sign or dot JSON doesn't allow.
+            TokenKind::DecimalLiteral(s)
+            | TokenKind::PercentLiteral(s)
+            | TokenKind::UnitLiteral { value: s, .. } => escape(s),
+        };
+        let file = match &self.location.path {
+            Some(path) => escape(&path.display().to_string()),
+            None => "null".to_string(),
+        };
+
+        format!(
+            "{{\"kind\":\"{}\",\"contents\":{},\"value\":{},\"file\":{},\"line\":{},\"column\":{},\"span\":{{\"start\":{},\"end\":{}}}}}",
+            self.kind.name(),
+            escape(&self.contents),
+            value,
+            file,
+            self.location.row + 1,
+            self.location.column + 1,
+            self.span.start,
+            self.span.end
+        )
+    }
+}
+
+/// Serializes tokens as a JSON array of token objects.
+pub fn tokens_to_json(tokens: &[Token]) -> String {
+    let tokens: Vec<String> = tokens.iter().map(Token::to_json).collect();
+    format!("[{}]", tokens.join(","))
+}
+
+/// Serializes errors and warnings as a JSON array of diagnostic objects, each with a
+/// `severity`, `code`, `message`, `file` and one based `range`.
+pub fn diagnostics_to_json(errors: &[Error], warnings: &[Warning]) -> String {
+    let diagnostics: Vec<String> = errors
+        .iter()
+        .map(Error::to_json)
+        .chain(warnings.iter().map(Warning::to_json))
+        .collect();
+    format!("[{}]", diagnostics.join(","))
+}
+
+fn diagnostic_json(
+    severity: &str,
+    code: Option<&str>,
+    message: &str,
+    location: &Location,
+) -> String {
+    let file = match &location.path {
+        Some(path) => escape(&path.display().to_string()),
+        None => "null".to_string(),
+    };
+    let code = match code {
+        Some(code) => escape(code),
+        None => "null".to_string(),
+    };
+    let line = location.row + 1;
+    let column = location.column + 1;
+
+    format!(
+        "{{\"severity\":\"{}\",\"code\":{},\"message\":{},\"file\":{},\"range\":{{\"start\":{{\"line\":{},\"column\":{}}},\"end\":{{\"line\":{},\"column\":{}}}}}}}",
+        severity,
+        code,
+        escape(message),
+        file,
+        line,
+        column,
+        line,
+        column + 1
+    )
+}
+
+/// Returns the string as a quoted and escaped JSON string.
+pub(crate) fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{error::ErrorKind, span::Span};
+
+    #[test]
+    fn diagnostics_serialize() {
+        let errors = vec![Error {
+            kind: ErrorKind::UnclosedString,
+            message: "Unclosed string".to_string(),
+            location: Location::new(4, 0, Some(PathBuf::from("a.txt"))),
+            span: Span::new(4, 8),
+            source_line: None,
+            mismatch: None,
+            label: None,
+            args: Box::default(),
+        }];
+        let warnings = vec![Warning {
+            message: "Say \"hi\"".to_string(),
+            location: (1, 0).into(),
+        }];
+
+        let expected = concat!(
+            "[{\"severity\":\"error\",\"code\":\"T0001\",\"message\":\"Unclosed string\",",
+            "\"file\":\"a.txt\",\"range\":{\"start\":{\"line\":1,\"column\":5},",
+            "\"end\":{\"line\":1,\"column\":6}}},",
+            "{\"severity\":\"warning\",\"code\":null,\"message\":\"Say \\\"hi\\\"\",",
+            "\"file\":null,\"range\":{\"start\":{\"line\":2,\"column\":1},",
+            "\"end\":{\"line\":2,\"column\":2}}}]"
+        );
+        assert_eq!(expected, diagnostics_to_json(&errors, &warnings));
+    }
+
+    #[test]
+    fn tokens_serialize() {
+        let tokens = crate::tokenizer::tokenize("x 1.5", (0, 0).into()).unwrap();
+        let expected = concat!(
+            "[{\"kind\":\"identifier\",\"contents\":\"x\",\"value\":\"x\",\"file\":null,",
+            "\"line\":1,\"column\":1,\"span\":{\"start\":0,\"end\":1}},",
+            "{\"kind\":\"float_literal\",\"contents\":\"1.5\",\"value\":1.5,\"file\":null,",
+            "\"line\":1,\"column\":3,\"span\":{\"start\":2,\"end\":5}}]"
+        );
+        assert_eq!(expected, tokens_to_json(&tokens));
+
+        let config = crate::config::TokenizerConfig {
+            fractions: crate::config::Fractions::Decimal,
+            ..Default::default()
+        };
+        let tokens = crate::tokenizer::tokenize_with_config(".5", (0, 0).into(), &config).unwrap();
+        let expected = concat!(
+            "[{\"kind\":\"decimal_literal\",\"contents\":\".5\",\"value\":\".5\",",
+            "\"file\":null,\"line\":1,\"column\":1,\"span\":{\"start\":0,\"end\":2}}]"
+        );
+        assert_eq!(expected, tokens_to_json(&tokens));
+    }
+
+    #[test]
+    fn non_finite_floats_serialize_as_strings() {
+        let config = crate::config::TokenizerConfig {
+            special_floats: true,
+            ..Default::default()
+        };
+        let tokens =
+            crate::tokenizer::tokenize_with_config("inf -inf nan", (0, 0).into(), &config).unwrap();
+        let values: Vec<String> = tokens
+            .iter()
+            .map(|token| {
+                let json = token.to_json();
+                let start = json.find("\"value\":").unwrap() + 8;
+                let end = json.find(",\"file\"").unwrap();
+                json[start..end].to_string()
+            })
+            .collect();
+        assert_eq!(values, ["\"inf\"", "\"-inf\"", "\"nan\""]);
+    }
+
+    #[test]
+    fn escape_control_characters() {
+        assert_eq!("\"a\\tb\\u0007\"", escape("a\tb\u{7}"));
+    }
+}
@@ -0,0 +1,160 @@
+use std::fmt::{self, Debug};
+
+use crate::token::{Token, TokenKind};
+
+/// Tokens compared by their kinds alone, ignoring their locations, spans and contents, for
+/// tests that only care which tokens were read. It compares equal to a slice, array or
+/// `Vec` of kinds, and its `Debug` output lists just the kinds, so it reads well in
+/// `assert_eq!`:
+///
+/// ```
+/// use tokenizer::{tokenize, TokenKind, TokenKindSeq};
+///
+/// let tokens = tokenize("x = 1", (0, 0).into()).unwrap();
+/// assert_eq!(
+///     TokenKindSeq(&tokens),
+///     [
+///         TokenKind::Identifier("x".into()),
+///         TokenKind::Symbol("=".into()),
+///         TokenKind::IntegerLiteral(1),
+///     ]
+/// );
+/// ```
+#[derive(Clone, Copy)]
+pub struct TokenKindSeq<'a>(pub &'a [Token]);
+impl TokenKindSeq<'_> {
+    fn eq_kinds(&self, kinds: &[TokenKind]) -> bool {
+        self.0.len() == kinds.len()
+            && self
+                .0
+                .iter()
+                .zip(kinds)
+                .all(|(token, kind)| token.kind == *kind)
+    }
+}
+impl Debug for TokenKindSeq<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.0.iter().map(|token| &token.kind))
+            .finish()
+    }
+}
+impl PartialEq for TokenKindSeq<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len()
+            && self
+                .0
+                .iter()
+                .zip(other.0)
+                .all(|(token, other)| token.kind == other.kind)
+    }
+}
+impl PartialEq<[TokenKind]> for TokenKindSeq<'_> {
+    fn eq(&self, other: &[TokenKind]) -> bool {
+        self.eq_kinds(other)
+    }
+}
+impl PartialEq<&[TokenKind]> for TokenKindSeq<'_> {
+    fn eq(&self, other: &&[TokenKind]) -> bool {
+        self.eq_kinds(other)
+    }
+}
+impl<const N: usize> PartialEq<[TokenKind; N]> for TokenKindSeq<'_> {
+    fn eq(&self, other: &[TokenKind; N]) -> bool {
+        self.eq_kinds(other)
+    }
+}
+impl PartialEq<Vec<TokenKind>> for TokenKindSeq<'_> {
+    fn eq(&self, other: &Vec<TokenKind>) -> bool {
+        self.eq_kinds(other)
+    }
+}
+
+/// Panics unless the tokens have the expected kinds, in order, ignoring their locations,
+/// spans and contents. The message names the first token that differs and where it is, or
+/// the first token missing or left over.
+#[track_caller]
+pub fn assert_kinds_eq(tokens: &[Token], expected: &[TokenKind]) {
+    let differs = tokens
+        .iter()
+        .zip(expected)
+        .position(|(token, kind)| token.kind != *kind);
+    let message = match differs {
+        Some(i) => format!(
+            "token {} is {} at {}, expected {}",
+            i,
+            tokens[i].kind,
+            position(&tokens[i]),
+            expected[i]
+        ),
+        None if tokens.len() > expected.len() => format!(
+            "expected {} tokens, got {}, the first extra being {} at {}",
+            expected.len(),
+            tokens.len(),
+            tokens[expected.len()].kind,
+            position(&tokens[expected.len()])
+        ),
+        None if tokens.len() < expected.len() => format!(
+            "expected {} tokens, got {}, the first missing being {}",
+            expected.len(),
+            tokens.len(),
+            expected[tokens.len()]
+        ),
+        None => return,
+    };
+    panic!(
+        "{}\n  actual: {:?}\nexpected: {:?}",
+        message,
+        TokenKindSeq(tokens),
+        expected
+    );
+}
+
+/// Returns the token's one based row and column, as errors are rendered.
+fn position(token: &Token) -> String {
+    format!("{}:{}", token.location.row + 1, token.location.column + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::tokenize;
+
+    fn panic_message(f: impl FnOnce() + std::panic::UnwindSafe) -> String {
+        let payload = std::panic::catch_unwind(f).unwrap_err();
+        payload.downcast_ref::<String>().unwrap().clone()
+    }
+
+    #[test]
+    fn kinds_are_compared_without_locations() {
+        let tokens = tokenize("f(x)", (0, 0).into()).unwrap();
+        let moved = tokenize("\n\n  f ( x )", (4, 0).into()).unwrap();
+        let expected = [
+            TokenKind::Identifier("f".into()),
+            TokenKind::Symbol("(".into()),
+            TokenKind::Identifier("x".into()),
+            TokenKind::Symbol(")".into()),
+        ];
+        assert_ne!(tokens, moved);
+        assert_eq!(TokenKindSeq(&tokens), TokenKindSeq(&moved));
+        assert_eq!(TokenKindSeq(&moved), expected);
+        assert_eq!(TokenKindSeq(&moved), expected.to_vec());
+        assert_ne!(TokenKindSeq(&moved), &expected[..3]);
+        assert_eq!(
+            format!("{:?}", TokenKindSeq(&tokens[..1])),
+            "[Identifier(\"f\")]"
+        );
+        assert_kinds_eq(&moved, &expected);
+
+        let message = panic_message(|| assert_kinds_eq(&moved, &expected[..2]));
+        assert!(message.starts_with(
+            "expected 2 tokens, got 4, the first extra being identifier 'x' at 7:7\n"
+        ));
+        let message = panic_message(|| assert_kinds_eq(&moved[..3], &expected));
+        assert!(message.starts_with("expected 4 tokens, got 3, the first missing being symbol ')'"));
+        let mut wrong = expected.clone();
+        wrong[1] = TokenKind::Symbol("[".into());
+        let message = panic_message(move || assert_kinds_eq(&moved, &wrong));
+        assert!(message.starts_with("token 1 is symbol '(' at 7:5, expected symbol '['\n"));
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{error::Error, location::Location, token::Token, tokenizer::Tokenizer};
+
+/// A source of tokens, so a generated or hand-written lexer can stand in for `Tokenizer`
+/// and still produce the tokens, locations and errors the rest of the crate works with.
+pub trait Lexer {
+    /// Reads the next token, returning `None` at the end of the input and after an error.
+    fn next_token(&mut self) -> Option<Result<Token, Error>>;
+
+    /// Returns the location of the next character to read.
+    fn location(&self) -> Location;
+
+    /// Replaces the input, starting again from the location.
+    fn reset(&mut self, contents: &str, location: Location);
+
+    /// Reads the rest of the tokens, stopping at the first error.
+    fn tokenize_rest(&mut self) -> Result<Vec<Token>, Error> {
+        std::iter::from_fn(|| self.next_token()).collect()
+    }
+}
+
+impl Lexer for Tokenizer {
+    fn next_token(&mut self) -> Option<Result<Token, Error>> {
+        Tokenizer::next_token(self)
+    }
+
+    fn location(&self) -> Location {
+        Tokenizer::location(self).clone()
+    }
+
+    fn reset(&mut self, contents: &str, location: Location) {
+        Tokenizer::reset(self, contents, location)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::TokenizerConfig,
+        error::ErrorKind,
+        span::Span,
+        token::{Provenance, TokenKind},
+        tokenizer::tokenize,
+    };
+
+    /// Reads each run of characters between spaces as an identifier.
+    struct Words {
+        contents: String,
+        offset: usize,
+        location: Location,
+    }
+    impl Lexer for Words {
+        fn next_token(&mut self) -> Option<Result<Token, Error>> {
+            let rest = &self.contents[self.offset..];
+            let start = self.offset + (rest.len() - rest.trim_start_matches(' ').len());
+            self.location.column += start - self.offset;
+            let len = self.contents[start..]
+                .find(' ')
+                .unwrap_or(self.contents.len() - start);
+            if len == 0 {
+                return None;
+            }
+
+            let word = &self.contents[start..start + len];
+            let token = Token {
+                location: self.location.clone(),
+                span: Span::new(start, start + len),
+                contents: word.into(),
+                kind: TokenKind::Identifier(word.into()),
+                provenance: Provenance::Original,
+            };
+            self.offset = start + len;
+            self.location.column += len;
+            Some(Ok(token))
+        }
+
+        fn location(&self) -> Location {
+            self.location.clone()
+        }
+
+        fn reset(&mut self, contents: &str, location: Location) {
+            self.contents = contents.to_string();
+            self.offset = 0;
+            self.location = location;
+        }
+    }
+
+    fn kinds(lexer: &mut impl Lexer, contents: &str) -> Vec<TokenKind> {
+        lexer.reset(contents, (0, 0).into());
+        let tokens = lexer.tokenize_rest().unwrap();
+        tokens.into_iter().map(|token| token.kind).collect()
+    }
+
+    #[test]
+    fn lexers_can_be_swapped() {
+        let mut words = Words {
+            contents: String::new(),
+            offset: 0,
+            location: Location::default(),
+        };
+        let mut tokenizer = Tokenizer::with_config(TokenizerConfig::default());
+        assert_eq!(kinds(&mut words, "a  bc"), kinds(&mut tokenizer, "a  bc"));
+        assert_eq!(words.location(), (0, 5).into());
+
+        let source = "f(x, 1.5) # done\ny";
+        tokenizer.reset(source, (0, 0).into());
+        let first = Lexer::next_token(&mut tokenizer).unwrap().unwrap();
+        assert_eq!(first.kind, TokenKind::Identifier("f".into()));
+        assert_eq!(Lexer::location(&tokenizer), (0, 1).into());
+        let mut tokens = vec![first];
+        tokens.extend(tokenizer.tokenize_rest().unwrap());
+        assert_eq!(tokens, tokenize(source, (0, 0).into()).unwrap());
+
+        tokenizer.reset("a \"b", (0, 0).into());
+        assert!(Lexer::next_token(&mut tokenizer).unwrap().is_ok());
+        let error = Lexer::next_token(&mut tokenizer).unwrap().unwrap_err();
+        assert_eq!(error.kind, ErrorKind::UnclosedString);
+        assert!(Lexer::next_token(&mut tokenizer).is_none());
+    }
+}
@@ -1,9 +1,109 @@
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+#[cfg(feature = "bumpalo")]
+mod arena;
+#[cfg(feature = "chumsky")]
+mod chumsky;
+mod class;
+#[cfg(feature = "codespan-reporting")]
+mod codespan;
+#[cfg(feature = "termcolor")]
+mod color;
+mod compact;
+mod compiled;
+mod config;
+mod dfa;
 mod error;
+#[cfg(feature = "ffi")]
+mod ffi;
+mod include;
+mod interner;
+mod json;
+mod kinds;
+mod lexer;
+mod lines;
+mod lint;
 mod location;
+#[cfg(feature = "macros")]
+mod macros;
+#[cfg(feature = "miette")]
+mod miette;
+#[cfg(feature = "node")]
+mod node;
+#[cfg(feature = "nom")]
+mod nom;
+mod preprocess;
+#[cfg(feature = "proc-macro2")]
+mod proc_macro2;
+#[cfg(feature = "python")]
+mod python;
+mod search;
+#[cfg(feature = "security")]
+mod security;
+mod source_map;
+mod span;
+mod spec;
+#[cfg(feature = "spec-files")]
+mod spec_file;
+mod stats;
+mod table;
+mod text;
 mod token;
 mod tokenizer;
+mod trivia;
+mod warning;
+#[cfg(feature = "wasm")]
+mod wasm;
 
+#[cfg(feature = "arbitrary")]
+pub use crate::arbitrary::*;
+#[cfg(feature = "chumsky")]
+pub use crate::chumsky::*;
+#[cfg(feature = "miette")]
+pub use crate::miette::*;
+#[cfg(feature = "nom")]
+pub use crate::nom::*;
+#[cfg(feature = "proc-macro2")]
+pub use crate::proc_macro2::*;
+#[cfg(feature = "bumpalo")]
+pub use arena::*;
+pub use class::*;
+#[cfg(feature = "termcolor")]
+pub use color::*;
+pub use compact::*;
+pub use compiled::*;
+pub use config::*;
 pub use error::*;
+#[cfg(feature = "ffi")]
+pub use ffi::*;
+pub use include::*;
+pub use interner::*;
+pub use json::*;
+pub use kinds::*;
+pub use lexer::*;
+pub use lines::*;
+pub use lint::*;
 pub use location::*;
+#[cfg(feature = "macros")]
+pub use macros::*;
+#[cfg(feature = "node")]
+pub use node::*;
+pub use preprocess::*;
+#[cfg(feature = "python")]
+pub use python::*;
+pub use search::*;
+#[cfg(feature = "security")]
+pub use security::*;
+pub use source_map::*;
+pub use span::*;
+#[cfg(feature = "spec-files")]
+pub use spec_file::*;
+pub use stats::*;
+pub use table::*;
+pub use text::*;
 pub use token::*;
 pub use tokenizer::*;
+pub use trivia::*;
+pub use warning::*;
+#[cfg(feature = "wasm")]
+pub use wasm::*;
@@ -0,0 +1,112 @@
+use std::io::BufRead;
+
+use crate::{
+    config::TokenizerConfig,
+    error::{Error, ErrorKind},
+    token::Token,
+    tokenizer::Tokenizer,
+};
+
+/// Tokenizes a reader a line at a time, yielding each zero based row with its tokens, so
+/// line-oriented inputs too large to hold in memory can be processed as a stream.
+///
+/// Each line is tokenized on its own, so strings and quoted identifiers must close on the
+/// line they open on. Tokens are located at their row and their spans are byte offsets in
+/// the line. A line that fails to tokenize yields its error and the next line carries on,
+/// but the iterator ends after an error reading the input, such as invalid UTF-8.
+pub struct LineTokens<R> {
+    reader: R,
+    tokenizer: Tokenizer,
+    line: String,
+    row: usize,
+    done: bool,
+}
+impl<R: BufRead> LineTokens<R> {
+    pub fn new(reader: R, config: &TokenizerConfig) -> Self {
+        Self {
+            reader,
+            tokenizer: Tokenizer::with_config(config.clone()),
+            line: String::new(),
+            row: 0,
+            done: false,
+        }
+    }
+}
+impl<R: BufRead> Iterator for LineTokens<R> {
+    type Item = Result<(usize, Vec<Token>), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        self.line.clear();
+        let row = self.row;
+        match self.reader.read_line(&mut self.line) {
+            Ok(0) => {
+                self.done = true;
+                return None;
+            }
+            Ok(_) => self.row += 1,
+            Err(error) => {
+                self.done = true;
+                return Some(Err(Error {
+                    kind: ErrorKind::FailedRead,
+                    message: format!("Couldn't read line {}: {}", row + 1, error),
+                    location: (row, 0).into(),
+                    span: Default::default(),
+                    source_line: None,
+                    mismatch: None,
+                    label: None,
+                    args: Box::new([
+                        ("line", (row + 1).to_string()),
+                        ("reason", error.to_string()),
+                    ]),
+                }));
+            }
+        }
+
+        let line = self.line.strip_suffix('\n').unwrap_or(&self.line);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        self.tokenizer.reset(line, (row, 0).into());
+        Some(self.tokenizer.tokens().map(|tokens| (row, tokens.to_vec())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::token::TokenKind;
+
+    #[test]
+    fn lines_are_tokenized_independently() {
+        let input = "a = 1\r\nb = \"x\ny # c\n\n";
+        let mut lines = LineTokens::new(Cursor::new(input), &TokenizerConfig::default());
+
+        let (row, tokens) = lines.next().unwrap().unwrap();
+        assert_eq!(row, 0);
+        assert_eq!(tokens.len(), 3);
+
+        let error = lines.next().unwrap().unwrap_err();
+        assert_eq!(error.kind, ErrorKind::UnclosedString);
+        assert_eq!(error.location, (1, 4).into());
+
+        let (row, tokens) = lines.next().unwrap().unwrap();
+        assert_eq!(row, 2);
+        assert_eq!(tokens[0].kind, TokenKind::Identifier("y".into()));
+        assert_eq!(tokens[0].location, (2, 0).into());
+
+        assert_eq!(lines.next().unwrap().unwrap(), (3, vec![]));
+        assert!(lines.next().is_none());
+
+        let input: &[u8] = b"a\n\xff\nb\n";
+        let mut lines = LineTokens::new(input, &TokenizerConfig::default());
+        assert!(lines.next().unwrap().is_ok());
+        let error = lines.next().unwrap().unwrap_err();
+        assert_eq!(error.kind, ErrorKind::FailedRead);
+        assert!(error.message.starts_with("Couldn't read line 2: "));
+        assert!(lines.next().is_none());
+    }
+}
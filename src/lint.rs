@@ -0,0 +1,297 @@
+use crate::{
+    location::Location,
+    token::{Token, TokenKind},
+    warning::Warning,
+};
+
+/// How seriously a lint rule's findings should be treated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintLevel {
+    /// The rule is not run.
+    Allow,
+    /// Findings are reported but shouldn't fail a check.
+    Warn,
+    /// Findings should fail a check.
+    Deny,
+}
+
+/// What a lint rule gets to look at.
+pub struct LintContext<'a> {
+    /// The raw source that was tokenized.
+    pub source: &'a str,
+    /// The tokens produced from the source.
+    pub tokens: &'a [Token],
+    /// The location the source was tokenized from.
+    pub location: Location,
+}
+impl LintContext<'_> {
+    /// Returns the location of a zero based line and column in the source.
+    pub fn location_at(&self, line: usize, column: usize) -> Location {
+        let mut location = self.location.clone();
+        if line == 0 {
+            location.column += column;
+        } else {
+            location.row += line;
+            location.column = column;
+        }
+        location
+    }
+}
+
+/// A lexical check run against a token stream and its source.
+pub trait LintRule {
+    /// A short, stable name for the rule, such as `trailing_whitespace`.
+    fn name(&self) -> &'static str;
+
+    /// Returns a warning for every problem found.
+    fn check(&self, context: &LintContext) -> Vec<Warning>;
+}
+
+/// A problem found by a lint rule.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lint {
+    pub rule: &'static str,
+    pub level: LintLevel,
+    pub message: String,
+    pub location: Location,
+}
+
+/// Runs a set of lint rules, each with its own level.
+#[derive(Default)]
+pub struct Linter {
+    rules: Vec<(Box<dyn LintRule>, LintLevel)>,
+}
+impl Linter {
+    /// Creates a linter with no rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a linter with every built-in rule set to `LintLevel::Warn`.
+    pub fn with_builtin_rules() -> Self {
+        let mut linter = Self::new();
+        linter.add_rule(TrailingWhitespace, LintLevel::Warn);
+        linter.add_rule(MixedIndentation, LintLevel::Warn);
+        linter.add_rule(LongToken::default(), LintLevel::Warn);
+        linter.add_rule(NonAsciiString, LintLevel::Warn);
+        linter
+    }
+
+    /// Adds a rule at the given level.
+    pub fn add_rule(&mut self, rule: impl LintRule + 'static, level: LintLevel) {
+        self.rules.push((Box::new(rule), level));
+    }
+
+    /// Changes the level of the rule with the given name. Returns false if there is no such rule.
+    pub fn set_level(&mut self, name: &str, level: LintLevel) -> bool {
+        let mut found = false;
+        for (rule, rule_level) in self.rules.iter_mut() {
+            if rule.name() == name {
+                *rule_level = level;
+                found = true;
+            }
+        }
+        found
+    }
+
+    /// Runs every rule that isn't allowed, returning their findings.
+    pub fn check(&self, source: &str, location: Location, tokens: &[Token]) -> Vec<Lint> {
+        let context = LintContext {
+            source,
+            tokens,
+            location,
+        };
+
+        let mut lints = Vec::new();
+        for (rule, level) in self.rules.iter() {
+            if *level == LintLevel::Allow {
+                continue;
+            }
+
+            lints.extend(rule.check(&context).into_iter().map(|warning| Lint {
+                rule: rule.name(),
+                level: *level,
+                message: warning.message,
+                location: warning.location,
+            }));
+        }
+        lints
+    }
+}
+
+/// Flags lines that end with spaces or tabs.
+pub struct TrailingWhitespace;
+impl LintRule for TrailingWhitespace {
+    fn name(&self) -> &'static str {
+        "trailing_whitespace"
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<Warning> {
+        let mut warnings = Vec::new();
+        for (line_number, line) in context.source.lines().enumerate() {
+            let trimmed = line.trim_end_matches([' ', '\t']);
+            if trimmed.len() != line.len() {
+                warnings.push(Warning {
+                    message: "Trailing whitespace".to_string(),
+                    location: context.location_at(line_number, trimmed.chars().count()),
+                });
+            }
+        }
+        warnings
+    }
+}
+
+/// Flags lines indented with both tabs and spaces.
+pub struct MixedIndentation;
+impl LintRule for MixedIndentation {
+    fn name(&self) -> &'static str {
+        "mixed_indentation"
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<Warning> {
+        let mut warnings = Vec::new();
+        for (line_number, line) in context.source.lines().enumerate() {
+            let indentation = &line[..line.len() - line.trim_start_matches([' ', '\t']).len()];
+            if indentation.contains(' ') && indentation.contains('\t') {
+                warnings.push(Warning {
+                    message: "Indentation mixes tabs and spaces".to_string(),
+                    location: context.location_at(line_number, 0),
+                });
+            }
+        }
+        warnings
+    }
+}
+
+/// Flags tokens longer than a maximum number of characters.
+pub struct LongToken {
+    pub max_length: usize,
+}
+impl Default for LongToken {
+    fn default() -> Self {
+        Self { max_length: 100 }
+    }
+}
+impl LintRule for LongToken {
+    fn name(&self) -> &'static str {
+        "long_token"
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<Warning> {
+        context
+            .tokens
+            .iter()
+            .filter_map(|token| {
+                let length = token.contents.chars().count();
+                (length > self.max_length).then(|| Warning {
+                    message: format!(
+                        "Token is {} characters long, the maximum is {}",
+                        length, self.max_length
+                    ),
+                    location: token.location.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Flags string literals that contain non-ASCII characters.
+pub struct NonAsciiString;
+impl LintRule for NonAsciiString {
+    fn name(&self) -> &'static str {
+        "non_ascii_string"
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<Warning> {
+        context
+            .tokens
+            .iter()
+            .filter_map(|token| match &token.kind {
+                TokenKind::StringLiteral(s) if !s.is_ascii() => Some(Warning {
+                    message: "String literal contains non-ASCII characters".to_string(),
+                    location: token.location.clone(),
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Tokenizer;
+
+    fn lint(linter: &Linter, source: &str) -> Vec<Lint> {
+        let tokens = Tokenizer::tokenize(source, (0, 0).into()).unwrap();
+        linter.check(source, (0, 0).into(), &tokens)
+    }
+
+    #[test]
+    fn builtin_rules() {
+        let linter = Linter::with_builtin_rules();
+        let source = "x = \"caf\u{e9}\"  \n \tindented";
+        let expected = vec![
+            Lint {
+                rule: "trailing_whitespace",
+                level: LintLevel::Warn,
+                message: "Trailing whitespace".to_string(),
+                location: (0, 10).into(),
+            },
+            Lint {
+                rule: "mixed_indentation",
+                level: LintLevel::Warn,
+                message: "Indentation mixes tabs and spaces".to_string(),
+                location: (1, 0).into(),
+            },
+            Lint {
+                rule: "non_ascii_string",
+                level: LintLevel::Warn,
+                message: "String literal contains non-ASCII characters".to_string(),
+                location: (0, 4).into(),
+            },
+        ];
+        assert_eq!(expected, lint(&linter, source));
+    }
+
+    #[test]
+    fn levels_can_be_changed() {
+        let mut linter = Linter::with_builtin_rules();
+        assert!(linter.set_level("trailing_whitespace", LintLevel::Deny));
+        assert!(linter.set_level("non_ascii_string", LintLevel::Allow));
+        assert!(!linter.set_level("missing", LintLevel::Allow));
+
+        let lints = lint(&linter, "\"caf\u{e9}\" ");
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].rule, "trailing_whitespace");
+        assert_eq!(lints[0].level, LintLevel::Deny);
+    }
+
+    #[test]
+    fn user_rules() {
+        struct NoFoo;
+        impl LintRule for NoFoo {
+            fn name(&self) -> &'static str {
+                "no_foo"
+            }
+
+            fn check(&self, context: &LintContext) -> Vec<Warning> {
+                context
+                    .tokens
+                    .iter()
+                    .filter(|token| token.kind == TokenKind::Identifier("foo".into()))
+                    .map(|token| Warning {
+                        message: "Don't use foo".to_string(),
+                        location: token.location.clone(),
+                    })
+                    .collect()
+            }
+        }
+
+        let mut linter = Linter::new();
+        linter.add_rule(NoFoo, LintLevel::Deny);
+        let lints = lint(&linter, "bar foo");
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].location, (0, 4).into());
+    }
+}
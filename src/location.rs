@@ -1,25 +1,55 @@
-use std::path::PathBuf;
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
 pub struct Location {
     pub row: usize,
     pub column: usize,
-    pub path: Option<PathBuf>,
+    /// The file the location is in. It's shared so that cloning a location, which happens
+    /// for every token, doesn't copy the path.
+    pub path: Option<Arc<Path>>,
 }
 
 impl Location {
     pub fn new(column: usize, row: usize, path: Option<PathBuf>) -> Self {
-        Self { column, row, path }
-    }
-}
-impl Default for Location {
-    fn default() -> Self {
         Self {
-            row: Default::default(),
-            column: Default::default(),
-            path: Default::default(),
+            column,
+            row,
+            path: path.map(Arc::from),
         }
     }
+
+    /// Returns the byte offset of this location in `source`, treating `row` and `column`
+    /// as zero based line and character indexes.
+    pub fn byte_offset(&self, source: &str) -> Option<usize> {
+        let mut offset = 0;
+        for (row, line) in source.split('\n').enumerate() {
+            if row == self.row {
+                let column = line
+                    .char_indices()
+                    .map(|(i, _)| i)
+                    .chain(std::iter::once(line.len()))
+                    .nth(self.column)?;
+                return Some(offset + column);
+            }
+            offset += line.len() + 1;
+        }
+        None
+    }
+
+    /// Returns the byte range of the character at this location, or an empty range at the
+    /// end of `source` if the location is out of bounds.
+    pub fn byte_range(&self, source: &str) -> Range<usize> {
+        let start = self.byte_offset(source).unwrap_or(source.len());
+        let end = source[start..]
+            .chars()
+            .next()
+            .map_or(start, |c| start + c.len_utf8());
+        start..end
+    }
 }
 impl From<(usize, usize)> for Location {
     fn from((row, column): (usize, usize)) -> Self {
@@ -32,6 +62,15 @@ impl From<(usize, usize)> for Location {
 }
 impl From<PathBuf> for Location {
     fn from(path: PathBuf) -> Self {
+        Self {
+            row: 0,
+            column: 0,
+            path: Some(path.into()),
+        }
+    }
+}
+impl From<Arc<Path>> for Location {
+    fn from(path: Arc<Path>) -> Self {
         Self {
             row: 0,
             column: 0,
@@ -39,3 +78,28 @@ impl From<PathBuf> for Location {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn byte_offset_works() {
+        let source = "ab\n\u{e9}cd";
+        assert_eq!(Some(0), Location::from((0, 0)).byte_offset(source));
+        assert_eq!(Some(2), Location::from((0, 2)).byte_offset(source));
+        assert_eq!(Some(5), Location::from((1, 1)).byte_offset(source));
+        assert_eq!(None, Location::from((1, 4)).byte_offset(source));
+        assert_eq!(None, Location::from((2, 0)).byte_offset(source));
+    }
+
+    #[test]
+    fn clones_share_the_path() {
+        let location = Location::from(PathBuf::from("a.txt"));
+        let clone = location.clone();
+        assert!(Arc::ptr_eq(
+            location.path.as_ref().unwrap(),
+            clone.path.as_ref().unwrap()
+        ));
+    }
+}
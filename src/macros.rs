@@ -0,0 +1,131 @@
+pub use tokenizer_macros::{tokenize, Tokenize};
+
+use crate::{
+    config::TokenizerConfig,
+    error::{Error, ErrorKind},
+    location::Location,
+    span::Span,
+    token::{Comment, CommentStyle, Directive, Provenance, Token, TokenKind},
+    tokenizer::Tokenizer,
+};
+
+/// A user defined token type built from this crate's tokens, usually with
+/// `#[derive(Tokenize)]`.
+pub trait Tokenize: Sized {
+    /// Returns the value for the token, or `None` if the type has no value for it.
+    fn from_token(token: &Token) -> Option<Self>;
+
+    /// Tokenizes the contents, pairing each value with the token it came from.
+    fn tokenize(contents: &str, location: Location) -> Result<Vec<(Self, Token)>, Error> {
+        Self::tokenize_with_config(contents, location, &TokenizerConfig::default())
+    }
+
+    /// Tokenizes the contents with the config, erroring on the first token the type has no
+    /// value for.
+    fn tokenize_with_config(
+        contents: &str,
+        location: Location,
+        config: &TokenizerConfig,
+    ) -> Result<Vec<(Self, Token)>, Error> {
+        Tokenizer::tokenize_with_config(contents, location, config)?
+            .into_iter()
+            .map(|token| match Self::from_token(&token) {
+                Some(value) => Ok((value, token)),
+                None => Err(Error {
+                    kind: ErrorKind::UnexpectedToken,
+                    message: format!("Unexpected {}", token.kind),
+                    location: token.location.clone(),
+                    span: token.span,
+                    source_line: None,
+                    mismatch: None,
+                    label: None,
+                    args: Box::new([("found", token.kind.to_string())]),
+                }),
+            })
+            .collect()
+    }
+}
+
+/// A token produced at compile time by the `tokenize!` macro.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StaticToken {
+    pub location: Location,
+    pub span: Span,
+    pub contents: &'static str,
+    pub kind: StaticTokenKind,
+}
+impl StaticToken {
+    /// Returns an owned copy of the token.
+    pub fn to_token(&self) -> Token {
+        let kind = match self.kind {
+            StaticTokenKind::StringLiteral(s) => TokenKind::StringLiteral(s.into()),
+            StaticTokenKind::Comment { style, raw } => {
+                TokenKind::Comment(Box::new(Comment::new(style, raw)))
+            }
+            StaticTokenKind::Identifier(s) => TokenKind::Identifier(s.into()),
+            StaticTokenKind::Symbol(s) => TokenKind::Symbol(s.into()),
+            StaticTokenKind::IntegerLiteral(i) => TokenKind::IntegerLiteral(i),
+            StaticTokenKind::FloatLiteral(f) => TokenKind::FloatLiteral(f),
+            StaticTokenKind::DecimalLiteral(s) => TokenKind::DecimalLiteral(s.into()),
+            StaticTokenKind::PercentLiteral(s) => TokenKind::PercentLiteral(s.into()),
+            StaticTokenKind::DateTimeLiteral(s) => TokenKind::DateTimeLiteral(s.into()),
+            StaticTokenKind::ColorLiteral(s) => TokenKind::ColorLiteral(s.into()),
+            StaticTokenKind::Attribute(s) => TokenKind::Attribute(s.into()),
+            StaticTokenKind::Variable { sigil, name } => TokenKind::Variable {
+                sigil,
+                name: name.into(),
+            },
+            StaticTokenKind::Directive { name, arguments } => {
+                TokenKind::Directive(Box::new(Directive {
+                    name: name.into(),
+                    arguments: arguments.into(),
+                }))
+            }
+            StaticTokenKind::UnitLiteral { value, unit } => TokenKind::UnitLiteral {
+                value: value.into(),
+                unit: unit.into(),
+            },
+            StaticTokenKind::Unknown(s) => TokenKind::Unknown(s.into()),
+        };
+
+        Token {
+            location: self.location.clone(),
+            span: self.span,
+            contents: self.contents.into(),
+            kind,
+            provenance: Provenance::Original,
+        }
+    }
+}
+
+/// The kind of a `StaticToken`, mirroring `TokenKind` with static strings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StaticTokenKind {
+    StringLiteral(&'static str),
+    Comment {
+        style: CommentStyle,
+        raw: &'static str,
+    },
+    Identifier(&'static str),
+    Symbol(&'static str),
+    IntegerLiteral(i128),
+    FloatLiteral(f64),
+    DecimalLiteral(&'static str),
+    PercentLiteral(&'static str),
+    DateTimeLiteral(&'static str),
+    ColorLiteral(&'static str),
+    Attribute(&'static str),
+    Variable {
+        sigil: char,
+        name: &'static str,
+    },
+    Directive {
+        name: &'static str,
+        arguments: &'static str,
+    },
+    UnitLiteral {
+        value: &'static str,
+        unit: &'static str,
+    },
+    Unknown(&'static str),
+}
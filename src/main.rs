@@ -0,0 +1,468 @@
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use tokenizer::{
+    file_stats_to_json, Error, LintLevel, Linter, Location, Pattern, Token, TokenKindTag,
+    TokenStats, Tokenizer, TokenizerConfig,
+};
+
+const USAGE: &str = "\
+Usage: tokenizer <command> [options] <paths>...
+
+Commands:
+  check            Print the errors, warnings and lints in each file
+  stats            Print token statistics for each file and in total
+  grep <pattern>   Print where the token pattern matches, such as `ident \"(\" ... \")\"`
+
+Options:
+  --json         Print JSON instead of text, for stats
+  --watch        Check again whenever the files change, for check
+  --spec <path>  Tokenize with the config in a .toml or .json spec file
+  -h, --help
+";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("check") => Options::parse(&args[1..])
+            .and_then(|options| options.only("check", &["--watch"]))
+            .and_then(|options| check(&options)),
+        Some("stats") => Options::parse(&args[1..])
+            .and_then(|options| options.only("stats", &["--json"]))
+            .and_then(|options| stats(&options)),
+        Some("grep") => match args.get(1) {
+            Some(pattern) => Options::parse(&args[2..])
+                .and_then(|options| options.only("grep", &[]))
+                .and_then(|options| grep(pattern, &options)),
+            None => Err("missing pattern".to_string()),
+        },
+        Some("-h" | "--help") => {
+            print!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Some(command) => Err(format!("unknown command '{}'", command)),
+        None => Err("missing command".to_string()),
+    };
+
+    match result {
+        Ok(code) => code,
+        Err(message) => {
+            eprintln!("tokenizer: {}\n\n{}", message, USAGE);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// The options shared by every command.
+#[derive(Debug, Default, PartialEq)]
+struct Options {
+    json: bool,
+    watch: bool,
+    spec: Option<PathBuf>,
+    paths: Vec<PathBuf>,
+}
+impl Options {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut options = Self::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--json" => options.json = true,
+                "--watch" => options.watch = true,
+                "--spec" => match args.next() {
+                    Some(path) => options.spec = Some(PathBuf::from(path)),
+                    None => return Err("--spec needs a path".to_string()),
+                },
+                option if option.starts_with('-') => {
+                    return Err(format!("unknown option '{}'", option))
+                }
+                path => options.paths.push(PathBuf::from(path)),
+            }
+        }
+        if options.paths.is_empty() {
+            return Err("missing paths".to_string());
+        }
+        Ok(options)
+    }
+
+    /// Returns an error if an option outside of `allowed` was given to the command.
+    fn only(self, command: &str, allowed: &[&str]) -> Result<Self, String> {
+        for (option, given) in [("--json", self.json), ("--watch", self.watch)] {
+            if given && !allowed.contains(&option) {
+                return Err(format!("{} doesn't support {}", command, option));
+            }
+        }
+        Ok(self)
+    }
+
+    /// Loads the config from the `--spec` file, or the default config without one.
+    fn config(&self) -> Result<TokenizerConfig, String> {
+        match &self.spec {
+            None => Ok(TokenizerConfig::default()),
+            #[cfg(feature = "spec-files")]
+            Some(path) => TokenizerConfig::from_spec_file(path)
+                .map_err(|error| format!("{}: {}", path.display(), error)),
+            #[cfg(not(feature = "spec-files"))]
+            Some(_) => {
+                Err("--spec needs tokenizer to be built with the spec-files feature".to_string())
+            }
+        }
+    }
+}
+
+/// Prints the diagnostics in each file, checking again on every change with `--watch`.
+fn check(options: &Options) -> Result<ExitCode, String> {
+    let config = options.config()?;
+    if !options.watch {
+        return Ok(check_files(&options.paths, &config));
+    }
+
+    #[cfg(feature = "watch")]
+    {
+        watch(&options.paths, || {
+            check_files(&options.paths, &config);
+        })
+        .map_err(|error| error.to_string())?;
+        Ok(ExitCode::SUCCESS)
+    }
+    #[cfg(not(feature = "watch"))]
+    Err("--watch needs tokenizer to be built with the watch feature".to_string())
+}
+
+/// Prints the errors, warnings and lints in the files along with a summary, failing if
+/// there are errors or denied lints.
+fn check_files(paths: &[PathBuf], config: &TokenizerConfig) -> ExitCode {
+    let linter = Linter::with_builtin_rules();
+    let (mut files, mut errors, mut warnings) = (0, 0, 0);
+    for path in source_files(paths) {
+        files += 1;
+        let source = match std::fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(error) => {
+                eprintln!("tokenizer: {}: {}", path.display(), error);
+                errors += 1;
+                continue;
+            }
+        };
+        let location = Location::new(0, 0, Some(path.clone()));
+        match Tokenizer::tokenize_with_warnings(&source, location.clone(), config) {
+            Ok((tokens, found)) => {
+                for warning in found {
+                    print_diagnostic("warning", &warning.location, &warning.message);
+                    warnings += 1;
+                }
+                for lint in linter.check(&source, location, &tokens) {
+                    let (level, count) = match lint.level {
+                        LintLevel::Deny => ("error", &mut errors),
+                        _ => ("warning", &mut warnings),
+                    };
+                    let level = format!("{}[{}]", level, lint.rule);
+                    print_diagnostic(&level, &lint.location, &lint.message);
+                    *count += 1;
+                }
+            }
+            Err(error) => {
+                print_error(&error, &source);
+                errors += 1;
+            }
+        }
+    }
+
+    println!(
+        "checked {} files: {} errors, {} warnings",
+        files, errors, warnings
+    );
+    if errors > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Prints the rendered error to stderr, in color if built with the termcolor feature.
+fn print_error(error: &Error, source: &str) {
+    #[cfg(feature = "termcolor")]
+    if error.eprint_colored(source).is_ok() {
+        return;
+    }
+    eprint!("{}", error.render(source));
+}
+
+fn print_diagnostic(level: &str, location: &Location, message: &str) {
+    let path = location
+        .path
+        .as_ref()
+        .map_or(String::new(), |path| format!("{}:", path.display()));
+    println!(
+        "{}{}:{}: {}: {}",
+        path,
+        location.row + 1,
+        location.column + 1,
+        level,
+        message
+    );
+}
+
+/// Runs `run`, then runs it again after each batch of changes to the paths, until watching
+/// fails.
+#[cfg(feature = "watch")]
+fn watch(paths: &[PathBuf], mut run: impl FnMut()) -> notify::Result<()> {
+    use notify::{EventKind, RecursiveMode, Watcher};
+    use std::{sync::mpsc, time::Duration};
+
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    for path in paths {
+        watcher.watch(path, RecursiveMode::Recursive)?;
+    }
+
+    run();
+    for event in &receiver {
+        if let EventKind::Access(_) = event?.kind {
+            continue;
+        }
+        // Editors often save with several events, so wait for them to settle.
+        std::thread::sleep(Duration::from_millis(50));
+        while receiver.try_recv().is_ok() {}
+        println!();
+        run();
+    }
+    Ok(())
+}
+
+/// Prints the stats of each file and the total across them.
+fn stats(options: &Options) -> Result<ExitCode, String> {
+    let config = options.config()?;
+    let mut failed = false;
+    let files: Vec<_> = tokenize_files(&options.paths, &config, &mut failed)
+        .into_iter()
+        .map(|(path, _, tokens)| (path, TokenStats::from(tokens.as_slice())))
+        .collect();
+    let code = if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    };
+
+    if options.json {
+        println!("{}", file_stats_to_json(&files));
+        return Ok(code);
+    }
+
+    let mut total = TokenStats::default();
+    for (path, stats) in &files {
+        print_stats(&path.display().to_string(), stats);
+        total.combine(stats);
+    }
+    if files.len() > 1 {
+        print_stats(&format!("total ({} files)", files.len()), &total);
+    }
+    Ok(code)
+}
+
+/// Prints the location and line of each match of the pattern, exiting like grep does:
+/// with 0 if something matched, 1 if nothing did and 2 on errors.
+fn grep(pattern: &str, options: &Options) -> Result<ExitCode, String> {
+    let config = options.config()?;
+    let pattern = match Pattern::parse(pattern) {
+        Ok(pattern) => pattern,
+        Err(error) => {
+            print_error(&error, pattern);
+            return Ok(ExitCode::from(2));
+        }
+    };
+
+    let mut matched = false;
+    let mut failed = false;
+    for (path, source, tokens) in tokenize_files(&options.paths, &config, &mut failed) {
+        for line in match_lines(&pattern, &path, &source, &tokens) {
+            println!("{}", line);
+            matched = true;
+        }
+    }
+
+    Ok(match (failed, matched) {
+        (true, _) => ExitCode::from(2),
+        (false, true) => ExitCode::SUCCESS,
+        (false, false) => ExitCode::FAILURE,
+    })
+}
+
+/// Returns a `path:line:column:source line` line for each match, with one based numbers.
+fn match_lines(pattern: &Pattern, path: &Path, source: &str, tokens: &[Token]) -> Vec<String> {
+    let lines: Vec<&str> = source.lines().collect();
+    pattern
+        .find_all(tokens)
+        .into_iter()
+        .map(|range| {
+            let location = &tokens[range.start].location;
+            format!(
+                "{}:{}:{}:{}",
+                path.display(),
+                location.row + 1,
+                location.column + 1,
+                lines.get(location.row).unwrap_or(&"")
+            )
+        })
+        .collect()
+}
+
+fn print_stats(name: &str, stats: &TokenStats) {
+    println!(
+        "{}: {} tokens, {} lines, {:.1}% comments, longest identifier {}",
+        name,
+        stats.total,
+        stats.lines,
+        stats.comment_ratio * 100.0,
+        stats.max_identifier_length
+    );
+    let mut counts: Vec<(&TokenKindTag, &usize)> = stats.counts.iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(a.1));
+    for (tag, count) in counts {
+        println!("  {:<16} {}", tag.name(), count);
+    }
+}
+
+/// Reads and tokenizes the files at the paths, printing any errors and setting `failed`
+/// for files that couldn't be read or tokenized.
+fn tokenize_files(
+    paths: &[PathBuf],
+    config: &TokenizerConfig,
+    failed: &mut bool,
+) -> Vec<(PathBuf, String, Vec<Token>)> {
+    let mut files = Vec::new();
+    for path in source_files(paths) {
+        let source = match std::fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(error) => {
+                eprintln!("tokenizer: {}: {}", path.display(), error);
+                *failed = true;
+                continue;
+            }
+        };
+        let location = Location::new(0, 0, Some(path.clone()));
+        match Tokenizer::tokenize_with_config(&source, location, config) {
+            Ok(tokens) => files.push((path, source, tokens)),
+            Err(error) => {
+                print_error(&error, &source);
+                *failed = true;
+            }
+        }
+    }
+    files
+}
+
+/// Returns the files at the paths, with directories searched recursively, skipping hidden
+/// entries.
+fn source_files(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            collect_files(path, &mut files);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    files
+}
+
+fn collect_files(directory: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return;
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            !path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+        })
+        .collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            collect_files(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn options_parse() {
+        let args = ["--json".to_string(), "src".to_string()];
+        assert_eq!(
+            Options::parse(&args),
+            Ok(Options {
+                json: true,
+                watch: false,
+                spec: None,
+                paths: vec![PathBuf::from("src")],
+            })
+        );
+        let args = [
+            "--spec".to_string(),
+            "lang.toml".to_string(),
+            "src".to_string(),
+        ];
+        let options = Options::parse(&args).unwrap();
+        assert_eq!(options.spec, Some(PathBuf::from("lang.toml")));
+        assert_eq!(options.paths, [PathBuf::from("src")]);
+        assert!(Options::parse(&["src".to_string(), "--spec".to_string()]).is_err());
+        assert!(Options::parse(&["--jsn".to_string()]).is_err());
+        assert!(Options::parse(&[]).is_err());
+
+        let args = [
+            "--json".to_string(),
+            "--watch".to_string(),
+            "src".to_string(),
+        ];
+        let options = Options::parse(&args).unwrap();
+        assert!(options.only("check", &["--watch"]).is_err());
+        let options = Options::parse(&args[1..]).unwrap();
+        assert!(options.only("check", &["--watch"]).is_ok());
+    }
+
+    #[cfg(feature = "spec-files")]
+    #[test]
+    fn spec_files_configure_every_command() {
+        let directory = std::env::temp_dir().join(format!("tokenizer-spec-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let spec = directory.join("lang.toml");
+        let source = directory.join("input.txt");
+        std::fs::write(&spec, "percents = true").unwrap();
+        std::fs::write(&source, "50%").unwrap();
+
+        let mut options = Options::parse(&[source.display().to_string()]).unwrap();
+        assert_eq!(options.config().unwrap(), TokenizerConfig::default());
+        options.spec = Some(spec.clone());
+        let config = options.config().unwrap();
+        assert!(config.percents);
+
+        let mut failed = false;
+        let files = tokenize_files(&options.paths, &config, &mut failed);
+        assert!(!failed);
+        assert_eq!(files[0].2[0].kind.tag(), TokenKindTag::PercentLiteral);
+
+        options.spec = Some(directory.join("missing.toml"));
+        assert!(options.config().is_err());
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn grep_prints_matches_with_their_lines() {
+        let source = "a = f(1)\n  b = g(h(2), 3)";
+        let tokens = Tokenizer::tokenize(source, (0, 0).into()).unwrap();
+        let pattern = Pattern::parse(r#"ident "(" ... ")""#).unwrap();
+        assert_eq!(
+            match_lines(&pattern, Path::new("x.cfg"), source, &tokens),
+            ["x.cfg:1:5:a = f(1)", "x.cfg:2:7:  b = g(h(2), 3)"]
+        );
+    }
+}
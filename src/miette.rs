@@ -0,0 +1,84 @@
+use std::fmt::Display;
+
+use miette::{Diagnostic, LabeledSpan, NamedSource, SourceCode};
+
+use crate::error::Error;
+
+impl Diagnostic for Error {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(self.kind.code()))
+    }
+}
+
+/// An error paired with the source it came from, so miette can show the offending code.
+#[derive(Debug)]
+pub struct ErrorWithSource {
+    pub error: Error,
+    pub source: NamedSource<String>,
+}
+
+impl Error {
+    /// Attaches the source the error came from. The source is named after the error's path,
+    /// if it has one.
+    pub fn with_source(self, source: &str) -> ErrorWithSource {
+        let name = match &self.location.path {
+            Some(path) => path.display().to_string(),
+            None => String::new(),
+        };
+        ErrorWithSource {
+            error: self,
+            source: NamedSource::new(name, source.to_string()),
+        }
+    }
+}
+
+impl Display for ErrorWithSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+impl std::error::Error for ErrorWithSource {}
+
+impl Diagnostic for ErrorWithSource {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.error.code()
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.source)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let range = self.error.byte_range(self.source.inner());
+        let primary = LabeledSpan::new(Some(self.error.message.clone()), range.start, range.len());
+        let secondary = self.error.label.iter().map(|label| {
+            LabeledSpan::new(
+                Some(label.message.clone()),
+                label.span.start,
+                label.span.len(),
+            )
+        });
+        Some(Box::new(std::iter::once(primary).chain(secondary)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Tokenizer;
+
+    #[test]
+    fn error_with_source_has_label() {
+        let source = "x = 1\ny = \"abc";
+        let error = Tokenizer::tokenize(source, (0, 0).into())
+            .unwrap_err()
+            .with_source(source);
+        let labels: Vec<LabeledSpan> = error.labels().unwrap().collect();
+        assert_eq!(labels.len(), 1);
+        assert_eq!(labels[0].offset(), 10);
+        assert_eq!(labels[0].len(), 4);
+        assert_eq!(labels[0].label(), Some("Unclosed string"));
+        assert!(error.source_code().is_some());
+        assert_eq!(error.code().unwrap().to_string(), "T0001");
+    }
+}
@@ -0,0 +1,109 @@
+use std::path::PathBuf;
+
+use napi::{Either, Error as NapiError, Status};
+use napi_derive::napi;
+
+use crate::{
+    location::Location,
+    token::{Token, TokenKind},
+    tokenizer::Tokenizer,
+};
+
+/// The largest integer a JavaScript number holds exactly, `Number.MAX_SAFE_INTEGER`.
+const MAX_SAFE_INTEGER: u128 = (1 << 53) - 1;
+
+/// A token as seen from Node. Lines and columns are zero based, and `start` and `end` are
+/// byte offsets in the source.
+#[napi(object)]
+pub struct NodeToken {
+    /// The name of the kind, such as `identifier`.
+    pub kind: String,
+    pub contents: String,
+    /// A number for integer and float literals and a string otherwise, including for
+    /// decimals, percents, units and integers a JavaScript number can't hold exactly.
+    pub value: Either<String, f64>,
+    pub file: Option<String>,
+    pub line: u32,
+    pub column: u32,
+    pub start: u32,
+    pub end: u32,
+}
+impl From<Token> for NodeToken {
+    fn from(token: Token) -> Self {
+        let value = match &token.kind {
+            TokenKind::StringLiteral(s)
+            | TokenKind::Identifier(s)
+            | TokenKind::Symbol(s)
+            | TokenKind::DecimalLiteral(s)
+            | TokenKind::PercentLiteral(s)
+            | TokenKind::UnitLiteral { value: s, .. }
+            | TokenKind::DateTimeLiteral(s)
+            | TokenKind::ColorLiteral(s)
+            | TokenKind::Attribute(s)
+            | TokenKind::Variable { name: s, .. }
+            | TokenKind::Unknown(s) => Either::A(s.to_string()),
+            TokenKind::Comment(comment) => Either::A(comment.text.to_string()),
+            TokenKind::Directive(directive) => Either::A(directive.name.to_string()),
+            TokenKind::IntegerLiteral(i) if i.unsigned_abs() <= MAX_SAFE_INTEGER => {
+                Either::B(*i as f64)
+            }
+            TokenKind::IntegerLiteral(i) => Either::A(i.to_string()),
+            #[cfg(feature = "bigint")]
+            TokenKind::BigIntegerLiteral(i) => Either::A(i.to_string()),
+            TokenKind::FloatLiteral(f) => Either::B(*f),
+        };
+
+        Self {
+            kind: token.kind.name().to_string(),
+            contents: token.contents.into(),
+            value,
+            file: token.location.path.map(|path| path.display().to_string()),
+            line: token.location.row as u32,
+            column: token.location.column as u32,
+            start: token.span.start as u32,
+            end: token.span.end as u32,
+        }
+    }
+}
+
+/// Tokenizes the source, throwing an error with the rendered diagnostic on failure.
+#[napi(js_name = "tokenize")]
+pub fn tokenize_node(source: String) -> napi::Result<Vec<NodeToken>> {
+    tokenize_at(&source, Location::default())
+}
+
+/// Reads and tokenizes the file, throwing an error if it can't be read or tokenized.
+#[napi(js_name = "tokenizeFile")]
+pub fn tokenize_file_node(path: String) -> napi::Result<Vec<NodeToken>> {
+    let source = std::fs::read_to_string(&path)
+        .map_err(|error| NapiError::new(Status::GenericFailure, error.to_string()))?;
+    tokenize_at(&source, PathBuf::from(path).into())
+}
+
+fn tokenize_at(source: &str, location: Location) -> napi::Result<Vec<NodeToken>> {
+    match Tokenizer::tokenize(source, location) {
+        Ok(tokens) => Ok(tokens.into_iter().map(NodeToken::from).collect()),
+        Err(error) => Err(NapiError::new(Status::InvalidArg, error.render(source))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integers_are_numbers_only_when_exact() {
+        let tokens = Tokenizer::tokenize(
+            "9007199254740991 -9007199254740991 9007199254740993",
+            Location::default(),
+        )
+        .unwrap();
+        let values: Vec<_> = tokens
+            .into_iter()
+            .map(|token| NodeToken::from(token).value)
+            .collect();
+        assert!(matches!(values[0], Either::B(f) if f == 9007199254740991.0));
+        assert!(matches!(values[1], Either::B(f) if f == -9007199254740991.0));
+        assert!(matches!(&values[2], Either::A(s) if s == "9007199254740993"));
+    }
+}
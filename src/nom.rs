@@ -0,0 +1,163 @@
+use std::{iter::Enumerate, slice::Iter};
+
+use ::nom::{
+    error::{ErrorKind as NomErrorKind, ParseError},
+    Err, IResult, Input, Needed,
+};
+
+use crate::{
+    location::Location,
+    token::{Token, TokenKind},
+};
+
+/// A slice of tokens that nom parsers can consume.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tokens<'a> {
+    pub tokens: &'a [Token],
+}
+impl<'a> Tokens<'a> {
+    pub fn new(tokens: &'a [Token]) -> Self {
+        Self { tokens }
+    }
+
+    /// Returns the location of the next token, or `None` at the end of the input.
+    pub fn location(&self) -> Option<&'a Location> {
+        self.tokens.first().map(|token| &token.location)
+    }
+}
+
+impl<'a> Input for Tokens<'a> {
+    type Item = &'a Token;
+    type Iter = Iter<'a, Token>;
+    type IterIndices = Enumerate<Self::Iter>;
+
+    fn input_len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    fn take(&self, index: usize) -> Self {
+        Self::new(&self.tokens[..index])
+    }
+
+    fn take_from(&self, index: usize) -> Self {
+        Self::new(&self.tokens[index..])
+    }
+
+    fn take_split(&self, index: usize) -> (Self, Self) {
+        let (prefix, suffix) = self.tokens.split_at(index);
+        (Self::new(suffix), Self::new(prefix))
+    }
+
+    fn position<P>(&self, predicate: P) -> Option<usize>
+    where
+        P: Fn(Self::Item) -> bool,
+    {
+        self.tokens.iter().position(predicate)
+    }
+
+    fn iter_elements(&self) -> Self::Iter {
+        self.tokens.iter()
+    }
+
+    fn iter_indices(&self) -> Self::IterIndices {
+        self.tokens.iter().enumerate()
+    }
+
+    fn slice_index(&self, count: usize) -> Result<usize, Needed> {
+        if self.tokens.len() >= count {
+            Ok(count)
+        } else {
+            Err(Needed::new(count - self.tokens.len()))
+        }
+    }
+}
+
+/// A nom error that records where in the source parsing failed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenError {
+    /// The location of the token that couldn't be parsed, or `None` if the input ran out.
+    pub location: Option<Location>,
+    pub kind: NomErrorKind,
+}
+impl<'a> ParseError<Tokens<'a>> for TokenError {
+    fn from_error_kind(input: Tokens<'a>, kind: NomErrorKind) -> Self {
+        Self {
+            location: input.location().cloned(),
+            kind,
+        }
+    }
+
+    fn append(_: Tokens<'a>, _: NomErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+/// Returns a parser for the next token if it matches the predicate.
+pub fn token_where<'a, E: ParseError<Tokens<'a>>>(
+    predicate: impl Fn(&Token) -> bool,
+) -> impl Fn(Tokens<'a>) -> IResult<Tokens<'a>, &'a Token, E> {
+    move |input: Tokens<'a>| match input.tokens.first() {
+        Some(token) if predicate(token) => Ok((input.take_from(1), token)),
+        _ => Err(Err::Error(E::from_error_kind(input, NomErrorKind::Tag))),
+    }
+}
+
+/// Returns a parser for the symbol with the given text.
+pub fn symbol<'a, E: ParseError<Tokens<'a>>>(
+    symbol: &'static str,
+) -> impl Fn(Tokens<'a>) -> IResult<Tokens<'a>, &'a Token, E> {
+    token_where(move |token| token.is_symbol(symbol))
+}
+
+/// Parses an identifier, returning its text.
+pub fn identifier<'a, E: ParseError<Tokens<'a>>>(
+    input: Tokens<'a>,
+) -> IResult<Tokens<'a>, &'a str, E> {
+    match input.tokens.first().map(|token| &token.kind) {
+        Some(TokenKind::Identifier(s)) => Ok((input.take_from(1), s.as_str())),
+        _ => Err(Err::Error(E::from_error_kind(input, NomErrorKind::Alpha))),
+    }
+}
+
+/// Parses an integer literal, returning its value.
+pub fn integer<'a, E: ParseError<Tokens<'a>>>(input: Tokens<'a>) -> IResult<Tokens<'a>, i128, E> {
+    match input.tokens.first().map(|token| &token.kind) {
+        Some(TokenKind::IntegerLiteral(i)) => Ok((input.take_from(1), *i)),
+        _ => Err(Err::Error(E::from_error_kind(input, NomErrorKind::Digit))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::nom::{multi::separated_list1, sequence::delimited, Parser};
+
+    use super::*;
+    use crate::tokenizer::Tokenizer;
+
+    #[test]
+    fn parse_tokens_with_nom() {
+        let tokens = Tokenizer::tokenize("(1, 2, 3)", (0, 0).into()).unwrap();
+        let mut list = delimited(
+            symbol("("),
+            separated_list1(symbol(","), integer),
+            symbol(")"),
+        );
+        let (rest, values) = list
+            .parse(Tokens::new(&tokens))
+            .map_err(|e: Err<TokenError>| e)
+            .unwrap();
+        assert_eq!(values, vec![1, 2, 3]);
+        assert_eq!(rest.input_len(), 0);
+    }
+
+    #[test]
+    fn errors_have_locations() {
+        let tokens = Tokenizer::tokenize("let\n  1", (0, 0).into()).unwrap();
+        let result = (identifier::<TokenError>, identifier).parse(Tokens::new(&tokens));
+        let Err(Err::Error(error)) = result else {
+            panic!("expected an error");
+        };
+        assert_eq!(error.location, Some((1, 2).into()));
+        assert_eq!(error.kind, NomErrorKind::Alpha);
+    }
+}
@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+
+use crate::{
+    error::{Error, ErrorKind},
+    source_map::SourceMap,
+    token::{Directive, Token, TokenKind},
+};
+
+/// Filters a token stream by its `if`, `ifdef`, `ifndef`, `elif`, `else` and `endif`
+/// directives, evaluated against a table of defined symbols.
+///
+/// Directives are only produced when a character such as `#` is in
+/// `TokenizerConfig::directives`. The conditional directives and every token in an inactive
+/// section are dropped; everything else is kept untouched, locations and spans included.
+#[derive(Debug, Clone, Default)]
+pub struct Preprocessor {
+    symbols: HashMap<String, String>,
+}
+impl Preprocessor {
+    /// Creates a preprocessor with no defined symbols.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Defines a symbol with the given value.
+    pub fn define(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.symbols.insert(name.into(), value.into());
+    }
+
+    /// Removes a symbol. Returns false if it wasn't defined.
+    pub fn undefine(&mut self, name: &str) -> bool {
+        self.symbols.remove(name).is_some()
+    }
+
+    /// Returns the value of a symbol, if it's defined.
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.symbols.get(name).map(String::as_str)
+    }
+
+    /// Returns the tokens in active sections, without the conditional directives.
+    pub fn process(&self, tokens: Vec<Token>) -> Result<Vec<Token>, Error> {
+        let mut output = Vec::with_capacity(tokens.len());
+        let mut sections: Vec<Section> = Vec::new();
+
+        for token in tokens {
+            let directive = match &token.kind {
+                TokenKind::Directive(directive) => directive,
+                _ => {
+                    if sections.last().is_none_or(|section| section.active) {
+                        output.push(token);
+                    }
+                    continue;
+                }
+            };
+            let enclosing = sections.last().is_none_or(|section| section.active);
+
+            match directive.name.as_str() {
+                "if" | "ifdef" | "ifndef" => {
+                    let condition = enclosing && self.condition(&token, directive)?;
+                    sections.push(Section {
+                        opening: token,
+                        enclosing,
+                        active: condition,
+                        taken: condition,
+                        seen_else: false,
+                    });
+                }
+                "elif" => match sections.last_mut() {
+                    Some(section) if !section.seen_else => {
+                        let condition = section.enclosing
+                            && !section.taken
+                            && self.condition(&token, directive)?;
+                        section.active = condition;
+                        section.taken |= condition;
+                    }
+                    _ => return Err(directive_error(&token, ErrorKind::InvalidDirective)),
+                },
+                "else" => match sections.last_mut() {
+                    Some(section) if !section.seen_else => {
+                        section.active = section.enclosing && !section.taken;
+                        section.taken = true;
+                        section.seen_else = true;
+                    }
+                    _ => return Err(directive_error(&token, ErrorKind::InvalidDirective)),
+                },
+                "endif" => {
+                    if sections.pop().is_none() {
+                        return Err(directive_error(&token, ErrorKind::InvalidDirective));
+                    }
+                }
+                _ => {
+                    if enclosing {
+                        output.push(token);
+                    }
+                }
+            }
+        }
+
+        match sections.pop() {
+            Some(section) => Err(directive_error(
+                &section.opening,
+                ErrorKind::UnclosedConditional,
+            )),
+            None => Ok(output),
+        }
+    }
+
+    /// Returns the tokens in active sections along with a map of where each came from.
+    pub fn process_with_map(&self, tokens: Vec<Token>) -> Result<(Vec<Token>, SourceMap), Error> {
+        let tokens = self.process(tokens)?;
+        let map = SourceMap::from_tokens(&tokens);
+        Ok((tokens, map))
+    }
+
+    /// Evaluates the condition of an `if`, `ifdef`, `ifndef` or `elif` directive.
+    fn condition(&self, token: &Token, directive: &Directive) -> Result<bool, Error> {
+        let arguments = directive.arguments.as_str();
+        let result = match directive.name.as_str() {
+            "ifdef" => is_name(arguments).then(|| self.symbols.contains_key(arguments)),
+            "ifndef" => is_name(arguments).then(|| !self.symbols.contains_key(arguments)),
+            _ => {
+                let mut condition = Condition {
+                    symbols: &self.symbols,
+                    rest: arguments,
+                };
+                condition
+                    .or()
+                    .filter(|_| condition.rest.trim_start().is_empty())
+            }
+        };
+        result.ok_or_else(|| directive_error(token, ErrorKind::InvalidDirective))
+    }
+}
+
+/// An open conditional section.
+struct Section {
+    /// The directive that opened the section, for reporting it if it's never closed.
+    opening: Token,
+    /// Whether the section this one is nested in is active.
+    enclosing: bool,
+    /// Whether tokens in the current branch are kept.
+    active: bool,
+    /// Whether any branch so far has been taken.
+    taken: bool,
+    seen_else: bool,
+}
+
+/// A recursive descent evaluator for the condition of an `if` or `elif`.
+///
+/// A name is true when it's defined with a value other than `0` or nothing.
+struct Condition<'a> {
+    symbols: &'a HashMap<String, String>,
+    rest: &'a str,
+}
+impl<'a> Condition<'a> {
+    fn or(&mut self) -> Option<bool> {
+        let mut value = self.and()?;
+        while self.eat("||") {
+            value |= self.and()?;
+        }
+        Some(value)
+    }
+
+    fn and(&mut self) -> Option<bool> {
+        let mut value = self.not()?;
+        while self.eat("&&") {
+            value &= self.not()?;
+        }
+        Some(value)
+    }
+
+    fn not(&mut self) -> Option<bool> {
+        if !self.rest.trim_start().starts_with("!=") && self.eat("!") {
+            return self.not().map(|value| !value);
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Option<bool> {
+        if self.eat("(") {
+            let value = self.or()?;
+            return self.eat(")").then_some(value);
+        }
+
+        let name = self.word()?;
+        if name == "defined" {
+            let parenthesized = self.eat("(");
+            let name = self.word()?;
+            if parenthesized && !self.eat(")") {
+                return None;
+            }
+            return Some(self.symbols.contains_key(name));
+        }
+
+        let value = self.symbols.get(name).map(String::as_str);
+        if self.eat("==") {
+            let expected = self.word()?;
+            Some(value == Some(expected))
+        } else if self.eat("!=") {
+            let expected = self.word()?;
+            Some(value != Some(expected))
+        } else if name.starts_with(|c: char| c.is_ascii_digit()) {
+            Some(!name.trim_start_matches('0').is_empty())
+        } else {
+            Some(value.is_some_and(|value| !matches!(value, "" | "0")))
+        }
+    }
+
+    /// Consumes the text if it comes next, ignoring leading whitespace.
+    fn eat(&mut self, text: &str) -> bool {
+        match self.rest.trim_start().strip_prefix(text) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Consumes a name or a number.
+    fn word(&mut self) -> Option<&'a str> {
+        let rest = self.rest.trim_start();
+        let len = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let (word, rest) = rest.split_at(len);
+        self.rest = rest;
+        (!word.is_empty()).then_some(word)
+    }
+}
+
+/// Returns whether the text is a single name, as `ifdef` and `ifndef` expect.
+fn is_name(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn directive_error(token: &Token, kind: ErrorKind) -> Error {
+    let name = match &token.kind {
+        TokenKind::Directive(directive) => directive.name.to_string(),
+        _ => token.contents.to_string(),
+    };
+    let message = match kind {
+        ErrorKind::UnclosedConditional => format!("Unclosed {} directive", name),
+        _ => format!("Invalid {} directive", name),
+    };
+    Error {
+        kind,
+        message,
+        location: token.location.clone(),
+        span: token.span,
+        source_line: None,
+        mismatch: None,
+        label: None,
+        args: Box::new([("directive", name)]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::TokenizerConfig, span::Span, tokenizer::Tokenizer};
+
+    fn preprocess(preprocessor: &Preprocessor, source: &str) -> Result<Vec<Token>, Error> {
+        let config = TokenizerConfig {
+            directives: vec!['#'],
+            ..Default::default()
+        };
+        let tokens = Tokenizer::tokenize_with_config(source, (0, 0).into(), &config).unwrap();
+        preprocessor.process(tokens)
+    }
+
+    fn contents(tokens: &[Token]) -> Vec<&str> {
+        tokens.iter().map(|token| &*token.contents).collect()
+    }
+
+    #[test]
+    fn inactive_sections_are_removed() {
+        let mut preprocessor = Preprocessor::new();
+        preprocessor.define("BOARD", "rev2");
+        preprocessor.define("DEBUG", "1");
+        let source = "a\n\
+                      #ifdef DEBUG\nb\n#else\nc\n#endif\n\
+                      #if BOARD == rev1\nd\n#elif BOARD == rev2 && !defined(LEGACY)\ne\n\
+                      #if 0\nf\n#include \"g\"\n#endif\n#else\nh\n#endif\n\
+                      #ifndef DEBUG\ni\n#endif\n#define j";
+        let tokens = preprocess(&preprocessor, source).unwrap();
+        assert_eq!(contents(&tokens), ["a", "b", "e", "#define j"]);
+        assert_eq!(tokens[2].location, (9, 0).into());
+        assert_eq!(tokens[2].span, Span::new(92, 93));
+    }
+
+    #[test]
+    fn mismatched_directives_return_errors() {
+        let preprocessor = Preprocessor::new();
+
+        let error = preprocess(&preprocessor, "a\n#else").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::InvalidDirective);
+        assert_eq!(error.message, "Invalid else directive");
+        assert_eq!(error.location, (1, 0).into());
+
+        let error = preprocess(&preprocessor, "#if A &&\n#endif").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::InvalidDirective);
+
+        let error = preprocess(&preprocessor, "#ifdef A\n#else\n#else\n#endif").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::InvalidDirective);
+        assert_eq!(error.location, (2, 0).into());
+
+        let error = preprocess(&preprocessor, "#ifdef A\n#if B\n#endif").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::UnclosedConditional);
+        assert_eq!(error.message, "Unclosed ifdef directive");
+        assert_eq!(error.span, Span::new(0, 8));
+    }
+}
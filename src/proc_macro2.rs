@@ -0,0 +1,188 @@
+use ::proc_macro2::{Delimiter, Group, Ident, Literal, Punct, Spacing, TokenStream, TokenTree};
+
+use crate::{
+    config::TokenizerConfig,
+    error::{Error, ErrorKind},
+    token::{Token, TokenKind},
+};
+
+/// Converts tokens to a `proc_macro2::TokenStream` so they can be used with `quote!`.
+/// Brackets become groups, symbols become punctuation and comments are dropped. Every
+/// tree has the call site span, as there's no way to point into the original source.
+///
+/// Fails on unbalanced brackets, identifiers Rust doesn't accept, non-finite floats and
+/// unknown tokens.
+pub fn tokens_to_token_stream(tokens: &[Token]) -> Result<TokenStream, Error> {
+    tokens_to_token_stream_with_config(tokens, &TokenizerConfig::default())
+}
+
+/// Converts tokens to a `proc_macro2::TokenStream` like `tokens_to_token_stream`, failing
+/// with `ErrorKind::TooDeep` if groups nest deeper than `TokenizerConfig::max_depth`.
+pub fn tokens_to_token_stream_with_config(
+    tokens: &[Token],
+    config: &TokenizerConfig,
+) -> Result<TokenStream, Error> {
+    // The open groups, innermost last, with the token that opened each.
+    let mut groups: Vec<(Delimiter, &Token, Vec<TokenTree>)> = Vec::new();
+    let mut trees = Vec::new();
+
+    for token in tokens {
+        match &token.kind {
+            TokenKind::Comment(_) => {}
+            TokenKind::Symbol(s) => match s.as_str() {
+                "(" | "[" | "{" => {
+                    let delimiter = match s.as_str() {
+                        "(" => Delimiter::Parenthesis,
+                        "[" => Delimiter::Bracket,
+                        _ => Delimiter::Brace,
+                    };
+                    if let Some(limit) = config.max_depth.filter(|limit| groups.len() >= *limit) {
+                        return Err(Error::too_deep(limit, token.location.clone(), token.span));
+                    }
+                    groups.push((delimiter, token, std::mem::take(&mut trees)));
+                }
+                ")" | "]" | "}" => {
+                    let expected = match s.as_str() {
+                        ")" => Delimiter::Parenthesis,
+                        "]" => Delimiter::Bracket,
+                        _ => Delimiter::Brace,
+                    };
+                    match groups.pop() {
+                        Some((delimiter, _, outer)) if delimiter == expected => {
+                            let inner = std::mem::replace(&mut trees, outer);
+                            let stream = inner.into_iter().collect();
+                            trees.push(Group::new(delimiter, stream).into());
+                        }
+                        _ => return Err(unexpected(token, &format!("Unmatched '{}'", s))),
+                    }
+                }
+                _ => {
+                    let mut chars = s.chars().peekable();
+                    while let Some(c) = chars.next() {
+                        let spacing = match chars.peek() {
+                            Some(_) => Spacing::Joint,
+                            None => Spacing::Alone,
+                        };
+                        trees.push(Punct::new(c, spacing).into());
+                    }
+                }
+            },
+            TokenKind::Identifier(s) => trees.push(ident(token, s)?.into()),
+            // Rust reads a variable as the sigil and an identifier, as in `$name`.
+            TokenKind::Variable { sigil, name } if "=<>!~+-*/%^&|@.,;:#$?".contains(*sigil) => {
+                trees.push(Punct::new(*sigil, Spacing::Joint).into());
+                trees.push(ident(token, name)?.into());
+            }
+            TokenKind::StringLiteral(s) => trees.push(Literal::string(s).into()),
+            TokenKind::IntegerLiteral(i) => trees.push(Literal::i128_unsuffixed(*i).into()),
+            #[cfg(feature = "bigint")]
+            TokenKind::BigIntegerLiteral(i) => match i.to_string().parse::<Literal>() {
+                Ok(literal) => trees.push(literal.into()),
+                Err(_) => return Err(unexpected(token, &format!("Can't convert {}", token.kind))),
+            },
+            TokenKind::FloatLiteral(f) if f.is_finite() => {
+                trees.push(Literal::f64_unsuffixed(*f).into())
+            }
+            TokenKind::DecimalLiteral(s) => match s.parse::<Literal>() {
+                Ok(literal) => trees.push(literal.into()),
+                Err(_) => return Err(unexpected(token, &format!("Can't convert {}", token.kind))),
+            },
+            // Rust reads a percent as the number followed by a `%` symbol.
+            TokenKind::PercentLiteral(_) => {
+                match token.contents.trim_end_matches('%').parse::<Literal>() {
+                    Ok(literal) => trees.push(literal.into()),
+                    Err(_) => {
+                        return Err(unexpected(token, &format!("Can't convert {}", token.kind)))
+                    }
+                }
+                trees.push(Punct::new('%', Spacing::Alone).into());
+            }
+            // Rust reads a unit as the literal's suffix, as in `250ms`.
+            TokenKind::UnitLiteral { .. } => match token.contents.parse::<Literal>() {
+                Ok(literal) => trees.push(literal.into()),
+                Err(_) => return Err(unexpected(token, &format!("Can't convert {}", token.kind))),
+            },
+            TokenKind::FloatLiteral(_)
+            | TokenKind::DateTimeLiteral(_)
+            | TokenKind::ColorLiteral(_)
+            | TokenKind::Attribute(_)
+            | TokenKind::Variable { .. }
+            | TokenKind::Directive(_)
+            | TokenKind::Unknown(_) => {
+                return Err(unexpected(
+                    token,
+                    &format!("Can't convert {} '{}'", token.kind.name(), token.contents),
+                ))
+            }
+        }
+    }
+
+    match groups.pop() {
+        Some((_, open, _)) => Err(unexpected(open, &format!("Unclosed '{}'", open.contents))),
+        None => Ok(trees.into_iter().collect()),
+    }
+}
+
+/// Builds an identifier, checking it first as `Ident::new` panics on invalid input.
+fn ident(token: &Token, s: &str) -> Result<Ident, Error> {
+    let mut trees = s.parse::<TokenStream>().into_iter().flatten();
+    match (trees.next(), trees.next()) {
+        (Some(TokenTree::Ident(ident)), None) if ident == s => Ok(ident),
+        _ => Err(unexpected(
+            token,
+            &format!("'{}' isn't a valid Rust identifier", s),
+        )),
+    }
+}
+
+fn unexpected(token: &Token, message: &str) -> Error {
+    Error {
+        kind: ErrorKind::UnexpectedToken,
+        message: message.to_string(),
+        location: token.location.clone(),
+        span: token.span,
+        source_line: None,
+        mismatch: None,
+        label: None,
+        args: Box::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Tokenizer;
+
+    #[test]
+    fn convert_to_token_stream() {
+        let tokens = Tokenizer::tokenize("f(x, [1, 2.5]) >= \"hi\" # done", (0, 0).into()).unwrap();
+        let stream = tokens_to_token_stream(&tokens).unwrap();
+        assert_eq!(stream.to_string(), "f (x , [1 , 2.5]) >= \"hi\"");
+    }
+
+    #[test]
+    fn unbalanced_brackets_are_errors() {
+        let tokens = Tokenizer::tokenize("(a]", (0, 0).into()).unwrap();
+        let error = tokens_to_token_stream(&tokens).unwrap_err();
+        assert_eq!(error.message, "Unmatched ']'");
+        assert_eq!(error.location, (0, 2).into());
+
+        let tokens = Tokenizer::tokenize("{ a", (0, 0).into()).unwrap();
+        let error = tokens_to_token_stream(&tokens).unwrap_err();
+        assert_eq!(error.message, "Unclosed '{'");
+        assert_eq!(error.location, (0, 0).into());
+    }
+
+    #[test]
+    fn groups_nested_too_deep_are_errors() {
+        let config = TokenizerConfig {
+            max_depth: Some(2),
+            ..Default::default()
+        };
+        let tokens = Tokenizer::tokenize("[(a)] [([b])]", (0, 0).into()).unwrap();
+        let error = tokens_to_token_stream_with_config(&tokens, &config).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::TooDeep);
+        assert_eq!(error.location, (0, 8).into());
+        assert!(tokens_to_token_stream(&tokens).is_ok());
+    }
+}
@@ -0,0 +1,104 @@
+use pyo3::{create_exception, exceptions::PyException, prelude::*, types::PyString};
+
+use crate::{
+    token::{Token, TokenKind},
+    tokenizer::Tokenizer,
+};
+
+create_exception!(tokenizer, TokenizeError, PyException);
+
+/// A token as seen from Python. Lines and columns are zero based, and `start` and `end`
+/// are byte offsets in the source.
+#[pyclass(name = "Token", module = "tokenizer", frozen, eq, skip_from_py_object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PyToken(Token);
+#[pymethods]
+impl PyToken {
+    /// The name of the kind, such as `identifier`.
+    #[getter]
+    fn kind(&self) -> &'static str {
+        self.0.kind.name()
+    }
+
+    #[getter]
+    fn contents(&self) -> &str {
+        &self.0.contents
+    }
+
+    /// The value of the token, an `int`, `float` or `decimal.Decimal` for number literals
+    /// and a `str` otherwise.
+    #[getter]
+    fn value<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let value = match &self.0.kind {
+            TokenKind::StringLiteral(s)
+            | TokenKind::Identifier(s)
+            | TokenKind::Symbol(s)
+            | TokenKind::DateTimeLiteral(s)
+            | TokenKind::ColorLiteral(s)
+            | TokenKind::Attribute(s)
+            | TokenKind::Variable { name: s, .. }
+            | TokenKind::Unknown(s) => PyString::new(py, s).into_any(),
+            TokenKind::Comment(comment) => PyString::new(py, &comment.text).into_any(),
+            TokenKind::Directive(directive) => PyString::new(py, &directive.name).into_any(),
+            TokenKind::IntegerLiteral(i) => i.into_pyobject(py)?.into_any(),
+            #[cfg(feature = "bigint")]
+            TokenKind::BigIntegerLiteral(i) => i.into_pyobject(py)?.into_any(),
+            TokenKind::FloatLiteral(f) => f.into_pyobject(py)?.into_any(),
+            TokenKind::DecimalLiteral(s)
+            | TokenKind::PercentLiteral(s)
+            | TokenKind::UnitLiteral { value: s, .. } => py
+                .import("decimal")?
+                .getattr("Decimal")?
+                .call1((s.as_str(),))?,
+        };
+        Ok(value)
+    }
+
+    #[getter]
+    fn line(&self) -> usize {
+        self.0.location.row
+    }
+
+    #[getter]
+    fn column(&self) -> usize {
+        self.0.location.column
+    }
+
+    #[getter]
+    fn start(&self) -> usize {
+        self.0.span.start
+    }
+
+    #[getter]
+    fn end(&self) -> usize {
+        self.0.span.end
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Token(kind={:?}, contents={:?}, line={}, column={})",
+            self.0.kind.name(),
+            self.0.contents.as_str(),
+            self.0.location.row,
+            self.0.location.column
+        )
+    }
+}
+
+/// Tokenizes the source, raising `TokenizeError` with the rendered diagnostic on failure.
+#[pyfunction]
+fn tokenize(source: &str) -> PyResult<Vec<PyToken>> {
+    match Tokenizer::tokenize(source, (0, 0).into()) {
+        Ok(tokens) => Ok(tokens.into_iter().map(PyToken).collect()),
+        Err(error) => Err(TokenizeError::new_err(error.render(source))),
+    }
+}
+
+/// The `tokenizer` Python module.
+#[pymodule]
+fn tokenizer(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(tokenize, m)?)?;
+    m.add_class::<PyToken>()?;
+    m.add("TokenizeError", m.py().get_type::<TokenizeError>())?;
+    Ok(())
+}
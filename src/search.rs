@@ -0,0 +1,227 @@
+use std::ops::Range;
+
+use crate::{
+    error::Error,
+    token::{Token, TokenKind, TokenKindTag},
+    tokenizer::tokenize,
+};
+
+/// One step of a `Pattern`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternElement {
+    /// Any token of the kind.
+    Kind(TokenKindTag),
+    /// A symbol or identifier spelled exactly as the text, such as `(` or the keyword `fn`.
+    Text(String),
+    /// Any one token.
+    Any,
+    /// Any run of tokens, including none, with balanced brackets. It takes as few tokens as
+    /// it can, so in `"(" Gap ")"` the gap ends at the bracket that closes the first.
+    Gap,
+}
+impl PatternElement {
+    fn matches(&self, token: &Token) -> bool {
+        match self {
+            PatternElement::Kind(tag) => token.kind.tag() == *tag,
+            PatternElement::Text(text) => match &token.kind {
+                TokenKind::Symbol(s) | TokenKind::Identifier(s) => s == text.as_str(),
+                _ => false,
+            },
+            PatternElement::Any => true,
+            PatternElement::Gap => false,
+        }
+    }
+}
+
+/// A sequence of token matchers, for searching token streams more precisely than a regex
+/// over the text could.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pattern {
+    elements: Vec<PatternElement>,
+}
+impl Pattern {
+    pub fn new(elements: Vec<PatternElement>) -> Self {
+        Self { elements }
+    }
+
+    /// Reads a pattern from a query such as `ident "(" ... ")"`, which is tokenized with
+    /// the default config. Each element is one of:
+    ///
+    /// - a kind, either as named by `TokenKind::name`, such as `string_literal`, or short,
+    ///   as in `string`, `ident`, `int`, `float` or `unit`
+    /// - a quoted symbol or identifier, such as `"="` or `"fn"`
+    /// - `_` for any one token
+    /// - `...` for a gap
+    pub fn parse(query: &str) -> Result<Self, Error> {
+        const EXPECTED: &str = "a token kind, a quoted symbol, `_` or `...`";
+
+        let tokens = tokenize(query, (0, 0).into())?;
+        let mut elements = Vec::new();
+        let mut tokens = tokens.iter().peekable();
+        while let Some(token) = tokens.next() {
+            let element = match &token.kind {
+                TokenKind::StringLiteral(s) => PatternElement::Text(s.to_string()),
+                TokenKind::Identifier(s) if s == "_" => PatternElement::Any,
+                TokenKind::Identifier(s) => match kind_tag(s) {
+                    Some(tag) => PatternElement::Kind(tag),
+                    None => return Err(token.mismatch("a token kind")),
+                },
+                TokenKind::Symbol(s) if s == ".." => {
+                    match tokens
+                        .next_if(|next| next.is_symbol(".") && next.span.start == token.span.end)
+                    {
+                        Some(_) => PatternElement::Gap,
+                        None => return Err(token.mismatch(EXPECTED)),
+                    }
+                }
+                _ => return Err(token.mismatch(EXPECTED)),
+            };
+            elements.push(element);
+        }
+        Ok(Self { elements })
+    }
+
+    pub fn elements(&self) -> &[PatternElement] {
+        &self.elements
+    }
+
+    /// Returns the range of token indexes the pattern matches starting at `start`.
+    pub fn match_at(&self, tokens: &[Token], start: usize) -> Option<Range<usize>> {
+        self.match_from(0, tokens, start).map(|end| start..end)
+    }
+
+    /// Returns the ranges of token indexes of every non-empty match, from left to right
+    /// and without overlaps.
+    pub fn find_all(&self, tokens: &[Token]) -> Vec<Range<usize>> {
+        let mut matches = Vec::new();
+        let mut start = 0;
+        while start < tokens.len() {
+            match self.match_at(tokens, start) {
+                Some(range) if !range.is_empty() => {
+                    start = range.end;
+                    matches.push(range);
+                }
+                _ => start += 1,
+            }
+        }
+        matches
+    }
+
+    /// Returns the end of a match of the elements from `element` on, starting at the token
+    /// index `position`.
+    fn match_from(&self, element: usize, tokens: &[Token], mut position: usize) -> Option<usize> {
+        match self.elements.get(element) {
+            None => Some(position),
+            Some(PatternElement::Gap) => {
+                let mut depth = 0usize;
+                loop {
+                    if depth == 0 {
+                        if let Some(end) = self.match_from(element + 1, tokens, position) {
+                            return Some(end);
+                        }
+                    }
+                    match tokens.get(position)?.as_symbol() {
+                        Some("(" | "[" | "{") => depth += 1,
+                        Some(")" | "]" | "}") => depth = depth.checked_sub(1)?,
+                        _ => {}
+                    }
+                    position += 1;
+                }
+            }
+            Some(matcher) => {
+                if !matcher.matches(tokens.get(position)?) {
+                    return None;
+                }
+                self.match_from(element + 1, tokens, position + 1)
+            }
+        }
+    }
+}
+
+/// Returns the tag for a kind's name or short name in a query.
+fn kind_tag(name: &str) -> Option<TokenKindTag> {
+    let tag = match name {
+        "string" | "string_literal" => TokenKindTag::StringLiteral,
+        "comment" => TokenKindTag::Comment,
+        "ident" | "identifier" => TokenKindTag::Identifier,
+        "symbol" => TokenKindTag::Symbol,
+        "int" | "integer_literal" => TokenKindTag::IntegerLiteral,
+        "float" | "float_literal" => TokenKindTag::FloatLiteral,
+        "decimal" | "decimal_literal" => TokenKindTag::DecimalLiteral,
+        "percent" | "percent_literal" => TokenKindTag::PercentLiteral,
+        "datetime" | "datetime_literal" => TokenKindTag::DateTimeLiteral,
+        "color" | "color_literal" => TokenKindTag::ColorLiteral,
+        "attribute" => TokenKindTag::Attribute,
+        "variable" => TokenKindTag::Variable,
+        "directive" => TokenKindTag::Directive,
+        "unit" | "unit_literal" => TokenKindTag::UnitLiteral,
+        "unknown" => TokenKindTag::Unknown,
+        _ => return None,
+    };
+    Some(tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::tokenize;
+
+    #[test]
+    fn patterns_match_token_ranges() {
+        let tokens = tokenize("fn f(a, g(b)) { h() }\nfn (x)", (0, 0).into()).unwrap();
+
+        let call = Pattern::new(vec![
+            PatternElement::Kind(TokenKindTag::Identifier),
+            PatternElement::Text("(".into()),
+            PatternElement::Gap,
+            PatternElement::Text(")".into()),
+        ]);
+        assert_eq!(call.find_all(&tokens), [1..10, 11..14, 15..19]);
+
+        let function = Pattern::new(vec![
+            PatternElement::Text("fn".into()),
+            PatternElement::Kind(TokenKindTag::Identifier),
+        ]);
+        assert_eq!(function.find_all(&tokens).len(), 1);
+        assert_eq!(function.match_at(&tokens, 0), Some(0..2));
+        assert_eq!(function.match_at(&tokens, 15), None);
+
+        let unbalanced = Pattern::new(vec![
+            PatternElement::Text("g".into()),
+            PatternElement::Gap,
+            PatternElement::Text("}".into()),
+        ]);
+        assert_eq!(unbalanced.find_all(&tokens), []);
+    }
+
+    #[test]
+    fn queries_parse_into_patterns() {
+        let pattern = Pattern::parse(r#"ident "=" string _ ..."fn" float_literal"#).unwrap();
+        assert_eq!(
+            pattern.elements(),
+            [
+                PatternElement::Kind(TokenKindTag::Identifier),
+                PatternElement::Text("=".into()),
+                PatternElement::Kind(TokenKindTag::StringLiteral),
+                PatternElement::Any,
+                PatternElement::Gap,
+                PatternElement::Text("fn".into()),
+                PatternElement::Kind(TokenKindTag::FloatLiteral),
+            ]
+        );
+
+        let tokens = tokenize("a = \"x\"\nb = 1", (0, 0).into()).unwrap();
+        let assignment = Pattern::parse(r#"ident "=" string"#).unwrap();
+        assert_eq!(assignment.find_all(&tokens).len(), 1);
+        assert_eq!(assignment.match_at(&tokens, 0), Some(0..3));
+
+        let error = Pattern::parse("ident strings").unwrap_err();
+        assert_eq!(
+            error.message,
+            "Expected a token kind, got identifier 'strings'"
+        );
+        assert_eq!(error.location, (0, 6).into());
+        assert!(Pattern::parse("ident .. string").is_err());
+        assert!(Pattern::parse("ident (").is_err());
+    }
+}
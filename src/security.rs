@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+
+use unicode_security::{skeleton, MixedScript};
+
+use crate::{
+    token::{Token, TokenKind},
+    warning::Warning,
+};
+
+/// Returns warnings for identifiers that mix scripts or that are confusable with a
+/// different identifier seen earlier, following UTS #39.
+pub fn check_identifiers(tokens: &[Token]) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    // Maps a skeleton to the first identifier that produced it.
+    let mut skeletons: HashMap<String, &str> = HashMap::new();
+
+    for token in tokens {
+        let TokenKind::Identifier(identifier) = &token.kind else {
+            continue;
+        };
+
+        if !identifier.as_str().is_single_script() {
+            warnings.push(Warning {
+                message: format!("Identifier '{}' mixes scripts", identifier),
+                location: token.location.clone(),
+            });
+        }
+
+        let skeleton: String = skeleton(identifier).collect();
+        match skeletons.get(&skeleton) {
+            Some(first) if *first != identifier => warnings.push(Warning {
+                message: format!("Identifier '{}' is confusable with '{}'", identifier, first),
+                location: token.location.clone(),
+            }),
+            Some(_) => {}
+            None => {
+                skeletons.insert(skeleton, identifier);
+            }
+        }
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Tokenizer;
+
+    #[test]
+    fn detects_mixed_scripts() {
+        // The 'а' is Cyrillic.
+        let tokens = Tokenizer::tokenize("p\u{430}ypal", (0, 0).into()).unwrap();
+        let expected = vec![Warning {
+            message: "Identifier 'p\u{430}ypal' mixes scripts".to_string(),
+            location: (0, 0).into(),
+        }];
+        assert_eq!(expected, check_identifiers(&tokens));
+    }
+
+    #[test]
+    fn detects_confusable_identifiers() {
+        let tokens = Tokenizer::tokenize("scope sc\u{43e}pe scope", (0, 0).into()).unwrap();
+        let warnings = check_identifiers(&tokens);
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(
+            warnings[1],
+            Warning {
+                message: "Identifier 'sc\u{43e}pe' is confusable with 'scope'".to_string(),
+                location: (0, 6).into(),
+            }
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use crate::{
+    json::escape,
+    token::{Origin, Token},
+};
+
+/// Relates the index of each token in a transformed stream, such as the output of a
+/// `Preprocessor` or a spliced `IncludedFile`, to where the token came from. Diagnostics
+/// about the transformed stream can then point at the true origin even after the tokens
+/// themselves are rewritten.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    origins: Vec<Origin>,
+}
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a map where each token is its own origin.
+    pub fn from_tokens(tokens: &[Token]) -> Self {
+        Self {
+            origins: tokens.iter().map(Origin::from).collect(),
+        }
+    }
+
+    /// Adds the origin of the next output token.
+    pub fn push(&mut self, origin: Origin) {
+        self.origins.push(origin);
+    }
+
+    /// Returns the origin of the output token at the index.
+    pub fn origin(&self, index: usize) -> Option<&Origin> {
+        self.origins.get(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.origins.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.origins.is_empty()
+    }
+
+    /// Returns the map for a further transformation, where `sources` holds, for each of its
+    /// output tokens, the index in this map's output it came from.
+    ///
+    /// Panics if a source index is out of bounds.
+    pub fn remap(&self, sources: &[usize]) -> Self {
+        Self {
+            origins: sources
+                .iter()
+                .map(|source| self.origins[*source].clone())
+                .collect(),
+        }
+    }
+
+    /// Serializes the map as a JSON array with an object per output token, holding the
+    /// origin's `file`, one based `line` and `column`, and byte `span`.
+    pub fn to_json(&self) -> String {
+        let origins: Vec<String> = self
+            .origins
+            .iter()
+            .map(|origin| {
+                let file = match &origin.location.path {
+                    Some(path) => escape(&path.display().to_string()),
+                    None => "null".to_string(),
+                };
+                format!(
+                    "{{\"file\":{},\"line\":{},\"column\":{},\"span\":{{\"start\":{},\"end\":{}}}}}",
+                    file,
+                    origin.location.row + 1,
+                    origin.location.column + 1,
+                    origin.span.start,
+                    origin.span.end
+                )
+            })
+            .collect();
+        format!("[{}]", origins.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, path::PathBuf};
+
+    use crate::span::Span;
+    use crate::{config::TokenizerConfig, include::IncludeResolver, preprocess::Preprocessor};
+
+    #[test]
+    fn maps_follow_tokens_to_their_files() {
+        let files: HashMap<PathBuf, String> = [
+            ("main.cfg", "#ifdef FAST\nfast\n#endif\n#include \"b.cfg\""),
+            ("b.cfg", "x \"y\""),
+        ]
+        .into_iter()
+        .map(|(path, contents)| (PathBuf::from(path), contents.to_string()))
+        .collect();
+        let config = TokenizerConfig {
+            directives: vec!['#'],
+            ..Default::default()
+        };
+        let file = IncludeResolver::new(files, &config)
+            .tokenize_file("main.cfg".as_ref())
+            .unwrap();
+        let (tokens, spliced_map) = file.splice_with_map();
+        let (tokens, map) = Preprocessor::new().process_with_map(tokens).unwrap();
+        assert_eq!(map, spliced_map.remap(&[3, 4]));
+
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(map.len(), 2);
+        let expected = concat!(
+            "[{\"file\":\"b.cfg\",\"line\":1,\"column\":1,\"span\":{\"start\":0,\"end\":1}},",
+            "{\"file\":\"b.cfg\",\"line\":1,\"column\":3,\"span\":{\"start\":2,\"end\":5}}]"
+        );
+        assert_eq!(expected, map.to_json());
+
+        let rewritten = map.remap(&[1, 1, 0]);
+        assert_eq!(rewritten.origin(0), map.origin(1));
+        assert_eq!(rewritten.origin(2).unwrap().span, Span::new(0, 1));
+        assert_eq!(rewritten.origin(3), None);
+    }
+}
@@ -0,0 +1,71 @@
+use crate::token::Token;
+
+/// A range of bytes in a source, from `start` up to but not including `end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// Returns the span covering the tokens, from the start of the first to the end of the
+    /// last, or `None` if there are no tokens.
+    pub fn covering(tokens: &[Token]) -> Option<Self> {
+        let first = tokens.first()?;
+        let last = tokens.last()?;
+        Some(first.span.join(last.span))
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Returns the smallest span covering both spans and anything between them.
+    pub fn join(self, other: Self) -> Self {
+        Self::new(self.start.min(other.start), self.end.max(other.end))
+    }
+
+    /// Returns true if the byte at `offset` is in the span.
+    pub fn contains(&self, offset: usize) -> bool {
+        self.start <= offset && offset < self.end
+    }
+
+    /// Returns true if the spans share at least one byte.
+    pub fn intersects(&self, other: Self) -> bool {
+        self.start < other.end && other.start < self.end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Tokenizer;
+
+    #[test]
+    fn span_arithmetic() {
+        let a = Span::new(2, 5);
+        let b = Span::new(8, 10);
+        assert_eq!(a.join(b), Span::new(2, 10));
+        assert_eq!(b.join(a), Span::new(2, 10));
+        assert!(a.contains(2));
+        assert!(a.contains(4));
+        assert!(!a.contains(5));
+        assert!(!a.intersects(b));
+        assert!(a.intersects(Span::new(4, 9)));
+        assert!(!a.intersects(Span::new(5, 8)));
+    }
+
+    #[test]
+    fn covering_tokens() {
+        let tokens = Tokenizer::tokenize("f(a, b) + 1", (0, 0).into()).unwrap();
+        assert_eq!(Span::covering(&tokens[..6]), Some(Span::new(0, 7)));
+        assert_eq!(Span::covering(&[]), None);
+    }
+}
@@ -0,0 +1,120 @@
+/// Defines a `TokenizerConfig` constant from a list of options, checking at compile time
+/// that no two options contradict each other. Options that aren't listed keep their
+/// defaults.
+///
+/// ```
+/// tokenizer::tokenizer_spec! {
+///     pub const MY_LANGUAGE = {
+///         identifiers: UnicodeXid,
+///         whitespace: Ascii,
+///         invisible_characters: Error,
+///         fuel: 100_000,
+///     };
+/// }
+///
+/// assert_eq!(MY_LANGUAGE.fuel, Some(100_000));
+/// ```
+///
+/// Contradicting options fail to compile:
+///
+/// ```compile_fail
+/// tokenizer::tokenizer_spec! {
+///     const BROKEN = {
+///         whitespace: Ascii,
+///         unusual_whitespace: Warn,
+///     };
+/// }
+/// ```
+#[macro_export]
+macro_rules! tokenizer_spec {
+    ($(#[$attr:meta])* $vis:vis const $name:ident = { $($option:ident: $value:tt),* $(,)? };) => {
+        $(#[$attr])*
+        $vis const $name: $crate::TokenizerConfig = {
+            let mut config = $crate::TokenizerConfig::DEFAULT;
+            $(config.$option = $crate::tokenizer_spec!(@value $option $value);)*
+            if let Err(message) = config.validate() {
+                panic!("{}", message);
+            }
+            config
+        };
+    };
+
+    (@value identifiers $value:ident) => { $crate::IdentifierRules::$value };
+    (@value unexpected_characters $value:ident) => { $crate::UnexpectedCharacters::$value };
+    (@value digits $value:ident) => { $crate::Digits::$value };
+    (@value signs $value:ident) => { $crate::Signs::$value };
+    (@value plus_signs $value:tt) => { $value };
+    (@value leading_dots $value:ident) => { $crate::LeadingDots::$value };
+    (@value trailing_dots $value:tt) => { $value };
+    (@value special_floats $value:tt) => { $value };
+    (@value fractions $value:ident) => { $crate::Fractions::$value };
+    (@value percents $value:tt) => { $value };
+    (@value datetimes $value:tt) => { $value };
+    (@value colors $value:tt) => { $value };
+    (@value doc_comments $value:tt) => { $value };
+    (@value block_comments $value:ident) => { $crate::BlockComments::$value };
+    (@value unclosed_block_comments $value:ident) => { $crate::UnclosedBlockComments::$value };
+    (@value attributes $value:ident) => { $crate::Attributes::$value };
+    (@value whitespace $value:ident) => { $crate::Whitespace::$value };
+    (@value unusual_whitespace $value:ident) => { $crate::UnusualWhitespace::$value };
+    (@value normalization $value:ident) => { $crate::Normalization::$value };
+    (@value identifier_case $value:ident) => { $crate::IdentifierCase::$value };
+    (@value bidi_controls $value:ident) => { $crate::BidiControls::$value };
+    (@value invisible_characters $value:ident) => { $crate::InvisibleCharacters::$value };
+    (@value smart_quotes $value:ident) => { $crate::SmartQuotes::$value };
+    (@value string_newlines $value:ident) => { $crate::StringNewlines::$value };
+    (@value fuel $value:tt) => { ::core::option::Option::Some($value) };
+    (@value max_depth $value:tt) => { ::core::option::Option::Some($value) };
+    (@value backend $value:ident) => { $crate::Backend::$value };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::{IdentifierRules, TokenizerConfig, Whitespace},
+        tokenizer::Tokenizer,
+    };
+
+    tokenizer_spec! {
+        const ASCII_LANGUAGE = {
+            identifiers: Ascii,
+            whitespace: Ascii,
+            unexpected_characters: Token,
+        };
+    }
+
+    #[test]
+    fn spec_builds_config() {
+        assert_eq!(ASCII_LANGUAGE.identifiers, IdentifierRules::Ascii);
+        assert_eq!(ASCII_LANGUAGE.whitespace, Whitespace::Ascii);
+        assert_eq!(ASCII_LANGUAGE.fuel, None);
+
+        let tokens =
+            Tokenizer::tokenize_with_config("a\u{e9}", (0, 0).into(), &ASCII_LANGUAGE).unwrap();
+        assert_eq!(tokens.len(), 2);
+    }
+
+    #[test]
+    fn validate_finds_conflicts() {
+        assert_eq!(Ok(()), TokenizerConfig::default().validate());
+        let config = TokenizerConfig {
+            fuel: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(Err("fuel must be greater than zero"), config.validate());
+
+        let config = TokenizerConfig {
+            line_directive: Some("line".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            Err("line_directive has no effect when directives is empty"),
+            config.validate()
+        );
+        let config = TokenizerConfig {
+            directives: vec!['#'],
+            ..config
+        };
+        assert_eq!(Ok(()), config.validate());
+    }
+}
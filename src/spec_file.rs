@@ -0,0 +1,117 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use crate::config::TokenizerConfig;
+
+/// Why a tokenizer spec couldn't be loaded.
+#[derive(Debug)]
+pub enum SpecError {
+    Io(std::io::Error),
+    Toml(toml::de::Error),
+    Json(serde_json::Error),
+    /// The file's extension isn't `toml` or `json`.
+    UnknownFormat(PathBuf),
+    /// Options contradict each other, as found by `TokenizerConfig::validate`.
+    Invalid(&'static str),
+}
+impl Display for SpecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpecError::Io(error) => write!(f, "{}", error),
+            SpecError::Toml(error) => write!(f, "{}", error),
+            SpecError::Json(error) => write!(f, "{}", error),
+            SpecError::UnknownFormat(path) => write!(
+                f,
+                "Unknown spec format for '{}', expected a .toml or .json file",
+                path.display()
+            ),
+            SpecError::Invalid(message) => write!(f, "Invalid spec: {}", message),
+        }
+    }
+}
+impl std::error::Error for SpecError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SpecError::Io(error) => Some(error),
+            SpecError::Toml(error) => Some(error),
+            SpecError::Json(error) => Some(error),
+            SpecError::UnknownFormat(_) | SpecError::Invalid(_) => None,
+        }
+    }
+}
+
+impl TokenizerConfig {
+    /// Parses a config from a TOML spec. Options that aren't given keep their defaults.
+    pub fn from_toml(spec: &str) -> Result<Self, SpecError> {
+        let config: Self = toml::from_str(spec).map_err(SpecError::Toml)?;
+        config.validate().map_err(SpecError::Invalid)?;
+        Ok(config)
+    }
+
+    /// Parses a config from a JSON spec. Options that aren't given keep their defaults.
+    pub fn from_json(spec: &str) -> Result<Self, SpecError> {
+        let config: Self = serde_json::from_str(spec).map_err(SpecError::Json)?;
+        config.validate().map_err(SpecError::Invalid)?;
+        Ok(config)
+    }
+
+    /// Loads a config from a `.toml` or `.json` spec file.
+    pub fn from_spec_file(path: impl AsRef<Path>) -> Result<Self, SpecError> {
+        let path = path.as_ref();
+        let spec = std::fs::read_to_string(path).map_err(SpecError::Io)?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Self::from_toml(&spec),
+            Some("json") => Self::from_json(&spec),
+            _ => Err(SpecError::UnknownFormat(path.to_path_buf())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Deprecated, IdentifierRules, SmartQuotes};
+
+    #[test]
+    fn config_from_toml() {
+        let spec = r#"
+            identifiers = "unicode_xid"
+            smart_quotes = "delimit"
+            additional_whitespace = [","]
+            fuel = 1000
+
+            [[deprecated]]
+            lexeme = "<>"
+            message = "Use != instead"
+        "#;
+        let expected = TokenizerConfig {
+            identifiers: IdentifierRules::UnicodeXid,
+            smart_quotes: SmartQuotes::Delimit,
+            additional_whitespace: vec![','],
+            fuel: Some(1000),
+            deprecated: vec![Deprecated {
+                lexeme: "<>".to_string(),
+                message: "Use != instead".to_string(),
+            }],
+            ..Default::default()
+        };
+        assert_eq!(expected, TokenizerConfig::from_toml(spec).unwrap());
+    }
+
+    #[test]
+    fn config_from_json() {
+        let config = TokenizerConfig::from_json(r#"{"identifiers": "ascii"}"#).unwrap();
+        assert_eq!(config.identifiers, IdentifierRules::Ascii);
+
+        assert!(matches!(
+            TokenizerConfig::from_json(r#"{"identifier": "ascii"}"#),
+            Err(SpecError::Json(_))
+        ));
+        assert!(matches!(
+            TokenizerConfig::from_json(r#"{"fuel": 0}"#),
+            Err(SpecError::Invalid("fuel must be greater than zero"))
+        ));
+    }
+}
@@ -0,0 +1,225 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::PathBuf,
+};
+
+use crate::{
+    json::escape,
+    location::Location,
+    token::{Token, TokenKind, TokenKindTag},
+};
+
+/// A summary of a token stream, for code metrics.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TokenStats {
+    /// The number of tokens of each kind.
+    pub counts: BTreeMap<TokenKindTag, usize>,
+    pub total: usize,
+    /// The fraction of tokens that are comments, or 0 if there are no tokens.
+    pub comment_ratio: f64,
+    /// The length in characters of the longest identifier.
+    pub max_identifier_length: usize,
+    /// The number of distinct lines that a token starts on.
+    pub lines: usize,
+}
+impl TokenStats {
+    /// Returns the number of tokens of the kind.
+    pub fn count(&self, tag: TokenKindTag) -> usize {
+        self.counts.get(&tag).copied().unwrap_or(0)
+    }
+
+    /// Adds the stats of another stream, such as another file, to these.
+    pub fn combine(&mut self, other: &TokenStats) {
+        for (tag, count) in &other.counts {
+            *self.counts.entry(*tag).or_insert(0) += count;
+        }
+        self.total += other.total;
+        self.comment_ratio = if self.total == 0 {
+            0.0
+        } else {
+            self.count(TokenKindTag::Comment) as f64 / self.total as f64
+        };
+        self.max_identifier_length = self.max_identifier_length.max(other.max_identifier_length);
+        self.lines += other.lines;
+    }
+
+    /// Serializes the stats as a JSON object with the `tokens`, `lines`, `comment_ratio`,
+    /// `max_identifier_length` and the count of each kind by name in `kinds`.
+    pub fn to_json(&self) -> String {
+        let kinds: Vec<String> = self
+            .counts
+            .iter()
+            .map(|(tag, count)| format!("{}:{}", escape(tag.name()), count))
+            .collect();
+        format!(
+            "{{\"tokens\":{},\"lines\":{},\"comment_ratio\":{},\"max_identifier_length\":{},\"kinds\":{{{}}}}}",
+            self.total,
+            self.lines,
+            self.comment_ratio,
+            self.max_identifier_length,
+            kinds.join(",")
+        )
+    }
+}
+impl From<&[Token]> for TokenStats {
+    fn from(tokens: &[Token]) -> Self {
+        let mut counts = BTreeMap::new();
+        let mut max_identifier_length = 0;
+        let mut lines = HashSet::new();
+        for token in tokens {
+            *counts.entry(token.kind.tag()).or_insert(0) += 1;
+            if let TokenKind::Identifier(s) = &token.kind {
+                max_identifier_length = max_identifier_length.max(s.chars().count());
+            }
+            lines.insert((&token.location.path, token.location.row));
+        }
+
+        let comments = counts.get(&TokenKindTag::Comment).copied().unwrap_or(0);
+        Self {
+            counts,
+            total: tokens.len(),
+            comment_ratio: if tokens.is_empty() {
+                0.0
+            } else {
+                comments as f64 / tokens.len() as f64
+            },
+            max_identifier_length,
+            lines: lines.len(),
+        }
+    }
+}
+
+/// Serializes the stats of each file as a JSON object with a `files` array, holding each
+/// file's `path` and `stats`, and the `total` stats across the files.
+pub fn file_stats_to_json(files: &[(PathBuf, TokenStats)]) -> String {
+    let mut total = TokenStats::default();
+    let files: Vec<String> = files
+        .iter()
+        .map(|(path, stats)| {
+            total.combine(stats);
+            format!(
+                "{{\"path\":{},\"stats\":{}}}",
+                escape(&path.display().to_string()),
+                stats.to_json()
+            )
+        })
+        .collect();
+    format!(
+        "{{\"files\":[{}],\"total\":{}}}",
+        files.join(","),
+        total.to_json()
+    )
+}
+
+/// Every identifier in a token stream with the locations it occurs at, for cross-reference
+/// indexes and finding usages without a parser.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IdentifierTable {
+    occurrences: BTreeMap<String, Vec<Location>>,
+}
+impl IdentifierTable {
+    /// Collects the identifiers in the tokens, leaving out any in `keywords`.
+    pub fn new(tokens: &[Token], keywords: &[&str]) -> Self {
+        let mut occurrences: BTreeMap<String, Vec<Location>> = BTreeMap::new();
+        for token in tokens {
+            if let TokenKind::Identifier(s) = &token.kind {
+                if !keywords.contains(&s.as_str()) {
+                    occurrences
+                        .entry(s.to_string())
+                        .or_default()
+                        .push(token.location.clone());
+                }
+            }
+        }
+        Self { occurrences }
+    }
+
+    /// Returns the number of times the identifier occurs.
+    pub fn count(&self, name: &str) -> usize {
+        self.locations(name).len()
+    }
+
+    /// Returns the locations of the identifier in the order they occur.
+    pub fn locations(&self, name: &str) -> &[Location] {
+        self.occurrences.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns each identifier with its locations, in alphabetical order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[Location])> {
+        self.occurrences
+            .iter()
+            .map(|(name, locations)| (name.as_str(), locations.as_slice()))
+    }
+
+    /// Returns each identifier with its count, most frequent first and alphabetical among
+    /// equals.
+    pub fn by_frequency(&self) -> Vec<(&str, usize)> {
+        let mut counts: Vec<_> = self
+            .iter()
+            .map(|(name, locations)| (name, locations.len()))
+            .collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        counts
+    }
+
+    /// Returns the number of distinct identifiers.
+    pub fn len(&self) -> usize {
+        self.occurrences.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.occurrences.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::tokenize;
+
+    #[test]
+    fn stats_summarize_tokens() {
+        let tokens = tokenize(
+            "# setup\nlong_name = 1\n\nx = long_name # again",
+            (0, 0).into(),
+        )
+        .unwrap();
+        let stats = TokenStats::from(tokens.as_slice());
+        assert_eq!(stats.total, 8);
+        assert_eq!(stats.count(TokenKindTag::Identifier), 3);
+        assert_eq!(stats.count(TokenKindTag::Comment), 2);
+        assert_eq!(stats.count(TokenKindTag::StringLiteral), 0);
+        assert_eq!(stats.comment_ratio, 0.25);
+        assert_eq!(stats.max_identifier_length, 9);
+        assert_eq!(stats.lines, 3);
+
+        assert_eq!(TokenStats::from(&[][..]), TokenStats::default());
+
+        let mut combined = stats.clone();
+        combined.combine(&TokenStats::from(&tokens[1..4]));
+        assert_eq!(combined.total, 11);
+        assert_eq!(combined.lines, 4);
+        assert_eq!(combined.comment_ratio, 2.0 / 11.0);
+        assert_eq!(
+            TokenStats::from(&tokens[..2]).to_json(),
+            concat!(
+                "{\"tokens\":2,\"lines\":2,\"comment_ratio\":0.5,\"max_identifier_length\":9,",
+                "\"kinds\":{\"comment\":1,\"identifier\":1}}"
+            )
+        );
+    }
+
+    #[test]
+    fn identifiers_are_counted_with_locations() {
+        let tokens = tokenize("let b = a\nlet a = b + a", (0, 0).into()).unwrap();
+        let table = IdentifierTable::new(&tokens, &["let"]);
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.count("let"), 0);
+        assert_eq!(table.by_frequency(), [("a", 3), ("b", 2)]);
+        assert_eq!(
+            table.locations("b"),
+            [Location::from((0, 4)), Location::from((1, 8))]
+        );
+        assert_eq!(IdentifierTable::new(&tokens, &[]).count("let"), 2);
+    }
+}
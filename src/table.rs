@@ -0,0 +1,126 @@
+use std::io::{self, Write};
+
+use crate::token::Token;
+
+/// A delimited text format for token tables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableFormat {
+    /// Comma separated values, with fields quoted as in RFC 4180 when needed.
+    Csv,
+    /// Tab separated values, with tabs, line breaks and backslashes escaped with a
+    /// backslash, as spreadsheets and `COPY` in SQL databases read them.
+    Tsv,
+}
+impl TableFormat {
+    fn delimiter(self) -> char {
+        match self {
+            TableFormat::Csv => ',',
+            TableFormat::Tsv => '\t',
+        }
+    }
+
+    fn field(self, field: &str) -> String {
+        match self {
+            TableFormat::Csv if field.contains([',', '"', '\n', '\r']) => {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            }
+            TableFormat::Csv => field.to_string(),
+            TableFormat::Tsv => {
+                let mut escaped = String::with_capacity(field.len());
+                for c in field.chars() {
+                    match c {
+                        '\\' => escaped.push_str("\\\\"),
+                        '\t' => escaped.push_str("\\t"),
+                        '\n' => escaped.push_str("\\n"),
+                        '\r' => escaped.push_str("\\r"),
+                        c => escaped.push(c),
+                    }
+                }
+                escaped
+            }
+        }
+    }
+}
+
+/// The header of a token table.
+const COLUMNS: [&str; 7] = [
+    "path",
+    "row",
+    "column",
+    "byte_start",
+    "byte_end",
+    "kind",
+    "text",
+];
+
+/// Writes a header and then a row per token, with the token's path, zero based row and
+/// column, byte span, kind name and contents. Tokens without a path get an empty one.
+pub fn write_token_table(
+    writer: &mut impl Write,
+    tokens: &[Token],
+    format: TableFormat,
+) -> io::Result<()> {
+    write_row(writer, format, &COLUMNS.map(String::from))?;
+    for token in tokens {
+        let path = match &token.location.path {
+            Some(path) => path.display().to_string(),
+            None => String::new(),
+        };
+        write_row(
+            writer,
+            format,
+            &[
+                path,
+                token.location.row.to_string(),
+                token.location.column.to_string(),
+                token.span.start.to_string(),
+                token.span.end.to_string(),
+                token.kind.name().to_string(),
+                token.contents.to_string(),
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+/// Returns the token table as a string, as with `write_token_table`.
+pub fn tokens_to_table(tokens: &[Token], format: TableFormat) -> String {
+    let mut table = Vec::new();
+    write_token_table(&mut table, tokens, format).expect("writing to a Vec can't fail");
+    String::from_utf8(table).expect("the table is built from strings")
+}
+
+fn write_row(writer: &mut impl Write, format: TableFormat, fields: &[String]) -> io::Result<()> {
+    let fields: Vec<String> = fields.iter().map(|field| format.field(field)).collect();
+    writeln!(writer, "{}", fields.join(&format.delimiter().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{location::Location, tokenizer::tokenize};
+
+    #[test]
+    fn tokens_are_written_as_rows() {
+        let location = Location::new(0, 0, Some("a.cfg".into()));
+        let tokens = tokenize("x = \"a,\\\"b\\\"\"\n\"\\tc\"", location).unwrap();
+
+        let expected = concat!(
+            "path,row,column,byte_start,byte_end,kind,text\n",
+            "a.cfg,0,0,0,1,identifier,x\n",
+            "a.cfg,0,2,2,3,symbol,=\n",
+            "a.cfg,0,4,4,13,string_literal,\"\"\"a,\\\"\"b\\\"\"\"\"\"\n",
+            "a.cfg,1,0,14,19,string_literal,\"\"\"\\tc\"\"\"\n",
+        );
+        assert_eq!(tokens_to_table(&tokens, TableFormat::Csv), expected);
+
+        let expected = concat!(
+            "path\trow\tcolumn\tbyte_start\tbyte_end\tkind\ttext\n",
+            "a.cfg\t0\t0\t0\t1\tidentifier\tx\n",
+            "a.cfg\t0\t2\t2\t3\tsymbol\t=\n",
+            "a.cfg\t0\t4\t4\t13\tstring_literal\t\"a,\\\\\"b\\\\\"\"\n",
+            "a.cfg\t1\t0\t14\t19\tstring_literal\t\"\\\\tc\"\n",
+        );
+        assert_eq!(tokens_to_table(&tokens, TableFormat::Tsv), expected);
+    }
+}
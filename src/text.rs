@@ -0,0 +1,117 @@
+use std::{borrow::Borrow, fmt::Display, ops::Deref};
+
+#[cfg(not(feature = "compact_str"))]
+type Inner = Box<str>;
+#[cfg(feature = "compact_str")]
+type Inner = compact_str::CompactString;
+
+/// The text of a token. This is a `Box<str>`, or with the `compact_str` feature a
+/// `CompactString` that stores short text inline without allocating.
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct TokenText(Inner);
+impl TokenText {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for TokenText {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+impl AsRef<str> for TokenText {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+impl Borrow<str> for TokenText {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for TokenText {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+impl Display for TokenText {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self.as_str(), f)
+    }
+}
+
+impl From<String> for TokenText {
+    fn from(s: String) -> Self {
+        Self(s.into())
+    }
+}
+impl From<&str> for TokenText {
+    fn from(s: &str) -> Self {
+        Self(s.into())
+    }
+}
+impl From<char> for TokenText {
+    fn from(c: char) -> Self {
+        Self(c.to_string().into())
+    }
+}
+impl From<TokenText> for String {
+    fn from(text: TokenText) -> Self {
+        text.as_str().to_string()
+    }
+}
+
+impl PartialEq<str> for TokenText {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+impl PartialEq<&str> for TokenText {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+impl PartialEq<String> for TokenText {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+impl PartialEq<TokenText> for str {
+    fn eq(&self, other: &TokenText) -> bool {
+        self == other.as_str()
+    }
+}
+impl PartialEq<TokenText> for &str {
+    fn eq(&self, other: &TokenText) -> bool {
+        *self == other.as_str()
+    }
+}
+impl PartialEq<TokenText> for String {
+    fn eq(&self, other: &TokenText) -> bool {
+        self == other.as_str()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_compares_with_strings() {
+        let text = TokenText::from("abc");
+        assert_eq!(text, "abc");
+        assert_eq!(text, "abc".to_string());
+        assert_eq!("abc", text);
+        assert_eq!(format!("{} {:?}", text, text), "abc \"abc\"");
+        assert_eq!(String::from(text), "abc");
+    }
+
+    #[test]
+    fn text_is_smaller_than_string() {
+        assert!(std::mem::size_of::<TokenText>() <= std::mem::size_of::<String>());
+    }
+}
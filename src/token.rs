@@ -1,99 +1,631 @@
-use std::fmt::Display;
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    hash::{Hash, Hasher},
+};
 
-use crate::{error::Error, location::Location};
+use crate::{
+    error::{Error, ErrorKind, Mismatch},
+    location::Location,
+    span::Span,
+    text::TokenText,
+};
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Token {
     pub location: Location,
-    pub contents: String,
+    /// The byte range of the token in the source it was read from.
+    pub span: Span,
+    /// The text of the token. String literals keep their quotes and escapes as written,
+    /// with the decoded value in the kind.
+    pub contents: TokenText,
     pub kind: TokenKind,
+    /// Whether the token was read from the source or added by tooling.
+    pub provenance: Provenance,
 }
 impl Token {
+    /// Creates a token added by tooling rather than read from the source, such as an
+    /// automatically inserted terminator or a placeholder from error recovery. It takes its
+    /// location and span from the token it was derived from.
+    pub fn synthesized(
+        kind: TokenKind,
+        contents: &str,
+        reason: &'static str,
+        derived_from: &Token,
+    ) -> Self {
+        Self {
+            location: derived_from.location.clone(),
+            span: derived_from.span,
+            contents: contents.into(),
+            kind,
+            provenance: Provenance::Synthesized {
+                reason,
+                derived_from: Some(Box::new(Origin::from(derived_from))),
+            },
+        }
+    }
+
+    /// Returns true if the token was added by tooling rather than read from the source.
+    pub fn is_synthesized(&self) -> bool {
+        matches!(self.provenance, Provenance::Synthesized { .. })
+    }
+
+    /// Returns a short, one line description of the token, such as `0:4 identifier "foo"`,
+    /// which keeps snapshots and test diffs readable.
+    pub fn to_compact_string(&self) -> String {
+        format!(
+            "{}:{} {} {:?}",
+            self.location.row,
+            self.location.column,
+            self.kind.name(),
+            &*self.contents
+        )
+    }
+
     pub fn assert_string(&self, msg: &str) -> Result<String, Error> {
         if let TokenKind::StringLiteral(s) = &self.kind {
-            Ok(s.clone())
+            Ok(s.to_string())
         } else {
-            Err(Error {
-                location: self.location.clone(),
-                message: format!("Expected {}, got {}", msg, self.kind),
-            })
+            Err(self.mismatch(msg))
         }
     }
 
+    /// Returns the trimmed text of a comment.
     pub fn assert_comment(&self, msg: &str) -> Result<String, Error> {
-        if let TokenKind::Comment(s) = &self.kind {
-            Ok(s.clone())
+        if let TokenKind::Comment(comment) = &self.kind {
+            Ok(comment.text.to_string())
         } else {
-            Err(Error {
-                location: self.location.clone(),
-                message: format!("Expected {}, got {}", msg, self.kind),
-            })
+            Err(self.mismatch(msg))
         }
     }
 
     pub fn assert_identifier(&self, msg: &str) -> Result<String, Error> {
         if let TokenKind::Identifier(s) = &self.kind {
-            Ok(s.clone())
+            Ok(s.to_string())
         } else {
-            Err(Error {
-                location: self.location.clone(),
-                message: format!("Expected {}, got {}", msg, self.kind),
-            })
+            Err(self.mismatch(msg))
         }
     }
 
     pub fn assert_symbol(&self, msg: &str) -> Result<String, Error> {
         if let TokenKind::Symbol(s) = &self.kind {
-            Ok(s.clone())
+            Ok(s.to_string())
         } else {
-            Err(Error {
-                location: self.location.clone(),
-                message: format!("Expected {}, got {}", msg, self.kind),
-            })
+            Err(self.mismatch(msg))
         }
     }
 
     pub fn assert_int(&self, msg: &str) -> Result<i128, Error> {
         if let TokenKind::IntegerLiteral(i) = &self.kind {
-            Ok(i.clone())
+            Ok(*i)
         } else {
-            Err(Error {
-                location: self.location.clone(),
-                message: format!("Expected {}, got {}", msg, self.kind),
-            })
+            Err(self.mismatch(msg))
         }
     }
 
     pub fn assert_float(&self, msg: &str) -> Result<f64, Error> {
         if let TokenKind::FloatLiteral(f) = &self.kind {
-            Ok(f.clone())
+            Ok(*f)
         } else {
-            Err(Error {
-                location: self.location.clone(),
-                message: format!("Expected {}, got {}", msg, self.kind),
-            })
+            Err(self.mismatch(msg))
+        }
+    }
+
+    /// Returns an error saying that `expected` was expected instead of this token.
+    pub(crate) fn mismatch(&self, expected: &str) -> Error {
+        Error {
+            kind: ErrorKind::UnexpectedToken,
+            location: self.location.clone(),
+            message: format!("Expected {}, got {}", expected, self.kind),
+            span: self.span,
+            source_line: None,
+            mismatch: Some(Box::new(Mismatch {
+                expected: expected.to_string(),
+                found: self.kind.clone(),
+            })),
+            label: None,
+            args: Box::new([
+                ("expected", expected.to_string()),
+                ("found", self.kind.to_string()),
+            ]),
+        }
+    }
+
+    /// Returns the token's text as it appears in `source`, the text it was read from,
+    /// including any quotes, even if the tokenizer normalized it.
+    pub fn text<'a>(&self, source: &'a str) -> &'a str {
+        &source[self.span.start..self.span.end]
+    }
+
+    /// Returns the location just past the token in `source`, the text it was read from,
+    /// which is on a later row than `location` for strings that span lines.
+    pub fn end_location(&self, source: &str) -> Location {
+        let text = self.text(source);
+        let mut end = self.location.clone();
+        match text.rfind('\n') {
+            Some(last) => {
+                end.row += text.matches('\n').count();
+                end.column = text[last + 1..].chars().count();
+            }
+            None => end.column += text.chars().count(),
+        }
+        end
+    }
+
+    /// Returns the lexeme as it would be written in source, the same as `to_string`.
+    pub fn to_source(&self) -> String {
+        self.to_string()
+    }
+
+    /// Returns a snake case name for the token's kind, such as `string_literal`.
+    pub fn kind_name(&self) -> &'static str {
+        self.kind.name()
+    }
+
+    /// Returns true if the token is the symbol `s`, such as `=>`.
+    pub fn is_symbol(&self, s: &str) -> bool {
+        self.as_symbol() == Some(s)
+    }
+
+    /// Returns true if the token is the identifier `s`.
+    pub fn is_ident(&self, s: &str) -> bool {
+        self.as_identifier() == Some(s)
+    }
+
+    /// Returns true if the token is the keyword `s`. Keywords are read as identifiers, so
+    /// this is `is_ident` under a name that reads better in parsers.
+    pub fn is_keyword(&self, s: &str) -> bool {
+        self.is_ident(s)
+    }
+
+    /// Returns the value of a string literal without copying it.
+    pub fn as_str_literal(&self) -> Option<&str> {
+        match &self.kind {
+            TokenKind::StringLiteral(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the trimmed text of a comment.
+    pub fn as_comment(&self) -> Option<&str> {
+        match &self.kind {
+            TokenKind::Comment(comment) => Some(&comment.text),
+            _ => None,
+        }
+    }
+
+    pub fn as_identifier(&self) -> Option<&str> {
+        match &self.kind {
+            TokenKind::Identifier(s) => Some(s),
+            _ => None,
         }
     }
+
+    pub fn as_symbol(&self) -> Option<&str> {
+        match &self.kind {
+            TokenKind::Symbol(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i128> {
+        match self.kind {
+            TokenKind::IntegerLiteral(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    pub fn as_float(&self) -> Option<f64> {
+        match self.kind {
+            TokenKind::FloatLiteral(f) => Some(f),
+            _ => None,
+        }
+    }
+
+    /// Returns the exact digits of a decimal literal, such as `19.99`.
+    pub fn as_decimal(&self) -> Option<&str> {
+        match &self.kind {
+            TokenKind::DecimalLiteral(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+/// Writes the lexeme as it appeared in source, with the marker before comments. Strings
+/// are written with their quotes and escapes as written.
+impl Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            TokenKind::Comment(comment) => match comment.style {
+                CommentStyle::Line => write!(f, "#{}", comment.raw),
+                CommentStyle::Doc => write!(f, "##{}", comment.raw),
+                CommentStyle::Block => write!(f, "#|{}|#", comment.raw),
+            },
+            _ => write!(f, "{}", self.contents),
+        }
+    }
+}
+
+/// Describes the tokens one per line, as with `Token::to_compact_string`.
+pub fn tokens_to_compact_string(tokens: &[Token]) -> String {
+    let lines: Vec<String> = tokens.iter().map(Token::to_compact_string).collect();
+    lines.join("\n")
+}
+
+/// Reads the value of an integer literal.
+impl TryFrom<&Token> for i128 {
+    type Error = Error;
+
+    fn try_from(token: &Token) -> Result<Self, Error> {
+        token.assert_int("integer")
+    }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// Reads the value of a float literal.
+impl TryFrom<&Token> for f64 {
+    type Error = Error;
+
+    fn try_from(token: &Token) -> Result<Self, Error> {
+        token.assert_float("float")
+    }
+}
+
+/// Borrows the value of a string literal.
+impl<'a> TryFrom<&'a Token> for &'a str {
+    type Error = Error;
+
+    fn try_from(token: &'a Token) -> Result<Self, Error> {
+        token
+            .as_str_literal()
+            .ok_or_else(|| token.mismatch("string"))
+    }
+}
+
+/// A kind of token with its payload. Floats are compared by bit pattern so kinds can be
+/// map keys: `NaN` equals itself and `0.0` differs from `-0.0`.
+#[derive(Debug, Clone)]
 pub enum TokenKind {
-    StringLiteral(String),
-    Comment(String),
-    Identifier(String),
-    Symbol(String),
+    StringLiteral(TokenText),
+    Comment(Box<Comment>),
+    Identifier(TokenText),
+    Symbol(TokenText),
     IntegerLiteral(i128),
+    /// An integer literal too large for an `i128`.
+    #[cfg(feature = "bigint")]
+    BigIntegerLiteral(num_bigint::BigInt),
     FloatLiteral(f64),
+    /// A number with a fractional part, kept as its digits so no precision is lost. Any
+    /// Unicode digits are mapped to ASCII.
+    DecimalLiteral(TokenText),
+    /// A number followed by `%`, such as `12.5%`, holding the number as written rather
+    /// than divided by 100, with any Unicode digits mapped to ASCII.
+    PercentLiteral(TokenText),
+    /// An ISO 8601 date, date-time or time, such as `2024-05-01T12:30:00Z`, as written.
+    DateTimeLiteral(TokenText),
+    /// A hex color such as `#aabbcc`, holding the digits after the `#` as written.
+    ColorLiteral(TokenText),
+    /// An attribute such as `@name` or `#[derive(Debug)]`, holding the name or the trimmed
+    /// text inside the brackets.
+    Attribute(TokenText),
+    /// An identifier marked by a configured sigil, such as `$foo`.
+    Variable {
+        sigil: char,
+        name: TokenText,
+    },
+    /// A preprocessor-style directive that takes up the rest of its line, such as
+    /// `#include <stdio.h>`.
+    Directive(Box<Directive>),
+    /// A number with a configured unit suffix, such as `250ms` or `2GiB`. The number is
+    /// held as written, like a percent's.
+    UnitLiteral {
+        value: TokenText,
+        unit: Box<str>,
+    },
+    Unknown(TokenText),
+}
+impl TokenKind {
+    /// Returns the kind without its payload.
+    pub fn tag(&self) -> TokenKindTag {
+        match self {
+            TokenKind::StringLiteral(_) => TokenKindTag::StringLiteral,
+            TokenKind::Comment(_) => TokenKindTag::Comment,
+            TokenKind::Identifier(_) => TokenKindTag::Identifier,
+            TokenKind::Symbol(_) => TokenKindTag::Symbol,
+            TokenKind::IntegerLiteral(_) => TokenKindTag::IntegerLiteral,
+            #[cfg(feature = "bigint")]
+            TokenKind::BigIntegerLiteral(_) => TokenKindTag::IntegerLiteral,
+            TokenKind::FloatLiteral(_) => TokenKindTag::FloatLiteral,
+            TokenKind::DecimalLiteral(_) => TokenKindTag::DecimalLiteral,
+            TokenKind::PercentLiteral(_) => TokenKindTag::PercentLiteral,
+            TokenKind::DateTimeLiteral(_) => TokenKindTag::DateTimeLiteral,
+            TokenKind::ColorLiteral(_) => TokenKindTag::ColorLiteral,
+            TokenKind::Attribute(_) => TokenKindTag::Attribute,
+            TokenKind::Variable { .. } => TokenKindTag::Variable,
+            TokenKind::Directive(_) => TokenKindTag::Directive,
+            TokenKind::UnitLiteral { .. } => TokenKindTag::UnitLiteral,
+            TokenKind::Unknown(_) => TokenKindTag::Unknown,
+        }
+    }
+
+    fn text(&self) -> Option<&TokenText> {
+        match self {
+            TokenKind::StringLiteral(s)
+            | TokenKind::Identifier(s)
+            | TokenKind::Symbol(s)
+            | TokenKind::DecimalLiteral(s)
+            | TokenKind::PercentLiteral(s)
+            | TokenKind::DateTimeLiteral(s)
+            | TokenKind::ColorLiteral(s)
+            | TokenKind::Attribute(s)
+            | TokenKind::Unknown(s) => Some(s),
+            TokenKind::IntegerLiteral(_)
+            | TokenKind::FloatLiteral(_)
+            | TokenKind::Comment(_)
+            | TokenKind::Variable { .. }
+            | TokenKind::Directive(_)
+            | TokenKind::UnitLiteral { .. } => None,
+            #[cfg(feature = "bigint")]
+            TokenKind::BigIntegerLiteral(_) => None,
+        }
+    }
+
+    /// Returns a snake case name for the kind, such as `string_literal`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TokenKind::StringLiteral(_) => "string_literal",
+            TokenKind::Comment(_) => "comment",
+            TokenKind::Identifier(_) => "identifier",
+            TokenKind::Symbol(_) => "symbol",
+            TokenKind::IntegerLiteral(_) => "integer_literal",
+            #[cfg(feature = "bigint")]
+            TokenKind::BigIntegerLiteral(_) => "integer_literal",
+            TokenKind::FloatLiteral(_) => "float_literal",
+            TokenKind::DecimalLiteral(_) => "decimal_literal",
+            TokenKind::PercentLiteral(_) => "percent_literal",
+            TokenKind::DateTimeLiteral(_) => "datetime_literal",
+            TokenKind::ColorLiteral(_) => "color_literal",
+            TokenKind::Attribute(_) => "attribute",
+            TokenKind::Variable { .. } => "variable",
+            TokenKind::Directive(_) => "directive",
+            TokenKind::UnitLiteral { .. } => "unit_literal",
+            TokenKind::Unknown(_) => "unknown",
+        }
+    }
+
+    pub fn is_identifier(&self) -> bool {
+        matches!(self, TokenKind::Identifier(_))
+    }
+
+    pub fn is_symbol(&self) -> bool {
+        matches!(self, TokenKind::Symbol(_))
+    }
+
+    pub fn is_comment(&self) -> bool {
+        matches!(self, TokenKind::Comment(_))
+    }
+
+    /// Returns true for string, number, percent, datetime, color and unit literals.
+    pub fn is_literal(&self) -> bool {
+        matches!(
+            self.tag(),
+            TokenKindTag::StringLiteral
+                | TokenKindTag::IntegerLiteral
+                | TokenKindTag::FloatLiteral
+                | TokenKindTag::DecimalLiteral
+                | TokenKindTag::PercentLiteral
+                | TokenKindTag::DateTimeLiteral
+                | TokenKindTag::ColorLiteral
+                | TokenKindTag::UnitLiteral
+        )
+    }
+}
+impl PartialEq for TokenKind {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl Eq for TokenKind {}
+impl Hash for TokenKind {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.tag().hash(state);
+        match self {
+            TokenKind::IntegerLiteral(i) => i.hash(state),
+            // Hashed like an `IntegerLiteral` when it fits, as the two compare equal.
+            #[cfg(feature = "bigint")]
+            TokenKind::BigIntegerLiteral(i) => match i128::try_from(i) {
+                Ok(i) => i.hash(state),
+                Err(_) => i.hash(state),
+            },
+            TokenKind::FloatLiteral(f) => f.to_bits().hash(state),
+            TokenKind::Comment(comment) => comment.hash(state),
+            TokenKind::Variable { sigil, name } => (sigil, name).hash(state),
+            TokenKind::Directive(directive) => directive.hash(state),
+            TokenKind::UnitLiteral { value, unit } => (value, unit).hash(state),
+            _ => self.text().hash(state),
+        }
+    }
+}
+impl PartialOrd for TokenKind {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+/// Orders by kind, in declaration order, then by payload.
+impl Ord for TokenKind {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (TokenKind::IntegerLiteral(a), TokenKind::IntegerLiteral(b)) => a.cmp(b),
+            #[cfg(feature = "bigint")]
+            (TokenKind::BigIntegerLiteral(a), TokenKind::BigIntegerLiteral(b)) => a.cmp(b),
+            #[cfg(feature = "bigint")]
+            (TokenKind::IntegerLiteral(a), TokenKind::BigIntegerLiteral(b)) => {
+                num_bigint::BigInt::from(*a).cmp(b)
+            }
+            #[cfg(feature = "bigint")]
+            (TokenKind::BigIntegerLiteral(a), TokenKind::IntegerLiteral(b)) => {
+                a.cmp(&num_bigint::BigInt::from(*b))
+            }
+            (TokenKind::FloatLiteral(a), TokenKind::FloatLiteral(b)) => a.total_cmp(b),
+            (TokenKind::Comment(a), TokenKind::Comment(b)) => a.cmp(b),
+            (
+                TokenKind::Variable { sigil, name },
+                TokenKind::Variable {
+                    sigil: other_sigil,
+                    name: other_name,
+                },
+            ) => sigil.cmp(other_sigil).then_with(|| name.cmp(other_name)),
+            (TokenKind::Directive(a), TokenKind::Directive(b)) => a.cmp(b),
+            (
+                TokenKind::UnitLiteral { value, unit },
+                TokenKind::UnitLiteral {
+                    value: other_value,
+                    unit: other_unit,
+                },
+            ) => unit.cmp(other_unit).then_with(|| value.cmp(other_value)),
+            _ => self
+                .tag()
+                .cmp(&other.tag())
+                .then_with(|| self.text().cmp(&other.text())),
+        }
+    }
+}
+
+/// Whether a token was written in the source or added by tooling.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub enum Provenance {
+    /// Read from the source.
+    #[default]
+    Original,
+    /// Added by tooling, such as the tokens spliced in by an include.
+    Synthesized {
+        /// Why the token was added, such as `include expansion`.
+        reason: &'static str,
+        /// Where the source the token stands in for or was derived from was written.
+        derived_from: Option<Box<Origin>>,
+    },
+}
+
+/// Where a token was originally written.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Origin {
+    /// The token's location in its original file, path included.
+    pub location: Location,
+    /// The token's byte range in its original file.
+    pub span: Span,
+}
+impl From<&Token> for Origin {
+    fn from(token: &Token) -> Self {
+        Self {
+            location: token.location.clone(),
+            span: token.span,
+        }
+    }
+}
+
+/// The style and text of a `TokenKind::Comment`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Comment {
+    pub style: CommentStyle,
+    /// Everything after the comment's marker up to the end of the line, or between the
+    /// delimiters of a block comment, as written, so formatters can keep its whitespace.
+    pub raw: TokenText,
+    /// `raw` with whitespace trimmed from both ends.
+    pub text: TokenText,
+}
+impl Comment {
+    /// Creates a comment from the text after its marker.
+    pub fn new(style: CommentStyle, raw: &str) -> Self {
+        Self {
+            style,
+            raw: raw.into(),
+            text: raw.trim().into(),
+        }
+    }
+}
+
+/// How a comment was written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum CommentStyle {
+    /// A comment that starts with `#`.
+    Line,
+    /// A comment that starts with `##`, when `TokenizerConfig::doc_comments` is set.
+    Doc,
+    /// A comment between `#|` and `|#`, when `TokenizerConfig::block_comments` is set.
+    Block,
 }
+
+/// The name and arguments of a `TokenKind::Directive`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Directive {
+    /// The name after the directive character, such as `include`.
+    pub name: TokenText,
+    /// The rest of the line after the name, trimmed.
+    pub arguments: TokenText,
+}
+
+/// The kind of a token without its payload, for cheap matching and use as a map key.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TokenKindTag {
+    StringLiteral,
+    Comment,
+    Identifier,
+    Symbol,
+    IntegerLiteral,
+    FloatLiteral,
+    DecimalLiteral,
+    PercentLiteral,
+    DateTimeLiteral,
+    ColorLiteral,
+    Attribute,
+    Variable,
+    Directive,
+    UnitLiteral,
+    Unknown,
+}
+impl TokenKindTag {
+    /// Returns a snake case name for the kind, the same as `TokenKind::name`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TokenKindTag::StringLiteral => "string_literal",
+            TokenKindTag::Comment => "comment",
+            TokenKindTag::Identifier => "identifier",
+            TokenKindTag::Symbol => "symbol",
+            TokenKindTag::IntegerLiteral => "integer_literal",
+            TokenKindTag::FloatLiteral => "float_literal",
+            TokenKindTag::DecimalLiteral => "decimal_literal",
+            TokenKindTag::PercentLiteral => "percent_literal",
+            TokenKindTag::DateTimeLiteral => "datetime_literal",
+            TokenKindTag::ColorLiteral => "color_literal",
+            TokenKindTag::Attribute => "attribute",
+            TokenKindTag::Variable => "variable",
+            TokenKindTag::Directive => "directive",
+            TokenKindTag::UnitLiteral => "unit_literal",
+            TokenKindTag::Unknown => "unknown",
+        }
+    }
+}
+
 impl Display for TokenKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             TokenKind::StringLiteral(s) => write!(f, "string \"{}\"", s),
-            TokenKind::Comment(s) => write!(f, "comment \"{}\"", s),
+            TokenKind::Comment(comment) => write!(f, "comment \"{}\"", comment.text),
             TokenKind::Identifier(s) => write!(f, "identifier '{}'", s),
             TokenKind::Symbol(s) => write!(f, "symbol '{}'", s),
             TokenKind::IntegerLiteral(i) => write!(f, "int '{}'", i),
+            #[cfg(feature = "bigint")]
+            TokenKind::BigIntegerLiteral(i) => write!(f, "int '{}'", i),
             TokenKind::FloatLiteral(float) => write!(f, "float '{}'", float),
+            TokenKind::DecimalLiteral(s) => write!(f, "decimal '{}'", s),
+            TokenKind::PercentLiteral(percent) => write!(f, "percent '{}%'", percent),
+            TokenKind::DateTimeLiteral(s) => write!(f, "datetime '{}'", s),
+            TokenKind::ColorLiteral(s) => write!(f, "color '#{}'", s),
+            TokenKind::Attribute(s) => write!(f, "attribute '{}'", s),
+            TokenKind::Variable { sigil, name } => write!(f, "variable '{}{}'", sigil, name),
+            TokenKind::Directive(directive) => write!(f, "directive '{}'", directive.name),
+            TokenKind::UnitLiteral { value, unit } => write!(f, "unit '{}{}'", value, unit),
+            TokenKind::Unknown(s) => write!(f, "unknown '{}'", s),
         }
     }
 }
@@ -106,8 +638,10 @@ mod tests {
     fn assert_string_works() {
         let token = Token {
             location: (0, 0).into(),
-            contents: "jaja".to_string(),
-            kind: TokenKind::StringLiteral("jaja".to_string()),
+            span: Span::default(),
+            contents: "jaja".into(),
+            kind: TokenKind::StringLiteral("jaja".into()),
+            provenance: Provenance::Original,
         };
 
         let expected = Ok("jaja".to_string());
@@ -117,12 +651,26 @@ mod tests {
         // Test with wrong type
         let token = Token {
             location: (0, 0).into(),
-            contents: "jaja".to_string(),
-            kind: TokenKind::Identifier("jaja".to_string()),
+            span: Span::default(),
+            contents: "jaja".into(),
+            kind: TokenKind::Identifier("jaja".into()),
+            provenance: Provenance::Original,
         };
 
         let expected = Err(Error {
+            kind: ErrorKind::UnexpectedToken,
             location: (0, 0).into(),
+            span: Span::default(),
+            source_line: None,
+            mismatch: Some(Box::new(Mismatch {
+                expected: "msg".to_string(),
+                found: TokenKind::Identifier("jaja".into()),
+            })),
+            label: None,
+            args: Box::new([
+                ("expected", "msg".to_string()),
+                ("found", "identifier 'jaja'".to_string()),
+            ]),
             message: "Expected msg, got identifier 'jaja'".to_string(),
         });
         let actual = token.assert_string("msg");
@@ -133,8 +681,10 @@ mod tests {
     fn assert_comment_works() {
         let token = Token {
             location: (0, 0).into(),
-            contents: "jaja".to_string(),
-            kind: TokenKind::Comment("jaja".to_string()),
+            span: Span::default(),
+            contents: "jaja".into(),
+            kind: TokenKind::Comment(Box::new(Comment::new(CommentStyle::Line, "jaja"))),
+            provenance: Provenance::Original,
         };
 
         let expected = Ok("jaja".to_string());
@@ -144,12 +694,26 @@ mod tests {
         // Test with wrong type
         let token = Token {
             location: (0, 0).into(),
-            contents: "jaja".to_string(),
-            kind: TokenKind::Identifier("jaja".to_string()),
+            span: Span::default(),
+            contents: "jaja".into(),
+            kind: TokenKind::Identifier("jaja".into()),
+            provenance: Provenance::Original,
         };
 
         let expected = Err(Error {
+            kind: ErrorKind::UnexpectedToken,
             location: (0, 0).into(),
+            span: Span::default(),
+            source_line: None,
+            mismatch: Some(Box::new(Mismatch {
+                expected: "msg".to_string(),
+                found: TokenKind::Identifier("jaja".into()),
+            })),
+            label: None,
+            args: Box::new([
+                ("expected", "msg".to_string()),
+                ("found", "identifier 'jaja'".to_string()),
+            ]),
             message: "Expected msg, got identifier 'jaja'".to_string(),
         });
         let actual = token.assert_comment("msg");
@@ -160,8 +724,10 @@ mod tests {
     fn assert_identifier_works() {
         let token = Token {
             location: (0, 0).into(),
-            contents: "jaja".to_string(),
-            kind: TokenKind::Identifier("jaja".to_string()),
+            span: Span::default(),
+            contents: "jaja".into(),
+            kind: TokenKind::Identifier("jaja".into()),
+            provenance: Provenance::Original,
         };
 
         let expected = Ok("jaja".to_string());
@@ -171,12 +737,26 @@ mod tests {
         // Test with wrong type
         let token = Token {
             location: (0, 0).into(),
-            contents: "jaja".to_string(),
-            kind: TokenKind::StringLiteral("jaja".to_string()),
+            span: Span::default(),
+            contents: "jaja".into(),
+            kind: TokenKind::StringLiteral("jaja".into()),
+            provenance: Provenance::Original,
         };
 
         let expected = Err(Error {
+            kind: ErrorKind::UnexpectedToken,
             location: (0, 0).into(),
+            span: Span::default(),
+            source_line: None,
+            mismatch: Some(Box::new(Mismatch {
+                expected: "msg".to_string(),
+                found: TokenKind::StringLiteral("jaja".into()),
+            })),
+            label: None,
+            args: Box::new([
+                ("expected", "msg".to_string()),
+                ("found", "string \"jaja\"".to_string()),
+            ]),
             message: "Expected msg, got string \"jaja\"".to_string(),
         });
         let actual = token.assert_identifier("msg");
@@ -187,8 +767,10 @@ mod tests {
     fn assert_symbol_works() {
         let token = Token {
             location: (0, 0).into(),
-            contents: "jaja".to_string(),
-            kind: TokenKind::Symbol("jaja".to_string()),
+            span: Span::default(),
+            contents: "jaja".into(),
+            kind: TokenKind::Symbol("jaja".into()),
+            provenance: Provenance::Original,
         };
 
         let expected = Ok("jaja".to_string());
@@ -198,12 +780,26 @@ mod tests {
         // Test with wrong type
         let token = Token {
             location: (0, 0).into(),
-            contents: "jaja".to_string(),
-            kind: TokenKind::StringLiteral("jaja".to_string()),
+            span: Span::default(),
+            contents: "jaja".into(),
+            kind: TokenKind::StringLiteral("jaja".into()),
+            provenance: Provenance::Original,
         };
 
         let expected = Err(Error {
+            kind: ErrorKind::UnexpectedToken,
             location: (0, 0).into(),
+            span: Span::default(),
+            source_line: None,
+            mismatch: Some(Box::new(Mismatch {
+                expected: "msg".to_string(),
+                found: TokenKind::StringLiteral("jaja".into()),
+            })),
+            label: None,
+            args: Box::new([
+                ("expected", "msg".to_string()),
+                ("found", "string \"jaja\"".to_string()),
+            ]),
             message: "Expected msg, got string \"jaja\"".to_string(),
         });
         let actual = token.assert_symbol("msg");
@@ -214,8 +810,10 @@ mod tests {
     fn assert_int_works() {
         let token = Token {
             location: (0, 0).into(),
-            contents: "jaja".to_string(),
+            span: Span::default(),
+            contents: "jaja".into(),
             kind: TokenKind::IntegerLiteral(123),
+            provenance: Provenance::Original,
         };
 
         let expected = Ok(123);
@@ -225,12 +823,26 @@ mod tests {
         // Test with wrong type
         let token = Token {
             location: (0, 0).into(),
-            contents: "jaja".to_string(),
-            kind: TokenKind::StringLiteral("jaja".to_string()),
+            span: Span::default(),
+            contents: "jaja".into(),
+            kind: TokenKind::StringLiteral("jaja".into()),
+            provenance: Provenance::Original,
         };
 
         let expected = Err(Error {
+            kind: ErrorKind::UnexpectedToken,
             location: (0, 0).into(),
+            span: Span::default(),
+            source_line: None,
+            mismatch: Some(Box::new(Mismatch {
+                expected: "msg".to_string(),
+                found: TokenKind::StringLiteral("jaja".into()),
+            })),
+            label: None,
+            args: Box::new([
+                ("expected", "msg".to_string()),
+                ("found", "string \"jaja\"".to_string()),
+            ]),
             message: "Expected msg, got string \"jaja\"".to_string(),
         });
         let actual = token.assert_int("msg");
@@ -241,8 +853,10 @@ mod tests {
     fn assert_float_works() {
         let token = Token {
             location: (0, 0).into(),
-            contents: "jaja".to_string(),
+            span: Span::default(),
+            contents: "jaja".into(),
             kind: TokenKind::FloatLiteral(123.0),
+            provenance: Provenance::Original,
         };
 
         let expected = Ok(123.0);
@@ -252,15 +866,218 @@ mod tests {
         // Test with wrong type
         let token = Token {
             location: (0, 0).into(),
-            contents: "jaja".to_string(),
-            kind: TokenKind::StringLiteral("jaja".to_string()),
+            span: Span::default(),
+            contents: "jaja".into(),
+            kind: TokenKind::StringLiteral("jaja".into()),
+            provenance: Provenance::Original,
         };
 
         let expected = Err(Error {
+            kind: ErrorKind::UnexpectedToken,
             location: (0, 0).into(),
+            span: Span::default(),
+            source_line: None,
+            mismatch: Some(Box::new(Mismatch {
+                expected: "msg".to_string(),
+                found: TokenKind::StringLiteral("jaja".into()),
+            })),
+            label: None,
+            args: Box::new([
+                ("expected", "msg".to_string()),
+                ("found", "string \"jaja\"".to_string()),
+            ]),
             message: "Expected msg, got string \"jaja\"".to_string(),
         });
         let actual = token.assert_float("msg");
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn borrowing_accessors_work() {
+        let token = Token {
+            location: (0, 0).into(),
+            span: Span::default(),
+            contents: "\"jaja\"".into(),
+            kind: TokenKind::StringLiteral("jaja".into()),
+            provenance: Provenance::Original,
+        };
+        assert_eq!(token.as_str_literal(), Some("jaja"));
+        assert_eq!(token.as_identifier(), None);
+        assert_eq!(<&str>::try_from(&token), Ok("jaja"));
+        assert_eq!(
+            i128::try_from(&token),
+            Err(Error {
+                kind: ErrorKind::UnexpectedToken,
+                location: (0, 0).into(),
+                message: "Expected integer, got string \"jaja\"".to_string(),
+                span: Span::default(),
+                source_line: None,
+                mismatch: Some(Box::new(Mismatch {
+                    expected: "integer".to_string(),
+                    found: TokenKind::StringLiteral("jaja".into()),
+                })),
+                label: None,
+                args: Box::new([
+                    ("expected", "integer".to_string()),
+                    ("found", "string \"jaja\"".to_string()),
+                ]),
+            })
+        );
+
+        let token = Token {
+            location: (0, 0).into(),
+            span: Span::default(),
+            contents: "2.5".into(),
+            kind: TokenKind::FloatLiteral(2.5),
+            provenance: Provenance::Original,
+        };
+        assert_eq!(token.as_float(), Some(2.5));
+        assert_eq!(f64::try_from(&token), Ok(2.5));
+        assert_eq!(token.as_int(), None);
+    }
+
+    #[test]
+    fn kind_predicates_work() {
+        let token = Token {
+            location: (0, 0).into(),
+            span: Span::default(),
+            contents: "12".into(),
+            kind: TokenKind::IntegerLiteral(12),
+            provenance: Provenance::Original,
+        };
+        assert!(token.kind.is_literal());
+        assert!(!token.kind.is_identifier());
+        assert!(!token.kind.is_symbol());
+        assert_eq!(token.kind_name(), "integer_literal");
+
+        assert!(TokenKind::Symbol("+".into()).is_symbol());
+        assert!(TokenKind::Identifier("x".into()).is_identifier());
+        assert!(!TokenKind::Comment(Box::new(Comment::new(CommentStyle::Line, "x"))).is_literal());
+    }
+
+    #[test]
+    fn tags_ignore_payloads() {
+        let a = TokenKind::Identifier("a".into());
+        let b = TokenKind::Identifier("b".into());
+        assert_eq!(a.tag(), b.tag());
+        assert_eq!(a.tag(), TokenKindTag::Identifier);
+        assert_ne!(a.tag(), TokenKind::Symbol("a".into()).tag());
+    }
+
+    #[test]
+    fn matchers_work() {
+        let token = Token {
+            location: (0, 0).into(),
+            span: Span::default(),
+            contents: "=>".into(),
+            kind: TokenKind::Symbol("=>".into()),
+            provenance: Provenance::Original,
+        };
+        assert!(token.is_symbol("=>"));
+        assert!(!token.is_symbol("="));
+        assert!(!token.is_ident("=>"));
+
+        let token = Token {
+            location: (0, 0).into(),
+            span: Span::default(),
+            contents: "let".into(),
+            kind: TokenKind::Identifier("let".into()),
+            provenance: Provenance::Original,
+        };
+        assert!(token.is_ident("let"));
+        assert!(token.is_keyword("let"));
+        assert!(!token.is_keyword("fn"));
+        assert!(!token.is_symbol("let"));
+    }
+
+    #[test]
+    fn display_reconstructs_lexemes() {
+        let contents = r#"let s = "say \"hi\"" #  done
+2.50"#;
+        let tokens = crate::tokenizer::Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
+        let lexemes: Vec<String> = tokens.iter().map(Token::to_source).collect();
+        assert_eq!(
+            lexemes,
+            vec!["let", "s", "=", r#""say \"hi\"""#, "#  done", "2.50"]
+        );
+    }
+
+    #[test]
+    fn kinds_are_usable_as_keys() {
+        use std::collections::{BTreeSet, HashSet};
+
+        let kinds = vec![
+            TokenKind::FloatLiteral(f64::NAN),
+            TokenKind::FloatLiteral(f64::NAN),
+            TokenKind::FloatLiteral(0.0),
+            TokenKind::FloatLiteral(-0.0),
+            TokenKind::Identifier("a".into()),
+            TokenKind::Identifier("a".into()),
+            TokenKind::Symbol("a".into()),
+            TokenKind::IntegerLiteral(1),
+        ];
+        assert_eq!(kinds.iter().collect::<HashSet<_>>().len(), 6);
+
+        let sorted: Vec<_> = kinds
+            .into_iter()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        assert_eq!(
+            sorted,
+            vec![
+                TokenKind::Identifier("a".into()),
+                TokenKind::Symbol("a".into()),
+                TokenKind::IntegerLiteral(1),
+                TokenKind::FloatLiteral(-0.0),
+                TokenKind::FloatLiteral(0.0),
+                TokenKind::FloatLiteral(f64::NAN),
+            ]
+        );
+    }
+
+    #[test]
+    fn compact_strings_are_one_line_per_token() {
+        let tokens = crate::tokenizer::tokenize("foo = \"a b\"\n  2.5", (0, 0).into()).unwrap();
+        let expected = r#"0:0 identifier "foo"
+0:4 symbol "="
+0:6 string_literal "\"a b\""
+1:2 float_literal "2.5""#;
+        assert_eq!(expected, tokens_to_compact_string(&tokens));
+    }
+
+    #[test]
+    fn end_locations_follow_line_breaks_in_strings() {
+        let source = "x = \"a\nbc\" y";
+        let tokens = crate::tokenizer::tokenize(source, (0, 0).into()).unwrap();
+        assert_eq!(tokens[0].end_location(source), (0, 1).into());
+        assert_eq!(tokens[2].location, (0, 4).into());
+        assert_eq!(tokens[2].end_location(source), (1, 3).into());
+        assert_eq!(tokens[3].location, (1, 4).into());
+    }
+
+    #[test]
+    fn synthesized_tokens_point_at_their_source() {
+        let tokens = crate::tokenizer::tokenize("x = 1", (0, 0).into()).unwrap();
+        assert!(!tokens[2].is_synthesized());
+
+        let terminator = Token::synthesized(
+            TokenKind::Symbol(";".into()),
+            ";",
+            "inserted terminator",
+            &tokens[2],
+        );
+        assert!(terminator.is_synthesized());
+        assert_eq!(terminator.location, tokens[2].location);
+        assert_eq!(
+            terminator.provenance,
+            Provenance::Synthesized {
+                reason: "inserted terminator",
+                derived_from: Some(Box::new(Origin {
+                    location: (0, 4).into(),
+                    span: Span::new(4, 5),
+                })),
+            }
+        );
+    }
 }
@@ -1,593 +1,3929 @@
+use std::{collections::VecDeque, ops::ControlFlow, path::Path, sync::Arc};
+
+use unicode_normalization::UnicodeNormalization;
+
 use crate::{
-    error::Error,
+    class::{self, is_smart_quote, CharClass},
+    compiled::CompiledSpec,
+    config::{
+        Attributes, BidiControls, BlockComments, Digits, Fractions, IdentifierCase,
+        InvisibleCharacters, LeadingDots, Normalization, Signs, SmartQuotes, StringNewlines,
+        TokenizerConfig, UnclosedBlockComments, UnexpectedCharacters, UnusualWhitespace,
+    },
+    dfa::Rule,
+    error::{Error, ErrorKind, ErrorWithPartial, Label},
     location::Location,
-    token::{Token, TokenKind},
+    span::Span,
+    token::{Comment, CommentStyle, Directive, Provenance, Token, TokenKind},
+    warning::Warning,
 };
 
 pub fn tokenize(contents: &str, location: Location) -> Result<Vec<Token>, Error> {
     Tokenizer::tokenize(contents, location)
 }
 
+pub fn tokenize_with_config(
+    contents: &str,
+    location: Location,
+    config: &TokenizerConfig,
+) -> Result<Vec<Token>, Error> {
+    Tokenizer::tokenize_with_config(contents, location, config)
+}
+
+pub fn tokenize_with_spec(
+    contents: &str,
+    location: Location,
+    spec: &CompiledSpec,
+) -> Result<Vec<Token>, Error> {
+    Tokenizer::tokenize_with_spec(contents, location, spec)
+}
+
+pub fn tokenize_with_warnings(
+    contents: &str,
+    location: Location,
+    config: &TokenizerConfig,
+) -> Result<(Vec<Token>, Vec<Warning>), Error> {
+    Tokenizer::tokenize_with_warnings(contents, location, config)
+}
+
+pub fn tokenize_fragment(
+    contents: &str,
+    base: Location,
+    base_offset: usize,
+    config: &TokenizerConfig,
+) -> Result<Vec<Token>, Error> {
+    Tokenizer::tokenize_fragment(contents, base, base_offset, config)
+}
+
+pub fn tokenize_recovering(
+    contents: &str,
+    location: Location,
+    config: &TokenizerConfig,
+) -> (Vec<Token>, Vec<Error>) {
+    Tokenizer::tokenize_recovering(contents, location, config)
+}
+
+pub fn tokenize_line(
+    line: &str,
+    row: usize,
+    state: LineState,
+    config: &TokenizerConfig,
+) -> Result<(Vec<Token>, LineState), Error> {
+    Tokenizer::tokenize_line(line, row, state, config)
+}
+
+// Returned once per input, so the size of the error isn't worth boxing it for.
+#[allow(clippy::result_large_err)]
+pub fn tokenize_partial(
+    contents: &str,
+    location: Location,
+    config: &TokenizerConfig,
+) -> Result<Vec<Token>, ErrorWithPartial> {
+    Tokenizer::tokenize_partial(contents, location, config)
+}
+
+/// What a line left open for the next one to continue, when tokenizing line by line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum LineState {
+    /// Nothing is open, so the next line starts fresh.
+    #[default]
+    Normal,
+    /// A string literal is open and ends at the quote.
+    InString { quote: char },
+    /// A quoted identifier is open and ends at the delimiter.
+    InQuotedIdentifier { close: char },
+    /// A block comment is open, with `depth` `|#`s needed to close it.
+    InBlockComment { depth: usize },
+}
+
+/// Called with each token as it's read and the location of the next character.
+type TokenHook = Box<dyn FnMut(&Token, &Location) -> ControlFlow<()> + Send>;
+/// Called with each error as it's produced and the location of the next character.
+type ErrorHook = Box<dyn FnMut(&Error, &Location) + Send>;
+
 pub struct Tokenizer {
     contents: String,
-    index: usize,
     location: Location,
+    /// The config and the tables compiled from it.
+    spec: CompiledSpec,
+    warnings: Vec<Warning>,
+    /// The number of characters that may still be read, if limited.
+    fuel: Option<usize>,
+    /// Storage for `tokens`, reused between inputs.
+    tokens: Vec<Token>,
+    /// The byte offset of the next character in `contents`.
+    offset: usize,
+    /// The byte offset of `contents` in the source it was taken from, added to every span.
+    base_offset: usize,
+    /// What the contents leave open when tokenizing line by line, or `None` when strings,
+    /// quoted identifiers and block comments must close before the end of the contents.
+    line_state: Option<LineState>,
+    /// Whether an unclosed string becomes an `Unknown` token running to the end of its line,
+    /// with its error collected in `errors`, rather than ending tokenizing.
+    recovering: bool,
+    /// The errors recovered from so far.
+    errors: Vec<Error>,
+    /// Tokens read by `next_token` that it hasn't returned yet.
+    pending: VecDeque<Token>,
+    /// Whether `next_token` has checked the contents as a whole before reading tokens.
+    started: bool,
+    /// Whether `next_token` has reached the end of the contents or an error.
+    finished: bool,
+    on_token: Option<TokenHook>,
+    on_error: Option<ErrorHook>,
+    /// Offsets in `contents` paired with the offsets in the original source they came from,
+    /// at the start and end of each run whose length changed when copied in, such as a
+    /// `\r\n` read as `\n` or a normalized character. Used to map spans back to the source.
+    source_offsets: Vec<(usize, usize)>,
 }
 impl Tokenizer {
     pub fn tokenize(contents: &str, location: Location) -> Result<Vec<Token>, Error> {
-        let contents = contents.replace("\r\n", "\n");
+        Self::tokenize_with_config(contents, location, &TokenizerConfig::default())
+    }
+
+    pub fn tokenize_with_config(
+        contents: &str,
+        location: Location,
+        config: &TokenizerConfig,
+    ) -> Result<Vec<Token>, Error> {
+        Self::tokenize_with_warnings(contents, location, config).map(|(tokens, _)| tokens)
+    }
+
+    /// Tokenizes the contents with a compiled spec, saving the work of compiling the config
+    /// for each input.
+    pub fn tokenize_with_spec(
+        contents: &str,
+        location: Location,
+        spec: &CompiledSpec,
+    ) -> Result<Vec<Token>, Error> {
+        let mut tokenizer = Self::from_spec(contents, location, spec.clone());
         let mut tokens = Vec::new();
-        let mut tokenizer = Tokenizer {
-            index: 0,
-            contents,
-            location,
+        tokenizer.run(&mut tokens)?;
+        Ok(tokens)
+    }
+
+    /// Tokenizes the contents, returning the tokens along with any non-fatal warnings.
+    pub fn tokenize_with_warnings(
+        contents: &str,
+        location: Location,
+        config: &TokenizerConfig,
+    ) -> Result<(Vec<Token>, Vec<Warning>), Error> {
+        let mut tokenizer = Self::new(contents, location, config);
+        let mut tokens = Vec::new();
+        tokenizer.run(&mut tokens)?;
+
+        #[cfg(feature = "security")]
+        tokenizer
+            .warnings
+            .extend(crate::security::check_identifiers(&tokens));
+
+        Ok((tokens, tokenizer.warnings))
+    }
+
+    /// Tokenizes a fragment embedded in a larger source, such as an example in a doc
+    /// comment or a template block, reporting positions in the larger source. `base` is
+    /// the location of the fragment's first character and `base_offset` its byte offset.
+    ///
+    /// The fragment is taken to be a contiguous slice of the source, so the first line's
+    /// columns continue from `base.column`, later lines start at column 0 of the rows after
+    /// `base.row`, and every span, including those of errors, is offset by `base_offset`.
+    /// Errors can then be rendered against the whole source.
+    pub fn tokenize_fragment(
+        contents: &str,
+        base: Location,
+        base_offset: usize,
+        config: &TokenizerConfig,
+    ) -> Result<Vec<Token>, Error> {
+        let mut tokenizer = Self::new(contents, base, config);
+        tokenizer.base_offset = base_offset;
+        let mut tokens = Vec::new();
+        tokenizer.run(&mut tokens)?;
+        Ok(tokens)
+    }
+
+    /// Tokenizes one line, given what the lines before it left open, returning its tokens
+    /// and what it leaves open for the next line, as syntax highlighters that work a line
+    /// at a time need. A trailing line break is ignored.
+    ///
+    /// The part of a string, quoted identifier or block comment on the line is its own
+    /// token, which for a construct continued from an earlier line starts at column 0. Tokens are located at
+    /// `row`, and their spans are byte offsets in the line.
+    pub fn tokenize_line(
+        line: &str,
+        row: usize,
+        state: LineState,
+        config: &TokenizerConfig,
+    ) -> Result<(Vec<Token>, LineState), Error> {
+        let line = line.strip_suffix('\n').unwrap_or(line);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let mut tokenizer = Self::new(line, (row, 0).into(), config);
+        tokenizer.line_state = Some(LineState::Normal);
+
+        let mut tokens = Vec::new();
+        let location = tokenizer.location.clone();
+        match state {
+            LineState::Normal => {}
+            LineState::InString { quote } => {
+                tokens.push(tokenizer.read_string_contents(quote, location, 0)?);
+            }
+            LineState::InQuotedIdentifier { close } => {
+                tokens.push(tokenizer.read_quoted_identifier_contents(close, location, 0)?);
+            }
+            LineState::InBlockComment { depth } => {
+                tokens.push(tokenizer.read_block_comment_contents(location, 0, 0, depth)?);
+            }
+        }
+        tokenizer.run(&mut tokens)?;
+
+        Ok((tokens, tokenizer.line_state.unwrap_or_default()))
+    }
+
+    /// Tokenizes the contents, returning the tokens produced before the error if one occurs.
+    #[allow(clippy::result_large_err)]
+    pub fn tokenize_partial(
+        contents: &str,
+        location: Location,
+        config: &TokenizerConfig,
+    ) -> Result<Vec<Token>, ErrorWithPartial> {
+        let mut tokenizer = Self::new(contents, location, config);
+        let mut tokens = Vec::new();
+        match tokenizer.run(&mut tokens) {
+            Ok(()) => Ok(tokens),
+            Err(error) => Err(ErrorWithPartial { error, tokens }),
+        }
+    }
+
+    /// Tokenizes the contents, recovering from errors where it can so tools like editors
+    /// get tokens for the whole input, and returning the tokens with every error found.
+    ///
+    /// A string that isn't closed by the end of its line, because it never closes or
+    /// `StringNewlines::Error` is set, becomes an `Unknown` token covering the rest of the
+    /// line. Its error points at the end of the line, with a label at the opening quote,
+    /// and tokenizing carries on with the next line. The error for a block comment left open
+    /// under `UnclosedBlockComments::Recover` is collected too. Any other error ends
+    /// tokenizing and is returned last, after the tokens read before it.
+    pub fn tokenize_recovering(
+        contents: &str,
+        location: Location,
+        config: &TokenizerConfig,
+    ) -> (Vec<Token>, Vec<Error>) {
+        let mut tokenizer = Self::new(contents, location, config);
+        tokenizer.recovering = true;
+        let mut tokens = Vec::new();
+        let result = tokenizer.run(&mut tokens);
+
+        let mut errors = std::mem::take(&mut tokenizer.errors);
+        errors.extend(result.err());
+        (tokens, errors)
+    }
+
+    /// Creates a tokenizer with no contents, to be fed inputs with `reset` and reused
+    /// between them.
+    pub fn with_config(config: TokenizerConfig) -> Self {
+        Self::with_spec(config.compile())
+    }
+
+    /// Creates a tokenizer with no contents for a compiled spec, as with `with_config`.
+    pub fn with_spec(spec: CompiledSpec) -> Self {
+        Tokenizer {
+            contents: String::new(),
+            location: Location::default(),
+            fuel: spec.fuel,
+            spec,
+            warnings: Vec::new(),
+            tokens: Vec::new(),
+            offset: 0,
+            base_offset: 0,
+            line_state: None,
+            recovering: false,
+            errors: Vec::new(),
+            pending: VecDeque::new(),
+            started: false,
+            finished: false,
+            on_token: None,
+            on_error: None,
+            source_offsets: Vec::new(),
+        }
+    }
+
+    /// Replaces the contents to tokenize, keeping the buffers allocated for previous contents.
+    pub fn reset(&mut self, contents: &str, location: Location) {
+        self.contents.clear();
+        self.source_offsets.clear();
+        let normalize = self.spec.normalization == Normalization::Input;
+        let mut rest = contents;
+        while let Some(mut i) = rest.find(|c: char| c == '\r' || (normalize && !c.is_ascii())) {
+            // Combining marks are normalized with the character before them.
+            if i > 0 && rest.as_bytes()[i] != b'\r' {
+                i -= 1;
+            }
+            self.contents.push_str(&rest[..i]);
+            rest = &rest[i..];
+            let len = if rest.starts_with("\r\n") {
+                2
+            } else if rest.starts_with('\r') {
+                1
+            } else {
+                normalization_run_len(rest)
+            };
+            let start = (self.contents.len(), contents.len() - rest.len());
+            if rest.starts_with("\r\n") {
+                self.contents.push('\n');
+            } else if normalize {
+                self.contents.extend(rest[..len].nfc());
+            } else {
+                self.contents.push_str(&rest[..len]);
+            }
+            rest = &rest[len..];
+            if self.contents.len() - start.0 != len {
+                self.source_offsets.push(start);
+                self.source_offsets
+                    .push((self.contents.len(), contents.len() - rest.len()));
+            }
+        }
+        self.contents.push_str(rest);
+
+        self.offset = 0;
+        self.base_offset = 0;
+        self.line_state = None;
+        self.recovering = false;
+        self.errors.clear();
+        self.pending.clear();
+        self.started = false;
+        self.finished = false;
+        self.location = location;
+        self.fuel = self.spec.fuel;
+        self.warnings.clear();
+        self.tokens.clear();
+    }
+
+    /// Tokenizes the current contents. The returned tokens are only valid until the next
+    /// `reset`, which reuses their storage.
+    pub fn tokens(&mut self) -> Result<&[Token], Error> {
+        let mut tokens = std::mem::take(&mut self.tokens);
+        let result = self.run(&mut tokens);
+
+        #[cfg(feature = "security")]
+        self.warnings
+            .extend(crate::security::check_identifiers(&tokens));
+
+        self.tokens = tokens;
+        result.map(|_| self.tokens.as_slice())
+    }
+
+    /// Reads the next token of the current contents, returning `None` at the end and after
+    /// an error. Reading a token at a time suits callers that stop early, but the
+    /// identifier checks of the `security` feature only run when tokenizing as a whole.
+    pub fn next_token(&mut self) -> Option<Result<Token, Error>> {
+        loop {
+            if let Some(token) = self.pending.pop_front() {
+                return Some(Ok(token));
+            }
+            if self.finished {
+                return None;
+            }
+
+            let mut tokens = Vec::new();
+            let result = if self.started {
+                self.step(&mut tokens)
+            } else {
+                self.started = true;
+                self.start().map(|_| true)
+            };
+            match result {
+                Ok(true) => self.pending.extend(tokens),
+                Ok(false) => self.finished = true,
+                Err(error) => {
+                    self.finished = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+    }
+
+    /// Calls the hook with each token as it's read and the location of the next character,
+    /// for collecting metrics or showing progress through a large input. Returning
+    /// `ControlFlow::Break` stops tokenizing with `ErrorKind::Stopped`, so the hook can also
+    /// abort on a signal from outside, such as a cancelled request. Hooks are kept by
+    /// `reset`.
+    pub fn on_token(
+        &mut self,
+        hook: impl FnMut(&Token, &Location) -> ControlFlow<()> + Send + 'static,
+    ) {
+        self.on_token = Some(Box::new(hook));
+    }
+
+    /// Calls the hook with each error as it's produced, including those recovered from, and
+    /// the location of the next character.
+    pub fn on_error(&mut self, hook: impl FnMut(&Error, &Location) + Send + 'static) {
+        self.on_error = Some(Box::new(hook));
+    }
+
+    /// Returns the location of the next character to read.
+    pub fn location(&self) -> &Location {
+        &self.location
+    }
+
+    /// Returns the warnings from the last call to `tokens`.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    fn new(contents: &str, location: Location, config: &TokenizerConfig) -> Self {
+        Self::from_spec(contents, location, config.compile())
+    }
+
+    fn from_spec(contents: &str, location: Location, spec: CompiledSpec) -> Self {
+        let mut tokenizer = Self::with_spec(spec);
+        tokenizer.reset(contents, location);
+        tokenizer
+    }
+
+    /// Reads tokens until the end of the contents, pushing them onto `tokens`.
+    fn run(&mut self, tokens: &mut Vec<Token>) -> Result<(), Error> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "tokenize",
+            path = ?self.location.path,
+            bytes = self.contents.len(),
+        )
+        .entered();
+        #[cfg(feature = "tracing")]
+        let started = std::time::Instant::now();
+
+        let result = self.start().and_then(|_| {
+            while self.step(tokens)? {}
+            Ok(())
+        });
+
+        #[cfg(feature = "tracing")]
+        match &result {
+            Ok(()) => tracing::debug!(
+                tokens = tokens.len(),
+                elapsed = ?started.elapsed(),
+                "tokenized",
+            ),
+            Err(error) => tracing::debug!(
+                tokens = tokens.len(),
+                elapsed = ?started.elapsed(),
+                code = error.kind.code(),
+                error = %error.message,
+                "tokenizing failed",
+            ),
+        }
+        result
+    }
+
+    /// Checks the contents as a whole before reading tokens.
+    fn start(&mut self) -> Result<(), Error> {
+        self.check_bidi_controls()
+            .inspect_err(|error| self.report_error(error))
+    }
+
+    /// Reads whatever starts at the next character, pushing any resulting tokens onto
+    /// `tokens`, and returns whether there was anything to read.
+    fn step(&mut self, tokens: &mut Vec<Token>) -> Result<bool, Error> {
+        let len = tokens.len();
+        let recovered = self.errors.len();
+        let mut result = match self.peek_char() {
+            Some(_) if self.read_scanned(tokens) => Ok(true),
+            Some(c) => self.read_next(c, tokens).map(|_| true),
+            None => Ok(false),
+        };
+
+        if self.is_out_of_fuel() {
+            // The token being read when the fuel ran out may have been cut short.
+            tokens.truncate(len);
+            result = Err(Error {
+                kind: ErrorKind::OutOfFuel,
+                message: "Ran out of fuel".to_string(),
+                location: self.location.clone(),
+                span: self.span_from(self.offset),
+                source_line: Some(self.line_at(self.offset)),
+                mismatch: None,
+                label: None,
+                args: Box::default(),
+            });
+        }
+
+        if let Some(hook) = self.on_error.as_mut() {
+            for error in self.errors[recovered..].iter().chain(result.as_ref().err()) {
+                hook(error, &self.location);
+            }
+        }
+        let Some(hook) = self.on_token.as_mut() else {
+            return result;
         };
+        for token in &tokens[len..] {
+            if hook(token, &self.location).is_break() && result.is_ok() {
+                return Err(Error {
+                    kind: ErrorKind::Stopped,
+                    message: "Stopped by a hook".to_string(),
+                    location: self.location.clone(),
+                    span: self.span_from(self.offset),
+                    source_line: Some(self.line_at(self.offset)),
+                    mismatch: None,
+                    label: None,
+                    args: Box::default(),
+                });
+            }
+        }
+        result
+    }
 
-        while let Some(c) = tokenizer.peek_char() {
-            if c == '#' {
-                let token = tokenizer.read_comment()?;
-                tokens.push(token);
-            } else if c == '"' {
-                let token = tokenizer.read_string_literal()?;
-                tokens.push(token);
-            } else if c.is_whitespace() {
-                tokenizer.next_char();
+    /// Passes the error to the error hook, if there is one.
+    fn report_error(&mut self, error: &Error) {
+        if let Some(hook) = self.on_error.as_mut() {
+            hook(error, &self.location);
+        }
+    }
+
+    /// Reads whatever starts with `c`, pushing any resulting token onto `tokens`.
+    fn read_next(&mut self, c: char, tokens: &mut Vec<Token>) -> Result<(), Error> {
+        let class = self.classify(c);
+        if let Some(len) = self.directive_name_len(c) {
+            let token = self.read_directive(len);
+            self.apply_line_directive(&token)?;
+            tokens.push(token);
+        } else if let Some(&(_, close)) = self
+            .spec
+            .identifier_quotes
+            .iter()
+            .find(|(open, _)| *open == c)
+        {
+            let token = self.read_quoted_identifier(close)?;
+            tokens.push(token);
+        } else if class.contains(CharClass::COMMENT_START) {
+            let token = if let Some(len) = self.color_len() {
+                self.read_color(len)
+            } else if let Some(len) = self.bracket_attribute_len() {
+                self.read_attribute(len, 2, 1)
+            } else if self.spec.block_comments != BlockComments::None
+                && self.contents[self.offset..].starts_with("#|")
+            {
+                self.read_block_comment()?
             } else {
-                // If it's not a number, check to see if it starts with a '-' or '.'
-                // and if the next character is a number.
-                let mut is_numeric = c.is_numeric();
-                if !is_numeric && tokenizer.index + 1 < tokenizer.contents.len() {
-                    let next_char = tokenizer.contents.chars().nth(tokenizer.index).unwrap();
-                    if next_char == '-' || next_char == '.' {
-                        is_numeric = tokenizer
-                            .contents
-                            .chars()
-                            .nth(tokenizer.index + 1)
-                            .unwrap()
-                            .is_numeric();
+                self.read_comment()?
+            };
+            tokens.push(token);
+        } else if class.contains(CharClass::QUOTE) {
+            let token = self.read_string_literal('"')?;
+            tokens.push(token);
+        } else if is_smart_quote(c) && self.spec.smart_quotes != SmartQuotes::Allow {
+            if self.spec.smart_quotes == SmartQuotes::Error {
+                return Err(Error {
+                    kind: ErrorKind::SmartQuote,
+                    message: format!("Unexpected smart quote '{}', use '\"' instead", c),
+                    location: self.location.clone(),
+                    span: self.next_char_span(),
+                    source_line: Some(self.line_at(self.offset)),
+                    mismatch: None,
+                    label: None,
+                    args: Box::new([("char", c.to_string())]),
+                });
+            }
+            let token = self.read_string_literal('\u{201D}')?;
+            tokens.push(token);
+        } else if class.contains(CharClass::WHITESPACE) {
+            if !c.is_ascii_whitespace() && !self.spec.additional_whitespace.contains(&c) {
+                let message = format!("Unusual whitespace character U+{:04X}", c as u32);
+                let location = self.location.clone();
+                match self.spec.unusual_whitespace {
+                    UnusualWhitespace::Error => {
+                        return Err(Error {
+                            kind: ErrorKind::UnusualWhitespace,
+                            message,
+                            location,
+                            span: self.next_char_span(),
+                            source_line: Some(self.line_at(self.offset)),
+                            mismatch: None,
+                            label: None,
+                            args: Box::new([("code", format!("U+{:04X}", c as u32))]),
+                        })
                     }
+                    UnusualWhitespace::Warn => self.warnings.push(Warning { message, location }),
+                    UnusualWhitespace::Allow => {}
+                }
+            }
+            self.next_char();
+        } else if class.contains(CharClass::INVISIBLE) {
+            let start = self.offset;
+            let (c, location) = self.next_char().unwrap();
+            match self.spec.invisible_characters {
+                InvisibleCharacters::Error => {
+                    return Err(Error {
+                        kind: ErrorKind::InvisibleCharacter,
+                        message: format!("Invisible character U+{:04X}", c as u32),
+                        location,
+                        span: self.span_from(start),
+                        source_line: Some(self.line_at(start)),
+                        mismatch: None,
+                        label: None,
+                        args: Box::new([("code", format!("U+{:04X}", c as u32))]),
+                    })
                 }
+                InvisibleCharacters::Token => tokens.push(Token {
+                    location,
+                    span: self.span_from(start),
+                    contents: c.into(),
+                    kind: TokenKind::Unknown(c.into()),
+                    provenance: Provenance::Original,
+                }),
+                InvisibleCharacters::Allow | InvisibleCharacters::Skip => {}
+            }
+        } else {
+            // If it's not a number, check to see if it starts with a sign or '.'
+            // and if the next character is a number.
+            let mut is_numeric = self.digit_value(c).is_some();
+            let is_sign =
+                (c == '-' || (c == '+' && self.spec.plus_signs)) && self.is_sign(tokens.last());
+            let is_leading_dot = c == '.' && self.is_leading_dot(tokens.last());
+            if !is_numeric && (is_sign || is_leading_dot) {
+                is_numeric = self
+                    .char_at(self.offset + 1)
+                    .and_then(|c| self.digit_value(c))
+                    .is_some();
+            }
 
-                let token = if is_numeric {
-                    tokenizer.read_number()?
-                } else if is_symbol(c) {
-                    tokenizer.read_symbol()?
-                } else {
-                    tokenizer.read_identifier()?
-                };
+            let token = if let Some(len) = self.datetime_at(self.offset) {
+                self.read_datetime(len)
+            } else if is_numeric {
+                let number = self.read_number(!self.is_member_index(tokens.last()))?;
+                self.read_unit(number)
+            } else if let Some(value) = self.special_float_at(self.offset + usize::from(is_sign)) {
+                self.read_special_float(value, is_sign)
+            } else if let Some(len) = self.at_attribute_len() {
+                self.read_attribute(len, 1, 0)
+            } else if let Some(len) = self.variable_len(c) {
+                self.read_variable(len, c)
+            } else if class.contains(CharClass::SYMBOL) {
+                self.read_symbol()?
+            } else if class.contains(CharClass::IDENTIFIER_START) {
+                self.read_identifier()?
+            } else {
+                self.read_unexpected()?
+            };
+
+            self.check_deprecated(&token);
+            tokens.push(token);
+        }
+
+        Ok(())
+    }
+
+    /// Reads the token at the offset with the compiled rules, pushing it onto `tokens`
+    /// unless it's whitespace. Returns false, having read nothing, if there are no compiled
+    /// rules or the decision tree has to read the token.
+    fn read_scanned(&mut self, tokens: &mut Vec<Token>) -> bool {
+        let Some(dfa) = self.spec.dfa() else {
+            return false;
+        };
+        let start = self.offset;
+        let Some((rule, len)) = dfa.scan(&self.contents.as_bytes()[start..]) else {
+            return false;
+        };
+        let end = start + len;
+        let lexeme = &self.contents[start..end];
+
+        let kind = match rule {
+            Rule::Whitespace => None,
+            Rule::Comment => Some(TokenKind::Comment(Box::new(Comment::new(
+                CommentStyle::Line,
+                &lexeme[1..],
+            )))),
+            Rule::String => {
+                let mut value = String::with_capacity(len - 2);
+                let mut chars = lexeme[1..len - 1].chars();
+                // The automaton only accepts strings whose backslashes escape a character.
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => push_unescaped(&mut value, chars.next().unwrap(), '"'),
+                        c => value.push(c),
+                    }
+                }
+                Some(TokenKind::StringLiteral(value.into()))
+            }
+            Rule::Identifier => Some(TokenKind::Identifier(
+                self.identifier_payload(lexeme).into(),
+            )),
+            Rule::Integer => match lexeme.parse() {
+                Ok(i) => Some(TokenKind::IntegerLiteral(i)),
+                // Overflow is an error or a big integer, which the decision tree handles.
+                Err(_) => return false,
+            },
+            Rule::Float if self.spec.fractions == Fractions::Decimal => {
+                Some(TokenKind::DecimalLiteral(lexeme.into()))
+            }
+            Rule::Float => match lexeme.parse() {
+                Ok(f) => Some(TokenKind::FloatLiteral(f)),
+                Err(_) => return false,
+            },
+            Rule::Symbol => Some(TokenKind::Symbol(lexeme.into())),
+        };
 
-                tokens.push(token);
+        // Fuel counts characters, and a token it doesn't cover is cut short by the decision
+        // tree instead.
+        if let Some(fuel) = self.fuel.as_mut() {
+            let chars = lexeme.chars().count();
+            if chars > *fuel {
+                return false;
+            }
+            *fuel -= chars;
+        }
+        let location = self.location.clone();
+        for byte in lexeme.bytes() {
+            if byte == b'\n' {
+                self.location.row += 1;
+                self.location.column = 0;
+            } else if byte & 0xC0 != 0x80 {
+                // Only the first byte of a character moves the column.
+                self.location.column += 1;
             }
         }
+        self.offset = end;
 
-        Ok(tokens)
+        if let Some(kind) = kind {
+            let token = Token {
+                location,
+                span: self.span_from(start),
+                contents: match &kind {
+                    TokenKind::Comment(comment) => comment.text.clone(),
+                    _ => self.contents[start..end].into(),
+                },
+                kind,
+                provenance: Provenance::Original,
+            };
+            if !matches!(rule, Rule::Comment | Rule::String) {
+                self.check_deprecated(&token);
+            }
+            tokens.push(token);
+        }
+        true
     }
 
-    fn read_number(&mut self) -> Result<Token, Error> {
+    /// Reads a number, stopping at a `.` unless `allow_fraction` is set.
+    fn read_number(&mut self, allow_fraction: bool) -> Result<Token, Error> {
         let location = self.location.clone();
+        let start = self.offset;
         let mut buffer = String::new();
+        // The literal with any non-ASCII digits mapped to ASCII, used for parsing.
+        let mut normalized = String::new();
 
         // Chomp the first character
         let mut has_period = false;
 
         let c = self.next_char().unwrap().0;
         buffer.push(c);
+        normalized.push(self.normalize_digit(c));
 
         if c == '.' {
             has_period = true;
         }
 
         while let Some(c) = self.peek_char() {
-            // float case
-            if c == '.' && !has_period {
+            // float case, leaving a `..` range for the symbol reader
+            if c == '.'
+                && (!allow_fraction
+                    || self.char_at(self.offset + 1) == Some('.')
+                    || (!self.spec.trailing_dots && !self.digit_follows()))
+            {
+                break;
+            } else if c == '.' && !has_period {
                 has_period = true;
             } else if c == '.' && has_period {
                 return Err(Error {
+                    kind: ErrorKind::MultipleDecimalPoints,
                     message: "Float literal cannot have multiple decimal points".to_string(),
                     location: self.location.clone(),
+                    span: Span::new(self.source_offset(start), self.next_char_span().end),
+                    source_line: Some(self.line_at(start)),
+                    mismatch: None,
+                    label: None,
+                    args: Box::default(),
                 });
-            } else if !c.is_numeric() {
+            } else if self.digit_value(c).is_none() {
                 break;
             }
             buffer.push(c);
+            normalized.push(self.normalize_digit(c));
             self.next_char();
         }
 
-        let kind = if has_period {
-            TokenKind::FloatLiteral(buffer.parse().unwrap())
+        let kind = if has_period && self.spec.fractions == Fractions::Decimal {
+            TokenKind::DecimalLiteral(normalized.into())
+        } else if has_period {
+            match normalized.parse() {
+                Ok(f) => TokenKind::FloatLiteral(f),
+                Err(_) => {
+                    return Err(Error {
+                        kind: ErrorKind::InvalidNumber,
+                        message: format!("Invalid float literal '{}'", buffer),
+                        location,
+                        span: self.span_from(start),
+                        source_line: Some(self.line_at(start)),
+                        mismatch: None,
+                        label: None,
+                        args: Box::new([("type", "float".to_string()), ("literal", buffer)]),
+                    })
+                }
+            }
         } else {
-            TokenKind::IntegerLiteral(buffer.parse().unwrap())
+            match normalized.parse() {
+                Ok(i) => TokenKind::IntegerLiteral(i),
+                // The literal is a sign and ASCII digits, so it only fails on overflow.
+                #[cfg(feature = "bigint")]
+                Err(_) => TokenKind::BigIntegerLiteral(normalized.parse().unwrap()),
+                #[cfg(not(feature = "bigint"))]
+                Err(_) => {
+                    return Err(Error {
+                        kind: ErrorKind::InvalidNumber,
+                        message: format!("Invalid integer literal '{}'", buffer),
+                        location,
+                        span: self.span_from(start),
+                        source_line: Some(self.line_at(start)),
+                        mismatch: None,
+                        label: None,
+                        args: Box::new([("type", "integer".to_string()), ("literal", buffer)]),
+                    })
+                }
+            }
         };
 
         Ok(Token {
             location,
-            contents: buffer,
+            span: self.span_from(start),
+            contents: buffer.into(),
             kind,
+            provenance: Provenance::Original,
         })
     }
 
-    fn read_symbol(&mut self) -> Result<Token, Error> {
-        const TWO_CHAR_SYMBOLS: [&str; 10] =
-            ["==", "!=", ">=", "<=", "->", "=>", "*=", "-=", "+=", "/="];
-
-        let location = self.location.clone();
-        let mut buffer = String::new();
-        let first_char = self.contents.chars().nth(self.index).unwrap();
-        buffer.push(first_char);
-
-        // Check if the next char is a valid symbol
-        if self.index + 1 < self.contents.len() {
-            let second_char = self.contents.chars().nth(self.index + 1).unwrap();
-            if TWO_CHAR_SYMBOLS.contains(&format!("{}{}", first_char, second_char).as_str()) {
-                buffer.push(second_char);
-            }
+    /// Returns the length of the datetime at the offset if `datetimes` is set and it isn't
+    /// followed by identifier characters.
+    fn datetime_at(&self, offset: usize) -> Option<usize> {
+        if !self.spec.datetimes {
+            return None;
+        }
+        let len = datetime_len(&self.contents[offset..])?;
+        match self.char_at(offset + len) {
+            Some(c) if self.is_identifier_continue(c) => None,
+            _ => Some(len),
         }
+    }
 
-        for _ in 0..buffer.len() {
+    /// Reads a datetime that is `len` bytes long.
+    fn read_datetime(&mut self, len: usize) -> Token {
+        let location = self.location.clone();
+        let start = self.offset;
+        // Datetimes are ASCII, so each character is one byte.
+        for _ in 0..len {
             self.next_char();
         }
 
-        Ok(Token {
+        let contents = &self.contents[start..start + len];
+        Token {
             location,
-            contents: buffer.clone(),
-            kind: TokenKind::Symbol(buffer),
-        })
+            span: self.span_from(start),
+            contents: contents.into(),
+            kind: TokenKind::DateTimeLiteral(contents.into()),
+            provenance: Provenance::Original,
+        }
     }
 
-    fn read_identifier(&mut self) -> Result<Token, Error> {
-        let location = self.location.clone();
+    /// Reads a `%` or configured unit directly after the number, returning a
+    /// `PercentLiteral` or `UnitLiteral` if there is one and the number otherwise.
+    fn read_unit(&mut self, number: Token) -> Token {
+        let rest = &self.contents[self.offset..];
+        // The number is kept as written so that none of it is lost, whatever its size.
+        let value = || -> String {
+            number
+                .contents
+                .chars()
+                .map(|c| self.normalize_digit(c))
+                .collect()
+        };
+        let (unit, kind) = if self.spec.percents && rest.starts_with('%') {
+            ("%".to_string(), TokenKind::PercentLiteral(value().into()))
+        } else {
+            let unit = self
+                .spec
+                .units
+                .iter()
+                .filter(|unit| !unit.is_empty() && rest.starts_with(unit.as_str()))
+                .filter(|unit| match rest[unit.len()..].chars().next() {
+                    Some(c) => !self.is_identifier_continue(c),
+                    None => true,
+                })
+                .max_by_key(|unit| unit.len());
+            let Some(unit) = unit.cloned() else {
+                return number;
+            };
+            let kind = TokenKind::UnitLiteral {
+                value: value().into(),
+                unit: unit.as_str().into(),
+            };
+            (unit, kind)
+        };
 
-        let (token, _) = self.read_token().unwrap();
-        Ok(Token {
-            location,
-            contents: token.clone(),
-            kind: TokenKind::Identifier(token),
-        })
+        for _ in unit.chars() {
+            self.next_char();
+        }
+        Token {
+            location: number.location,
+            span: Span::new(number.span.start, self.source_offset(self.offset)),
+            contents: format!("{}{}", number.contents, unit).into(),
+            kind,
+            provenance: Provenance::Original,
+        }
     }
 
-    fn read_comment(&mut self) -> Result<Token, Error> {
+    /// Reads `inf` or `nan` as a float with the value, after a sign if `signed` is set.
+    fn read_special_float(&mut self, value: f64, signed: bool) -> Token {
         let location = self.location.clone();
-        // Chomp the '#'
-        self.next_char();
+        let start = self.offset;
+        // The sign and word are ASCII, so each character is one byte.
+        let end = start + usize::from(signed) + 3;
+        while self.offset < end {
+            self.next_char();
+        }
 
-        let (comment, _) = match self.read_until_endline() {
-            Some((comment, location)) => (comment, location),
-            None => (String::new(), location.clone()),
+        let contents = &self.contents[start..end];
+        let value = if contents.starts_with('-') {
+            -value
+        } else {
+            value
         };
-        Ok(Token {
+        Token {
             location,
-            contents: comment.trim().to_string(),
-            kind: TokenKind::Comment(comment.trim().to_string()),
-        })
+            span: self.span_from(start),
+            contents: contents.into(),
+            kind: TokenKind::FloatLiteral(value),
+            provenance: Provenance::Original,
+        }
     }
 
-    fn read_string_literal(&mut self) -> Result<Token, Error> {
+    fn read_symbol(&mut self) -> Result<Token, Error> {
+        const TWO_CHAR_SYMBOLS: [&str; 11] = [
+            "==", "!=", ">=", "<=", "->", "=>", "*=", "-=", "+=", "/=", "..",
+        ];
+        const THREE_CHAR_SYMBOLS: [&str; 1] = ["..="];
+
         let location = self.location.clone();
+        let start = self.offset;
         let mut buffer = String::new();
+        let first_char = self.char_at(self.offset).unwrap();
+        buffer.push(first_char);
 
-        // Chomp the '"'
-        self.next_char();
-        let mut closed = false;
-        let mut prev_char = None;
-        while let Some(c) = self.peek_char() {
-            if c == '"' {
-                if prev_char != Some('\\') {
-                    closed = true;
-                    self.next_char();
-                    break;
-                } else {
-                    buffer.pop();
+        // Check if the next char is a valid symbol. Symbols are ASCII, so it starts one
+        // byte after the first.
+        if let Some(second_char) = self.char_at(self.offset + 1) {
+            if TWO_CHAR_SYMBOLS.contains(&format!("{}{}", first_char, second_char).as_str()) {
+                buffer.push(second_char);
+            }
+        }
+        if buffer.len() == 2 {
+            if let Some(third_char) = self.char_at(self.offset + 2) {
+                if THREE_CHAR_SYMBOLS.contains(&format!("{}{}", buffer, third_char).as_str()) {
+                    buffer.push(third_char);
                 }
             }
-            buffer.push(c);
-            prev_char = Some(c);
-            self.next_char();
         }
 
-        if !closed {
-            return Err(Error {
-                message: "Unclosed string".to_string(),
-                location,
-            });
+        for _ in 0..buffer.len() {
+            self.next_char();
         }
 
         Ok(Token {
             location,
-            contents: buffer.clone(),
-            kind: TokenKind::StringLiteral(buffer),
+            span: self.span_from(start),
+            contents: buffer.clone().into(),
+            kind: TokenKind::Symbol(buffer.into()),
+            provenance: Provenance::Original,
         })
     }
 
-    /// Reads a token from the contents, stopping before the next token.
-    fn read_token(&mut self) -> Option<(String, Location)> {
-        if self.peek_char().is_none() {
-            return None;
-        }
-
-        let mut buffer = String::new();
+    fn read_identifier(&mut self) -> Result<Token, Error> {
         let location = self.location.clone();
-        while let Some(c) = self.peek_char() {
-            if c.is_whitespace() || is_symbol(c) {
-                break;
+        let start = self.offset;
+
+        let (mut token, _) = self.read_token().unwrap();
+        while let Some(len) = self.path_segment_len() {
+            let end = self.offset + len;
+            token.push_str(&self.contents[self.offset..end]);
+            while self.offset < end {
+                self.next_char();
             }
-            buffer.push(c);
-            self.next_char();
         }
+        let identifier = self.identifier_payload(&token);
+        Ok(Token {
+            location,
+            span: self.span_from(start),
+            contents: token.into(),
+            kind: TokenKind::Identifier(identifier.into()),
+            provenance: Provenance::Original,
+        })
+    }
 
-        if buffer.is_empty() {
-            return None;
+    /// Returns the identifier as it's stored in the token's kind, normalized and case folded
+    /// under the config.
+    fn identifier_payload(&self, identifier: &str) -> String {
+        let identifier: String = match self.spec.normalization {
+            Normalization::Identifiers => identifier.nfc().collect(),
+            Normalization::None | Normalization::Input => identifier.to_string(),
+        };
+        match self.spec.identifier_case {
+            IdentifierCase::AsWritten => identifier,
+            IdentifierCase::Lower => identifier.to_lowercase(),
+            IdentifierCase::Upper => identifier.to_uppercase(),
         }
-
-        Some((buffer, location))
     }
 
-    fn read_until_endline(&mut self) -> Option<(String, Location)> {
-        let mut buffer = String::new();
-        let location = self.location.clone();
-
-        while let Some(c) = self.peek_char() {
-            if c == '\n' {
-                break;
-            }
-            buffer.push(c);
-            self.next_char();
+    /// Returns the length in bytes of the directive's name if `c` is a directive character
+    /// that starts its line and is directly followed by a name.
+    fn directive_name_len(&self, c: char) -> Option<usize> {
+        if !self.spec.directives.contains(&c) {
+            return None;
         }
-
-        if buffer.is_empty() {
+        let line_start = self.contents[..self.offset]
+            .rfind('\n')
+            .map_or(0, |i| i + 1);
+        if !self.contents[line_start..self.offset].trim().is_empty() {
             return None;
         }
-
-        Some((buffer, location))
-    }
-
-    fn peek_char(&self) -> Option<char> {
-        self.contents.chars().nth(self.index)
+        let len = self.identifier_len(self.offset + c.len_utf8());
+        (len > 0).then_some(len)
     }
 
-    /// Returns the next character and updates the location
-    fn next_char(&mut self) -> Option<(char, Location)> {
-        let c = self.contents.chars().nth(self.index)?;
+    /// Reads a directive whose name is `name_len` bytes long, with the rest of the line as
+    /// its arguments.
+    fn read_directive(&mut self, name_len: usize) -> Token {
         let location = self.location.clone();
+        let start = self.offset;
 
-        self.index += 1;
-        self.location.column += 1;
-        if c == '\n' {
-            self.location.row += 1;
-            self.location.column = 0;
+        // Chomp the directive character
+        let (c, _) = self.next_char().unwrap();
+        let name_start = start + c.len_utf8();
+        while self.offset < name_start + name_len {
+            self.next_char();
+        }
+        let arguments_start = self.offset;
+        while self.peek_char().is_some_and(|c| c != '\n') {
+            self.next_char();
         }
 
-        Some((c, location))
+        let contents = self.contents[start..self.offset].trim_end();
+        Token {
+            location,
+            span: Span::new(
+                self.source_offset(start),
+                self.source_offset(start + contents.len()),
+            ),
+            contents: contents.into(),
+            kind: TokenKind::Directive(Box::new(Directive {
+                name: self.contents[name_start..arguments_start].into(),
+                arguments: self.contents[arguments_start..self.offset].trim().into(),
+            })),
+            provenance: Provenance::Original,
+        }
+    }
+
+    /// If the token is a line directive, moves past the end of its line and sets the
+    /// location of the next line from its arguments.
+    fn apply_line_directive(&mut self, token: &Token) -> Result<(), Error> {
+        let directive = match &token.kind {
+            TokenKind::Directive(directive)
+                if self.spec.line_directive.as_deref() == Some(directive.name.as_str()) =>
+            {
+                directive
+            }
+            _ => return Ok(()),
+        };
+
+        let (line, path) = match directive.arguments.split_once(char::is_whitespace) {
+            Some((line, path)) => (line, Some(path.trim())),
+            None => (directive.arguments.as_str(), None),
+        };
+        let row = line
+            .parse::<usize>()
+            .ok()
+            .and_then(|line| line.checked_sub(1));
+        let path = match path {
+            Some(path) => path
+                .strip_prefix('"')
+                .and_then(|path| path.strip_suffix('"'))
+                .map(|path| Some(Arc::from(Path::new(path)))),
+            None => Some(self.location.path.clone()),
+        };
+        let (Some(row), Some(path)) = (row, path) else {
+            return Err(Error {
+                kind: ErrorKind::InvalidDirective,
+                message: format!("Invalid {} directive", directive.name),
+                location: token.location.clone(),
+                span: token.span,
+                source_line: Some(self.line_at(self.offset)),
+                mismatch: None,
+                label: None,
+                args: Box::new([("directive", directive.name.to_string())]),
+            });
+        };
+
+        self.next_char();
+        self.location = Location {
+            row,
+            column: 0,
+            path,
+        };
+        Ok(())
+    }
+
+    /// Reads an identifier from after its opening delimiter up to the closing one, where a
+    /// doubled closing delimiter is part of the name.
+    fn read_quoted_identifier(&mut self, close: char) -> Result<Token, Error> {
+        let location = self.location.clone();
+        let start = self.offset;
+        // Chomp the opening delimiter
+        self.next_char();
+        self.read_quoted_identifier_contents(close, location, start)
+    }
+
+    /// Reads the rest of a quoted identifier that starts at the offset `start`.
+    fn read_quoted_identifier_contents(
+        &mut self,
+        close: char,
+        location: Location,
+        start: usize,
+    ) -> Result<Token, Error> {
+        let mut name = String::new();
+        loop {
+            match self.next_char() {
+                Some((c, _)) if c == close && self.peek_char() == Some(close) => {
+                    name.push(close);
+                    self.next_char();
+                }
+                Some((c, _)) if c == close => break,
+                Some((c, _)) => name.push(c),
+                None if self.line_state.is_some() => {
+                    self.line_state = Some(LineState::InQuotedIdentifier { close });
+                    break;
+                }
+                None => {
+                    return Err(Error {
+                        kind: ErrorKind::UnclosedIdentifier,
+                        message: "Unclosed identifier".to_string(),
+                        location,
+                        span: self.span_from(start),
+                        source_line: Some(self.line_at(start)),
+                        mismatch: None,
+                        label: None,
+                        args: Box::default(),
+                    })
+                }
+            }
+        }
+
+        Ok(Token {
+            location,
+            span: self.span_from(start),
+            contents: name.clone().into(),
+            kind: TokenKind::Identifier(name.into()),
+            provenance: Provenance::Original,
+        })
+    }
+
+    /// Returns the length in bytes of the path separator and identifier at the offset, if
+    /// there is one to join to the identifier before it.
+    fn path_segment_len(&self) -> Option<usize> {
+        let rest = &self.contents[self.offset..];
+        self.spec
+            .path_separators
+            .iter()
+            .filter(|separator| !separator.is_empty() && rest.starts_with(separator.as_str()))
+            .filter_map(|separator| {
+                let next = self.offset + separator.len();
+                let is_number = self
+                    .char_at(next)
+                    .and_then(|c| self.digit_value(c))
+                    .is_some();
+                let len = self.identifier_len(next);
+                (len > 0 && !is_number).then_some(separator.len() + len)
+            })
+            .max()
+    }
+
+    fn read_unexpected(&mut self) -> Result<Token, Error> {
+        let start = self.offset;
+        let (c, location) = self.next_char().unwrap();
+        match self.spec.unexpected_characters {
+            UnexpectedCharacters::Error => Err(Error {
+                kind: ErrorKind::UnexpectedCharacter,
+                message: format!("Unexpected character '{}'", c),
+                location,
+                span: self.span_from(start),
+                source_line: Some(self.line_at(start)),
+                mismatch: None,
+                label: None,
+                args: Box::new([("char", c.to_string())]),
+            }),
+            UnexpectedCharacters::Token => Ok(Token {
+                location,
+                span: self.span_from(start),
+                contents: c.into(),
+                kind: TokenKind::Unknown(c.into()),
+                provenance: Provenance::Original,
+            }),
+        }
+    }
+
+    fn read_comment(&mut self) -> Result<Token, Error> {
+        let location = self.location.clone();
+        let start = self.offset;
+        // Chomp the '#'
+        self.next_char();
+        let style = if self.spec.doc_comments && self.peek_char() == Some('#') {
+            self.next_char();
+            CommentStyle::Doc
+        } else {
+            CommentStyle::Line
+        };
+
+        let raw = match self.read_until_endline() {
+            Some((raw, _)) => raw,
+            None => String::new(),
+        };
+        let comment = Comment::new(style, &raw);
+        Ok(Token {
+            location,
+            span: self.span_from(start),
+            contents: comment.text.clone(),
+            kind: TokenKind::Comment(Box::new(comment)),
+            provenance: Provenance::Original,
+        })
+    }
+
+    /// Reads a block comment from its `#|` to the `|#` that closes it, handling one that's
+    /// still open at the end of the input as `unclosed_block_comments` says.
+    fn read_block_comment(&mut self) -> Result<Token, Error> {
+        let location = self.location.clone();
+        let start = self.offset;
+        // Chomp the '#|'
+        self.next_char();
+        self.next_char();
+        self.read_block_comment_contents(location, start, start + 2, 1)
+    }
+
+    /// Reads the rest of a block comment that starts at the offset `start`, with its text
+    /// starting at `text_start` and `depth` comments left to close.
+    fn read_block_comment_contents(
+        &mut self,
+        location: Location,
+        start: usize,
+        text_start: usize,
+        mut depth: usize,
+    ) -> Result<Token, Error> {
+        let nested = self.spec.block_comments == BlockComments::Nested;
+        let mut end = None;
+        while let Some((c, at)) = self.next_char() {
+            match (c, self.peek_char()) {
+                ('|', Some('#')) => {
+                    self.next_char();
+                    depth -= 1;
+                    if depth == 0 {
+                        end = Some(self.offset - 2);
+                        break;
+                    }
+                }
+                ('#', Some('|')) if nested => {
+                    if self.spec.max_depth == Some(depth) {
+                        let opener = self.offset - 1;
+                        let span =
+                            Span::new(self.source_offset(opener), self.source_offset(opener + 2));
+                        let mut error = Error::too_deep(depth, at, span);
+                        error.source_line = Some(self.line_at(opener));
+                        return Err(error);
+                    }
+                    self.next_char();
+                    depth += 1;
+                }
+                _ => {}
+            }
+        }
+
+        let comment = match end {
+            Some(end) => Comment::new(CommentStyle::Block, &self.contents[text_start..end]),
+            None if self.line_state.is_some() => {
+                self.line_state = Some(LineState::InBlockComment { depth });
+                Comment::new(CommentStyle::Block, &self.contents[text_start..])
+            }
+            None => {
+                let error = Error {
+                    kind: ErrorKind::UnclosedComment,
+                    message: "Unclosed block comment".to_string(),
+                    location: location.clone(),
+                    span: Span::new(self.source_offset(start), self.source_offset(start + 2)),
+                    source_line: Some(self.line_at(start)),
+                    mismatch: None,
+                    label: None,
+                    args: Box::default(),
+                };
+                match self.spec.unclosed_block_comments {
+                    UnclosedBlockComments::Error => return Err(error),
+                    UnclosedBlockComments::Warn => self.warnings.push(Warning {
+                        message: error.message,
+                        location: location.clone(),
+                    }),
+                    UnclosedBlockComments::Recover => {
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!(
+                            code = error.kind.code(),
+                            row = location.row,
+                            column = location.column,
+                            "read the rest of the input as an unknown token",
+                        );
+                        if self.recovering {
+                            self.errors.push(error);
+                        }
+                        let text = &self.contents[start..self.offset];
+                        return Ok(Token {
+                            location,
+                            span: self.span_from(start),
+                            contents: text.into(),
+                            kind: TokenKind::Unknown(text.into()),
+                            provenance: Provenance::Original,
+                        });
+                    }
+                }
+                Comment::new(CommentStyle::Block, &self.contents[text_start..self.offset])
+            }
+        };
+
+        Ok(Token {
+            location,
+            span: self.span_from(start),
+            contents: comment.text.clone(),
+            kind: TokenKind::Comment(Box::new(comment)),
+            provenance: Provenance::Original,
+        })
+    }
+
+    /// Returns the number of hex digits after the `#` at the offset if `colors` is set and
+    /// they form a color, with no identifier characters following.
+    fn color_len(&self) -> Option<usize> {
+        if !self.spec.colors {
+            return None;
+        }
+        let len = self.contents.as_bytes()[self.offset + 1..]
+            .iter()
+            .take_while(|b| b.is_ascii_hexdigit())
+            .count();
+        match self.char_at(self.offset + 1 + len) {
+            Some(c) if self.is_identifier_continue(c) => None,
+            _ => matches!(len, 3 | 4 | 6 | 8).then_some(len),
+        }
+    }
+
+    /// Reads a `#` followed by `len` hex digits as a color.
+    fn read_color(&mut self, len: usize) -> Token {
+        let location = self.location.clone();
+        let start = self.offset;
+        // The `#` and digits are ASCII, so each character is one byte.
+        for _ in 0..=len {
+            self.next_char();
+        }
+
+        Token {
+            location,
+            span: self.span_from(start),
+            contents: self.contents[start..self.offset].into(),
+            kind: TokenKind::ColorLiteral(self.contents[start + 1..self.offset].into()),
+            provenance: Provenance::Original,
+        }
+    }
+
+    /// Returns the length in bytes of the `@name` at the offset if `@` attributes are
+    /// enabled.
+    fn at_attribute_len(&self) -> Option<usize> {
+        if !matches!(self.spec.attributes, Attributes::At | Attributes::All)
+            || self.char_at(self.offset) != Some('@')
+        {
+            return None;
+        }
+        let len = self.identifier_len(self.offset + 1);
+        (len > 0).then_some(1 + len)
+    }
+
+    /// Returns the length in bytes of the sigil and name at the offset if `c` is a
+    /// configured sigil directly followed by an identifier.
+    fn variable_len(&self, c: char) -> Option<usize> {
+        if !self.spec.sigils.contains(&c) {
+            return None;
+        }
+        let len = self.identifier_len(self.offset + c.len_utf8());
+        (len > 0).then_some(c.len_utf8() + len)
+    }
+
+    /// Reads a variable `len` bytes long that starts with the sigil.
+    fn read_variable(&mut self, len: usize, sigil: char) -> Token {
+        let location = self.location.clone();
+        let start = self.offset;
+        let end = start + len;
+        while self.offset < end {
+            self.next_char();
+        }
+
+        Token {
+            location,
+            span: self.span_from(start),
+            contents: self.contents[start..end].into(),
+            kind: TokenKind::Variable {
+                sigil,
+                name: self
+                    .identifier_payload(&self.contents[start + sigil.len_utf8()..end])
+                    .into(),
+            },
+            provenance: Provenance::Original,
+        }
+    }
+
+    /// Returns the length in bytes of the identifier at the offset, or zero if there isn't
+    /// one.
+    fn identifier_len(&self, offset: usize) -> usize {
+        let starts_identifier = self
+            .char_at(offset)
+            .is_some_and(|c| self.classify(c).contains(CharClass::IDENTIFIER_START));
+        if !starts_identifier {
+            return 0;
+        }
+        self.contents[offset..]
+            .chars()
+            .take_while(|c| self.is_identifier_continue(*c))
+            .map(char::len_utf8)
+            .sum()
+    }
+
+    /// Returns the length in bytes of the `#[...]` at the offset if `#[` attributes are
+    /// enabled and the brackets are closed on the same line.
+    fn bracket_attribute_len(&self) -> Option<usize> {
+        if !matches!(
+            self.spec.attributes,
+            Attributes::HashBracket | Attributes::All
+        ) {
+            return None;
+        }
+        let rest = self.contents[self.offset + 1..].strip_prefix('[')?;
+        let mut depth = 1;
+        for (i, c) in rest.char_indices() {
+            match c {
+                '[' => depth += 1,
+                ']' if depth == 1 => return Some(i + 3),
+                ']' => depth -= 1,
+                '\n' => return None,
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Reads an attribute `len` bytes long, whose name or contents are what's left after
+    /// skipping `prefix` bytes at the start and `suffix` bytes at the end.
+    fn read_attribute(&mut self, len: usize, prefix: usize, suffix: usize) -> Token {
+        let location = self.location.clone();
+        let start = self.offset;
+        let end = start + len;
+        while self.offset < end {
+            self.next_char();
+        }
+
+        Token {
+            location,
+            span: self.span_from(start),
+            contents: self.contents[start..end].into(),
+            kind: TokenKind::Attribute(self.contents[start + prefix..end - suffix].trim().into()),
+            provenance: Provenance::Original,
+        }
+    }
+
+    /// Reads a string literal that ends with the given quote.
+    fn read_string_literal(&mut self, quote: char) -> Result<Token, Error> {
+        let location = self.location.clone();
+        let start = self.offset;
+        // Chomp the opening quote
+        self.next_char();
+        self.read_string_contents(quote, location, start)
+    }
+
+    /// Reads the rest of a string literal that starts at the offset `start`.
+    fn read_string_contents(
+        &mut self,
+        quote: char,
+        location: Location,
+        start: usize,
+    ) -> Result<Token, Error> {
+        let mut buffer = String::new();
+        let mut closed = false;
+        while let Some(c) = self.peek_char() {
+            #[cfg(feature = "memchr")]
+            if quote == '"' && self.fuel.is_none() {
+                // Copy everything up to the next quote or escape at once.
+                let rest = &self.contents.as_bytes()[self.offset..];
+                let stop = match self.spec.string_newlines {
+                    StringNewlines::Allow => memchr::memchr2(b'"', b'\\', rest),
+                    StringNewlines::Error => memchr::memchr3(b'"', b'\\', b'\n', rest),
+                };
+                let end = stop.map_or(self.contents.len(), |i| self.offset + i);
+                if end > self.offset {
+                    buffer.push_str(&self.contents[self.offset..end]);
+                    self.skip_to(end);
+                    continue;
+                }
+            }
+
+            if c == '\n' && self.spec.string_newlines == StringNewlines::Error {
+                let error = self.newline_in_string(location.clone(), start);
+                return self.recover_string(error, location, start);
+            }
+            self.next_char();
+            if c == quote {
+                closed = true;
+                break;
+            }
+            if c != '\\' {
+                buffer.push(c);
+                continue;
+            }
+            match self.peek_char() {
+                Some('\n') if self.spec.string_newlines == StringNewlines::Error => {
+                    let error = self.newline_in_string(location.clone(), start);
+                    return self.recover_string(error, location, start);
+                }
+                Some(escaped) => {
+                    self.next_char();
+                    push_unescaped(&mut buffer, escaped, quote);
+                }
+                None => buffer.push('\\'),
+            }
+        }
+
+        if !closed && self.line_state.is_some() {
+            if self.spec.string_newlines == StringNewlines::Error {
+                return Err(self.newline_in_string(location, start));
+            }
+            self.line_state = Some(LineState::InString { quote });
+        } else if !closed {
+            let error = Error {
+                kind: ErrorKind::UnclosedString,
+                message: "Unclosed string".to_string(),
+                location: location.clone(),
+                span: self.span_from(start),
+                source_line: Some(self.line_at(start)),
+                mismatch: None,
+                label: None,
+                args: Box::default(),
+            };
+            return self.recover_string(error, location, start);
+        }
+
+        Ok(Token {
+            location,
+            span: self.span_from(start),
+            contents: self.contents[start..self.offset].into(),
+            kind: TokenKind::StringLiteral(buffer.into()),
+            provenance: Provenance::Original,
+        })
+    }
+
+    /// Returns the error for the string starting at the offset `start`, or when recovering,
+    /// moves back to the end of the string's line, collects the error pointing there with a
+    /// label at the opening quote, and returns the rest of the line as an `Unknown` token.
+    fn recover_string(
+        &mut self,
+        mut error: Error,
+        location: Location,
+        start: usize,
+    ) -> Result<Token, Error> {
+        // Running out of fuel in the string ends tokenizing with `ErrorKind::OutOfFuel`.
+        if !self.recovering || self.is_out_of_fuel() {
+            return Err(error);
+        }
+
+        let end = self.contents[start..]
+            .find('\n')
+            .map_or(self.contents.len(), |i| start + i);
+        if let Some(fuel) = self.fuel.as_mut() {
+            *fuel += self.contents[end..self.offset].chars().count();
+        }
+        let text = &self.contents[start..end];
+        self.offset = end;
+        self.location = location.clone();
+        self.location.column += text.chars().count();
+
+        let quote_len = text.chars().next().map_or(0, char::len_utf8);
+        error.label = Some(Box::new(Label {
+            location: location.clone(),
+            span: Span::new(
+                self.source_offset(start),
+                self.source_offset(start + quote_len),
+            ),
+            message: "string starts here".to_string(),
+        }));
+        error.location = self.location.clone();
+        error.span = Span::new(self.source_offset(end), self.source_offset(end));
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            code = error.kind.code(),
+            row = location.row,
+            column = location.column,
+            "read the rest of the line as an unknown token",
+        );
+        self.errors.push(error);
+
+        Ok(Token {
+            location,
+            span: self.span_from(start),
+            contents: text.into(),
+            kind: TokenKind::Unknown(text.into()),
+            provenance: Provenance::Original,
+        })
+    }
+
+    /// Returns the error for a line break in the string starting at the offset `start`,
+    /// covering the string up to the line break.
+    fn newline_in_string(&self, location: Location, start: usize) -> Error {
+        Error {
+            kind: ErrorKind::NewlineInString,
+            message: "Newline in string".to_string(),
+            location,
+            span: self.span_from(start),
+            source_line: Some(self.line_at(start)),
+            mismatch: None,
+            label: None,
+            args: Box::default(),
+        }
+    }
+
+    /// Reads a token from the contents, stopping before the next token.
+    fn read_token(&mut self) -> Option<(String, Location)> {
+        self.peek_char()?;
+
+        let start = self.offset;
+        let location = self.location.clone();
+
+        #[cfg(feature = "memchr")]
+        if self.fuel.is_none() {
+            let run = self.contents.as_bytes()[start..]
+                .iter()
+                .take_while(|b| b.is_ascii() && self.is_identifier_continue(**b as char))
+                .count();
+            self.skip_to(start + run);
+        }
+
+        while let Some(c) = self.peek_char() {
+            if !self.is_identifier_continue(c) {
+                break;
+            }
+            self.next_char();
+        }
+
+        if self.offset == start {
+            return None;
+        }
+
+        Some((self.contents[start..self.offset].to_string(), location))
+    }
+
+    fn read_until_endline(&mut self) -> Option<(String, Location)> {
+        let start = self.offset;
+        let location = self.location.clone();
+
+        #[cfg(feature = "memchr")]
+        if self.fuel.is_none() {
+            let end = memchr::memchr(b'\n', &self.contents.as_bytes()[start..])
+                .map_or(self.contents.len(), |i| start + i);
+            self.skip_to(end);
+        }
+
+        while let Some(c) = self.peek_char() {
+            if c == '\n' {
+                break;
+            }
+            self.next_char();
+        }
+
+        if self.offset == start {
+            return None;
+        }
+
+        Some((self.contents[start..self.offset].to_string(), location))
+    }
+
+    /// Returns the value of `c` if it's a digit under the configured digit rules.
+    fn digit_value(&self, c: char) -> Option<u32> {
+        if let Some(d) = c.to_digit(10) {
+            return Some(d);
+        }
+
+        match self.spec.digits {
+            Digits::Ascii => None,
+            Digits::Unicode => UNICODE_DIGIT_ZEROS.iter().find_map(|zero| {
+                let offset = (c as u32).checked_sub(*zero as u32)?;
+                (offset < 10).then_some(offset)
+            }),
+        }
+    }
+
+    /// Maps a digit to its ASCII form, leaving other characters untouched.
+    fn normalize_digit(&self, c: char) -> char {
+        match self.digit_value(c) {
+            Some(d) => char::from_digit(d, 10).unwrap(),
+            None => c,
+        }
+    }
+
+    /// Returns the class of `c`, looking it up in the table if it's ASCII.
+    fn classify(&self, c: char) -> CharClass {
+        self.spec
+            .table()
+            .get(c)
+            .unwrap_or_else(|| CharClass::of(c, &self.spec))
+    }
+
+    fn is_identifier_continue(&self, c: char) -> bool {
+        match self.spec.table().get(c) {
+            Some(class) => class.contains(CharClass::IDENTIFIER_CONTINUE),
+            None => class::is_identifier_continue(c, &self.spec),
+        }
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        if self.fuel == Some(0) {
+            return None;
+        }
+
+        self.char_at(self.offset)
+    }
+
+    /// Returns the character starting at the byte offset, only decoding UTF-8 when the
+    /// byte there isn't ASCII.
+    fn char_at(&self, offset: usize) -> Option<char> {
+        let byte = *self.contents.as_bytes().get(offset)?;
+        if byte.is_ascii() {
+            Some(byte as char)
+        } else {
+            self.contents.get(offset..)?.chars().next()
+        }
+    }
+
+    /// Returns the next character and updates the location
+    fn next_char(&mut self) -> Option<(char, Location)> {
+        let c = self.peek_char()?;
+        let location = self.location.clone();
+
+        if let Some(fuel) = self.fuel.as_mut() {
+            *fuel -= 1;
+        }
+        self.offset += c.len_utf8();
+        advance(&mut self.location, c);
+
+        Some((c, location))
+    }
+
+    /// Moves past everything up to the byte offset `end` at once. Doesn't use any fuel, so
+    /// it's only for when fuel is unlimited.
+    #[cfg(feature = "memchr")]
+    fn skip_to(&mut self, end: usize) {
+        let skipped = &self.contents.as_bytes()[self.offset..end];
+        match memchr::memrchr(b'\n', skipped) {
+            Some(last) => {
+                self.location.row += memchr::memchr_iter(b'\n', skipped).count();
+                self.location.column = self.contents[self.offset + last + 1..end].chars().count();
+            }
+            None => self.location.column += self.contents[self.offset..end].chars().count(),
+        }
+        self.offset = end;
+    }
+
+    /// Returns the span in the original source from `start` to the current offset.
+    fn span_from(&self, start: usize) -> Span {
+        Span::new(self.source_offset(start), self.source_offset(self.offset))
+    }
+
+    /// Returns true if a `.` after the previous token can start a float, under the config.
+    fn is_leading_dot(&self, previous: Option<&Token>) -> bool {
+        match self.spec.leading_dots {
+            LeadingDots::Always => true,
+            LeadingDots::NotAfterOperand => !self.follows_operand(previous),
+            LeadingDots::Never => false,
+        }
+    }
+
+    /// Returns true if the previous token is a `.` for member access, which the number
+    /// being read is an index for.
+    fn is_member_index(&self, previous: Option<&Token>) -> bool {
+        self.spec.leading_dots == LeadingDots::NotAfterOperand
+            && previous.is_some_and(|token| {
+                token.is_symbol(".") && token.span.end == self.source_offset(self.offset)
+            })
+    }
+
+    /// Returns true if the previous token is an identifier, literal or closing bracket that
+    /// ends right where the next character starts.
+    fn follows_operand(&self, previous: Option<&Token>) -> bool {
+        previous.is_some_and(|token| {
+            let is_operand = match &token.kind {
+                TokenKind::Symbol(s) => matches!(s.as_str(), ")" | "]" | "}"),
+                kind => kind.is_identifier() || kind.is_literal(),
+            };
+            is_operand && token.span.end == self.source_offset(self.offset)
+        })
+    }
+
+    /// Returns the value of the `inf` or `nan` at the offset if `special_floats` is set and
+    /// the word isn't the start of a longer identifier.
+    fn special_float_at(&self, offset: usize) -> Option<f64> {
+        if !self.spec.special_floats {
+            return None;
+        }
+        let value = match self.contents.get(offset..offset + 3)? {
+            word if word.eq_ignore_ascii_case("inf") => f64::INFINITY,
+            word if word.eq_ignore_ascii_case("nan") => f64::NAN,
+            _ => return None,
+        };
+        match self.char_at(offset + 3) {
+            Some(c) if self.is_identifier_continue(c) => None,
+            _ => Some(value),
+        }
+    }
+
+    /// Returns true if the character after the next one is a digit.
+    fn digit_follows(&self) -> bool {
+        self.char_at(self.offset + 1)
+            .and_then(|c| self.digit_value(c))
+            .is_some()
+    }
+
+    /// Returns true if a `-` or `+` after the previous token is a sign, under the config.
+    fn is_sign(&self, previous: Option<&Token>) -> bool {
+        match self.spec.signs {
+            Signs::BeforeDigit => true,
+            Signs::Operator => false,
+            Signs::Context(is_sign) => is_sign(previous),
+        }
+    }
+
+    /// Returns the span in the original source of the next character, or an empty span at
+    /// the end of the contents.
+    fn next_char_span(&self) -> Span {
+        let len = self.peek_char().map_or(0, char::len_utf8);
+        Span::new(
+            self.source_offset(self.offset),
+            self.source_offset(self.offset + len),
+        )
+    }
+
+    /// Returns the line of `contents` containing the byte offset, without its newline.
+    fn line_at(&self, offset: usize) -> Box<str> {
+        let start = self.contents[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let end = self.contents[offset..]
+            .find('\n')
+            .map_or(self.contents.len(), |i| offset + i);
+        self.contents[start..end].into()
+    }
+
+    /// Maps an offset in `contents` to an offset in the original source.
+    fn source_offset(&self, offset: usize) -> usize {
+        let i = self
+            .source_offsets
+            .partition_point(|(copied, _)| *copied <= offset);
+        let source = match i.checked_sub(1).map(|i| self.source_offsets[i]) {
+            None => offset,
+            // Offsets inside a changed run map to its start, as nothing inside lines up.
+            Some((_, source)) if i % 2 == 1 && offset < self.source_offsets[i].0 => source,
+            Some((copied, source)) => source + offset - copied,
+        };
+        self.base_offset + source
+    }
+
+    /// Returns true if there are characters left but no fuel to read them.
+    fn is_out_of_fuel(&self) -> bool {
+        self.fuel == Some(0) && self.offset < self.contents.len()
+    }
+
+    /// Warns if the token matches a deprecated form registered in the config.
+    fn check_deprecated(&mut self, token: &Token) {
+        if let Some(deprecated) = self
+            .spec
+            .deprecated
+            .iter()
+            .find(|deprecated| deprecated.lexeme == token.contents)
+        {
+            self.warnings.push(Warning {
+                message: deprecated.message.clone(),
+                location: token.location.clone(),
+            });
+        }
+    }
+
+    /// Applies the configured policy to any bidirectional control characters in the contents.
+    fn check_bidi_controls(&mut self) -> Result<(), Error> {
+        if self.spec.bidi_controls == BidiControls::Allow {
+            return Ok(());
+        }
+
+        let mut location = self.location.clone();
+        for (offset, c) in self.contents.char_indices() {
+            if is_bidi_control(c) {
+                let message = format!("Bidirectional control character U+{:04X}", c as u32);
+                match self.spec.bidi_controls {
+                    BidiControls::Error => {
+                        return Err(Error {
+                            kind: ErrorKind::BidiControl,
+                            message,
+                            location,
+                            span: Span::new(
+                                self.source_offset(offset),
+                                self.source_offset(offset + c.len_utf8()),
+                            ),
+                            source_line: Some(self.line_at(offset)),
+                            mismatch: None,
+                            label: None,
+                            args: Box::new([("code", format!("U+{:04X}", c as u32))]),
+                        })
+                    }
+                    BidiControls::Warn => self.warnings.push(Warning {
+                        message,
+                        location: location.clone(),
+                    }),
+                    BidiControls::Allow => {}
+                }
+            }
+            advance(&mut location, c);
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the length of the character at the start of `text` along with the ones after it
+/// that normalizing could combine with it, so the run can be normalized on its own.
+fn normalization_run_len(text: &str) -> usize {
+    use unicode_normalization::char::{canonical_combining_class, compose};
+
+    let mut chars = text.char_indices();
+    let mut last = chars.next().map_or('\0', |(_, c)| c);
+    for (i, c) in chars {
+        if c.is_ascii() {
+            return i;
+        }
+        if canonical_combining_class(c) == 0 {
+            match compose(last, c) {
+                Some(composed) => last = composed,
+                None => return i,
+            }
+        }
+    }
+    text.len()
+}
+
+/// Pushes the character a backslash escape in a string stands for, or the escape as written
+/// if it isn't one of `\n`, `\r`, `\t`, `\0`, `\\` or a backslash before a quote.
+fn push_unescaped(buffer: &mut String, escaped: char, quote: char) {
+    match escaped {
+        'n' => buffer.push('\n'),
+        'r' => buffer.push('\r'),
+        't' => buffer.push('\t'),
+        '0' => buffer.push('\0'),
+        '\\' | '"' => buffer.push(escaped),
+        c if c == quote => buffer.push(c),
+        c => {
+            buffer.push('\\');
+            buffer.push(c);
+        }
+    }
+}
+
+/// Moves the location past the given character.
+fn advance(location: &mut Location, c: char) {
+    location.column += 1;
+    if c == '\n' {
+        location.row += 1;
+        location.column = 0;
     }
 }
 
-fn is_symbol(c: char) -> bool {
-    match c {
-        '+' | '-' | '*' | '/' | '=' | '>' | '<' | '!' | '?' | '.' | ',' | ';' | ':' | '(' | ')'
-        | '[' | ']' | '{' | '}' | '&' | '|' | '^' | '%' | '~' | '#' => true,
-        _ => false,
-    }
-}
+/// Returns true for the Unicode bidirectional embedding, override, isolate and mark characters.
+fn is_bidi_control(c: char) -> bool {
+    matches!(
+        c,
+        '\u{061C}' | '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}'
+    )
+}
+
+/// Returns the length of the ISO 8601 date, date-time or time at the start of `s`, such as
+/// `2024-05-01`, `2024-05-01T12:30:00Z` or `12:30:00.5`. Times need seconds, and only
+/// date-times can have an offset.
+fn datetime_len(s: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+    let Some(date) = date_len(bytes) else {
+        return time_len(bytes);
+    };
+    if !matches!(bytes.get(date), Some(b'T' | b't')) {
+        return Some(date);
+    }
+    let Some(time) = time_len(&bytes[date + 1..]) else {
+        return Some(date);
+    };
+
+    let len = date + 1 + time;
+    match bytes.get(len) {
+        Some(b'Z' | b'z') => Some(len + 1),
+        Some(b'+' | b'-')
+            if two_digits(bytes, len + 1, 23)
+                && bytes.get(len + 3) == Some(&b':')
+                && two_digits(bytes, len + 4, 59) =>
+        {
+            Some(len + 6)
+        }
+        _ => Some(len),
+    }
+}
+
+/// Returns the length of a `YYYY-MM-DD` date at the start of `bytes`.
+fn date_len(bytes: &[u8]) -> Option<usize> {
+    let year = bytes.get(..4)?.iter().all(u8::is_ascii_digit);
+    let date = year
+        && bytes.get(4) == Some(&b'-')
+        && two_digits(bytes, 5, 12)
+        && bytes.get(7) == Some(&b'-')
+        && two_digits(bytes, 8, 31);
+    date.then_some(10)
+}
+
+/// Returns the length of an `HH:MM:SS` time, with any fractional seconds, at the start of
+/// `bytes`.
+fn time_len(bytes: &[u8]) -> Option<usize> {
+    let time = two_digits(bytes, 0, 23)
+        && bytes.get(2) == Some(&b':')
+        && two_digits(bytes, 3, 59)
+        && bytes.get(5) == Some(&b':')
+        && two_digits(bytes, 6, 60);
+    if !time {
+        return None;
+    }
+    let fraction = match bytes.get(8) {
+        Some(b'.') => bytes[9..].iter().take_while(|b| b.is_ascii_digit()).count(),
+        _ => 0,
+    };
+    Some(if fraction > 0 { 9 + fraction } else { 8 })
+}
+
+/// Returns true if `bytes` has two ASCII digits at `at`, forming a number no larger than `max`.
+fn two_digits(bytes: &[u8], at: usize, max: u8) -> bool {
+    match bytes.get(at..at + 2) {
+        Some([tens, ones]) if tens.is_ascii_digit() && ones.is_ascii_digit() => {
+            (tens - b'0') * 10 + (ones - b'0') <= max
+        }
+        _ => false,
+    }
+}
+
+/// The zero digit of each Unicode decimal digit run accepted by `Digits::Unicode`.
+const UNICODE_DIGIT_ZEROS: [char; 12] = [
+    '\u{0660}', // Arabic-Indic
+    '\u{06F0}', // Extended Arabic-Indic
+    '\u{07C0}', // NKo
+    '\u{0966}', // Devanagari
+    '\u{09E6}', // Bengali
+    '\u{0A66}', // Gurmukhi
+    '\u{0AE6}', // Gujarati
+    '\u{0BE6}', // Tamil
+    '\u{0E50}', // Thai
+    '\u{0ED0}', // Lao
+    '\u{1040}', // Myanmar
+    '\u{FF10}', // Fullwidth
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Deprecated, IdentifierRules, Whitespace};
+
+    fn comment(raw: &str) -> TokenKind {
+        TokenKind::Comment(Box::new(Comment::new(CommentStyle::Line, raw)))
+    }
+
+    #[test]
+    fn empty() {
+        let contents = "";
+        let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
+        assert_eq!(tokens.len(), 0);
+
+        let contents = "\n\n \r\n";
+        let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
+        assert_eq!(tokens.len(), 0);
+    }
+
+    #[test]
+    fn empty_comment() {
+        let contents = r#"#"#;
+        let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].kind, comment(""));
+        assert_eq!(tokens[0].contents, "");
+        assert_eq!(tokens[0].location, (0, 0).into());
+    }
+
+    #[test]
+    fn single_comment() {
+        let contents = r#"# This is a comment"#;
+        let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].kind, comment(" This is a comment"));
+        assert_eq!(tokens[0].contents, "This is a comment");
+        assert_eq!(tokens[0].location, (0, 0).into());
+    }
+
+    #[test]
+    fn single_identifier() {
+        let contents = r#"my_variable"#;
+        let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].kind, TokenKind::Identifier("my_variable".into()));
+        assert_eq!(tokens[0].contents, "my_variable");
+        assert_eq!(tokens[0].location, (0, 0).into());
+    }
+
+    #[test]
+    fn multiple_identifiers_with_space() {
+        let contents = r#"my_variable my_variable2"#;
+        let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[0].kind, TokenKind::Identifier("my_variable".into()));
+        assert_eq!(tokens[0].contents, "my_variable");
+        assert_eq!(tokens[0].location, (0, 0).into());
+        assert_eq!(tokens[1].kind, TokenKind::Identifier("my_variable2".into()));
+        assert_eq!(tokens[1].contents, "my_variable2");
+        assert_eq!(tokens[1].location, (0, 12).into());
+    }
+
+    #[test]
+    fn multiple_identifiers_with_newline() {
+        let contents = "my_variable \nmy_variable2";
+        let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[0].kind, TokenKind::Identifier("my_variable".into()));
+        assert_eq!(tokens[0].contents, "my_variable");
+        assert_eq!(tokens[0].location, (0, 0).into());
+        assert_eq!(tokens[1].kind, TokenKind::Identifier("my_variable2".into()));
+        assert_eq!(tokens[1].contents, "my_variable2");
+        assert_eq!(tokens[1].location, (1, 0).into());
+    }
+
+    #[test]
+    fn multiple_identifiers_with_comment() {
+        let contents = "my_variable \n# This is a comment\nmy_variable2";
+        let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens[0].kind, TokenKind::Identifier("my_variable".into()));
+        assert_eq!(tokens[0].contents, "my_variable");
+        assert_eq!(tokens[0].location, (0, 0).into());
+        assert_eq!(tokens[1].kind, comment(" This is a comment"));
+        assert_eq!(tokens[1].contents, "This is a comment");
+        assert_eq!(tokens[1].location, (1, 0).into());
+        assert_eq!(tokens[2].kind, TokenKind::Identifier("my_variable2".into()));
+        assert_eq!(tokens[2].contents, "my_variable2");
+        assert_eq!(tokens[2].location, (2, 0).into());
+    }
+
+    #[test]
+    fn identifier_splits_on_comment() {
+        let contents = "my_variable# This is a comment\nmy_variable2";
+        let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens[0].kind, TokenKind::Identifier("my_variable".into()));
+        assert_eq!(tokens[0].contents, "my_variable");
+        assert_eq!(tokens[0].location, (0, 0).into());
+        assert_eq!(tokens[1].kind, comment(" This is a comment"));
+        assert_eq!(tokens[1].contents, "This is a comment");
+        assert_eq!(tokens[1].location, (0, 11).into());
+        assert_eq!(tokens[2].kind, TokenKind::Identifier("my_variable2".into()));
+        assert_eq!(tokens[2].contents, "my_variable2");
+        assert_eq!(tokens[2].location, (1, 0).into());
+    }
+
+    #[test]
+    fn string_literal_unclosed_returns_error() {
+        let contents = r#""This is a string"#;
+        let result = Tokenizer::tokenize(contents, (0, 0).into());
+        let expected = Err(Error {
+            kind: ErrorKind::UnclosedString,
+            message: "Unclosed string".to_string(),
+            location: (0, 0).into(),
+            span: Span::new(0, 17),
+            source_line: Some(contents.into()),
+            mismatch: None,
+            label: None,
+            args: Box::default(),
+        });
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn string_literal() {
+        let contents = r#""This is a string""#;
+        let result = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(
+            result[0].kind,
+            TokenKind::StringLiteral("This is a string".into())
+        );
+        assert_eq!(result[0].contents, contents);
+        assert_eq!(result[0].location, (0, 0).into());
+    }
+
+    #[test]
+    fn string_literal_with_escaping() {
+        let contents = r#""This is a string with \"escaping\" characters""#;
+        let result = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(
+            result[0].kind,
+            TokenKind::StringLiteral("This is a string with \"escaping\" characters".into())
+        );
+        assert_eq!(result[0].contents, contents);
+        assert_eq!(result[0].location, (0, 0).into());
+    }
+
+    #[test]
+    fn string_literal_with_escaped_comment() {
+        let contents = r#""This is a string with # escaped comment""#;
+        let result = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(
+            result[0].kind,
+            TokenKind::StringLiteral("This is a string with # escaped comment".into())
+        );
+        assert_eq!(result[0].contents, contents);
+        assert_eq!(result[0].location, (0, 0).into());
+    }
+
+    #[test]
+    fn read_single_symbol() {
+        let symbols = vec![
+            '+', '-', '*', '/', '=', '>', '<', '!', '?', '.', ',', ';', ':', '(', ')', '[', ']',
+            '{', '}', '&', '|', '^', '%', '~',
+        ];
+        for symbol in symbols {
+            let contents = symbol.to_string();
+            let tokens = Tokenizer::tokenize(&contents, (0, 0).into()).unwrap();
+            assert_eq!(tokens.len(), 1);
+            assert_eq!(tokens[0].kind, TokenKind::Symbol(symbol.into()));
+            assert_eq!(tokens[0].contents, symbol.to_string());
+            assert_eq!(tokens[0].location, (0, 0).into());
+        }
+    }
+
+    #[test]
+    fn read_two_char_symbols() {
+        let symbols = vec!["==", "!=", ">=", "<=", "->", "=>", "*=", "-=", "+=", "/="];
+        for symbol in symbols {
+            let contents = symbol.to_string();
+            let tokens = Tokenizer::tokenize(&contents, (0, 0).into()).unwrap();
+            assert_eq!(tokens.len(), 1);
+            assert_eq!(tokens[0].kind, TokenKind::Symbol(symbol.into()));
+            assert_eq!(tokens[0].contents, symbol);
+            assert_eq!(tokens[0].location, (0, 0).into());
+        }
+    }
+
+    #[test]
+    fn read_identifier_with_symbol() {
+        let symbols = vec![
+            ".", ",", ";", ":", "(", ")", "[", "]", "{", "}", "->", "==", "!=", ">=", "<=", "->",
+            "=>", "*=", "-=", "+=", "/=", "=>",
+        ];
+
+        for symbol in symbols {
+            let contents = format!("my_variable{}my_variable2", symbol);
+            let tokens = Tokenizer::tokenize(&contents, (0, 0).into()).unwrap();
+            assert_eq!(tokens.len(), 3);
+            assert_eq!(tokens[0].kind, TokenKind::Identifier("my_variable".into()));
+            assert_eq!(tokens[0].contents, "my_variable");
+            assert_eq!(tokens[0].location, (0, 0).into());
+            assert_eq!(tokens[1].kind, TokenKind::Symbol(symbol.into()));
+            assert_eq!(tokens[1].contents, symbol.to_string());
+            assert_eq!(tokens[1].location, (0, 11).into());
+            assert_eq!(tokens[2].kind, TokenKind::Identifier("my_variable2".into()));
+            assert_eq!(tokens[2].contents, "my_variable2");
+            assert_eq!(tokens[2].location, (0, 11 + symbol.len()).into());
+        }
+    }
+
+    #[test]
+    fn read_integer() {
+        let contents = "12345";
+        let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].kind, TokenKind::IntegerLiteral(12345));
+        assert_eq!(tokens[0].contents, "12345");
+        assert_eq!(tokens[0].location, (0, 0).into());
+    }
+
+    #[test]
+    fn read_float() {
+        let contents = "12345.6789";
+        let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].kind, TokenKind::FloatLiteral(12345.6789));
+        assert_eq!(tokens[0].contents, "12345.6789");
+        assert_eq!(tokens[0].location, (0, 0).into());
+    }
+
+    #[test]
+    fn read_float_with_negative() {
+        let contents = "-12345.6789";
+        let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].kind, TokenKind::FloatLiteral(-12345.6789));
+        assert_eq!(tokens[0].contents, "-12345.6789");
+        assert_eq!(tokens[0].location, (0, 0).into());
+    }
+
+    #[test]
+    fn read_float_with_multiple_decimals() {
+        let contents = "-12345.6789.12345";
+        let tokens = Tokenizer::tokenize(contents, (0, 0).into());
+        assert!(tokens.is_err());
+        assert_eq!(
+            tokens.err().unwrap(),
+            Error {
+                kind: ErrorKind::MultipleDecimalPoints,
+                message: "Float literal cannot have multiple decimal points".to_string(),
+                location: (0, 11).into(),
+                span: Span::new(0, 12),
+                source_line: Some(contents.into()),
+                mismatch: None,
+                label: None,
+                args: Box::default(),
+            }
+        );
+    }
+
+    #[test]
+    fn read_integer_with_negative() {
+        let contents = "-12345";
+        let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].kind, TokenKind::IntegerLiteral(-12345));
+        assert_eq!(tokens[0].contents, "-12345");
+        assert_eq!(tokens[0].location, (0, 0).into());
+    }
+
+    #[test]
+    fn sign_policies() {
+        let kinds = |contents: &str, signs: Signs| -> Vec<TokenKind> {
+            let config = TokenizerConfig {
+                signs,
+                ..Default::default()
+            };
+            Tokenizer::tokenize_with_config(contents, (0, 0).into(), &config)
+                .unwrap()
+                .into_iter()
+                .map(|token| token.kind)
+                .collect()
+        };
+        let minus = || TokenKind::Symbol("-".into());
+
+        assert_eq!(
+            kinds("a-1", Signs::BeforeDigit),
+            vec![
+                TokenKind::Identifier("a".into()),
+                TokenKind::IntegerLiteral(-1)
+            ]
+        );
+        assert_eq!(
+            kinds("-1", Signs::Operator),
+            vec![minus(), TokenKind::IntegerLiteral(1)]
+        );
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let context = Signs::Context(Signs::unary_position);
+        assert_eq!(
+            kinds("a-1", context),
+            vec![
+                TokenKind::Identifier("a".into()),
+                minus(),
+                TokenKind::IntegerLiteral(1)
+            ]
+        );
+        assert_eq!(
+            kinds("(-1)-2", context),
+            vec![
+                TokenKind::Symbol("(".into()),
+                TokenKind::IntegerLiteral(-1),
+                TokenKind::Symbol(")".into()),
+                minus(),
+                TokenKind::IntegerLiteral(2)
+            ]
+        );
+    }
 
     #[test]
-    fn empty() {
-        let contents = "";
-        let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
-        assert_eq!(tokens.len(), 0);
+    fn plus_signs() {
+        let tokens = Tokenizer::tokenize("+42", (0, 0).into()).unwrap();
+        assert_eq!(tokens[0].kind, TokenKind::Symbol("+".into()));
 
-        let contents = "\n\n \r\n";
-        let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
-        assert_eq!(tokens.len(), 0);
+        let config = TokenizerConfig {
+            plus_signs: true,
+            ..Default::default()
+        };
+        let tokens =
+            Tokenizer::tokenize_with_config("+42 +1.5 +x", (0, 0).into(), &config).unwrap();
+        let kinds: Vec<TokenKind> = tokens.into_iter().map(|token| token.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::IntegerLiteral(42),
+                TokenKind::FloatLiteral(1.5),
+                TokenKind::Symbol("+".into()),
+                TokenKind::Identifier("x".into())
+            ]
+        );
+
+        let config = TokenizerConfig {
+            plus_signs: true,
+            signs: Signs::Operator,
+            ..Default::default()
+        };
+        let tokens = Tokenizer::tokenize_with_config("+42", (0, 0).into(), &config).unwrap();
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(
+            config.validate(),
+            Err("plus_signs has no effect when signs is Operator")
+        );
     }
 
     #[test]
-    fn empty_comment() {
-        let contents = r#"#"#;
-        let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
-        assert_eq!(tokens.len(), 1);
-        assert_eq!(tokens[0].kind, TokenKind::Comment("".to_string()));
-        assert_eq!(tokens[0].contents, "");
-        assert_eq!(tokens[0].location, (0, 0).into());
+    fn dot_policies() {
+        let kinds = |contents: &str, leading_dots: LeadingDots, trailing_dots: bool| {
+            let config = TokenizerConfig {
+                leading_dots,
+                trailing_dots,
+                ..Default::default()
+            };
+            Tokenizer::tokenize_with_config(contents, (0, 0).into(), &config)
+                .unwrap()
+                .into_iter()
+                .map(|token| token.kind)
+                .collect::<Vec<_>>()
+        };
+        let dot = || TokenKind::Symbol(".".into());
+        let x = || TokenKind::Identifier("x".into());
+
+        assert_eq!(
+            kinds("x.5", LeadingDots::Always, true),
+            vec![x(), TokenKind::FloatLiteral(0.5)]
+        );
+        assert_eq!(
+            kinds("x.5 .5", LeadingDots::NotAfterOperand, true),
+            vec![
+                x(),
+                dot(),
+                TokenKind::IntegerLiteral(5),
+                TokenKind::FloatLiteral(0.5)
+            ]
+        );
+        assert_eq!(
+            kinds("x.0.1", LeadingDots::NotAfterOperand, true),
+            vec![
+                x(),
+                dot(),
+                TokenKind::IntegerLiteral(0),
+                dot(),
+                TokenKind::IntegerLiteral(1)
+            ]
+        );
+        assert_eq!(
+            kinds(".5", LeadingDots::Never, true),
+            vec![dot(), TokenKind::IntegerLiteral(5)]
+        );
+
+        assert_eq!(
+            kinds("5.x", LeadingDots::Always, true),
+            vec![TokenKind::FloatLiteral(5.0), x()]
+        );
+        assert_eq!(
+            kinds("5.x 2.5", LeadingDots::Always, false),
+            vec![
+                TokenKind::IntegerLiteral(5),
+                dot(),
+                x(),
+                TokenKind::FloatLiteral(2.5)
+            ]
+        );
     }
 
     #[test]
-    fn single_comment() {
-        let contents = r#"# This is a comment"#;
-        let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
-        assert_eq!(tokens.len(), 1);
+    fn ranges() {
+        let kinds = |contents: &str| {
+            Tokenizer::tokenize(contents, (0, 0).into())
+                .unwrap()
+                .into_iter()
+                .map(|token| token.kind)
+                .collect::<Vec<_>>()
+        };
+
         assert_eq!(
-            tokens[0].kind,
-            TokenKind::Comment("This is a comment".to_string())
+            kinds("1..10"),
+            vec![
+                TokenKind::IntegerLiteral(1),
+                TokenKind::Symbol("..".into()),
+                TokenKind::IntegerLiteral(10)
+            ]
+        );
+        assert_eq!(
+            kinds("0..=.5"),
+            vec![
+                TokenKind::IntegerLiteral(0),
+                TokenKind::Symbol("..=".into()),
+                TokenKind::FloatLiteral(0.5)
+            ]
+        );
+        assert_eq!(
+            kinds("1.5..a"),
+            vec![
+                TokenKind::FloatLiteral(1.5),
+                TokenKind::Symbol("..".into()),
+                TokenKind::Identifier("a".into())
+            ]
         );
-        assert_eq!(tokens[0].contents, "This is a comment");
-        assert_eq!(tokens[0].location, (0, 0).into());
     }
 
     #[test]
-    fn single_identifier() {
-        let contents = r#"my_variable"#;
-        let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
-        assert_eq!(tokens.len(), 1);
+    fn special_floats() {
+        let config = TokenizerConfig {
+            special_floats: true,
+            ..Default::default()
+        };
+        let tokens =
+            Tokenizer::tokenize_with_config("inf -INF NaN info", (0, 0).into(), &config).unwrap();
+        assert_eq!(tokens.len(), 4);
+        assert_eq!(tokens[0].kind, TokenKind::FloatLiteral(f64::INFINITY));
+        assert_eq!(tokens[1].kind, TokenKind::FloatLiteral(f64::NEG_INFINITY));
+        assert_eq!(tokens[1].contents, "-INF");
+        assert_eq!(tokens[1].span, Span::new(4, 8));
+        assert!(matches!(tokens[2].kind, TokenKind::FloatLiteral(f) if f.is_nan()));
+        assert_eq!(tokens[3].kind, TokenKind::Identifier("info".into()));
+
+        let tokens = Tokenizer::tokenize("inf", (0, 0).into()).unwrap();
+        assert_eq!(tokens[0].kind, TokenKind::Identifier("inf".into()));
+    }
+
+    #[test]
+    fn xid_identifiers_split_on_unexpected_characters() {
+        let config = TokenizerConfig {
+            identifiers: IdentifierRules::UnicodeXid,
+            unexpected_characters: UnexpectedCharacters::Token,
+            ..Default::default()
+        };
+        let tokens =
+            Tokenizer::tokenize_with_config("foo$bar_1 été", (0, 0).into(), &config).unwrap();
+        assert_eq!(tokens.len(), 4);
+        assert_eq!(tokens[0].kind, TokenKind::Identifier("foo".into()));
+        assert_eq!(tokens[1].kind, TokenKind::Unknown("$".into()));
+        assert_eq!(tokens[1].location, (0, 3).into());
+        assert_eq!(tokens[2].kind, TokenKind::Identifier("bar_1".into()));
+        assert_eq!(tokens[3].kind, TokenKind::Identifier("été".into()));
+
+        let config = TokenizerConfig {
+            identifiers: IdentifierRules::UnicodeXid,
+            ..Default::default()
+        };
+        let result = Tokenizer::tokenize_with_config("foo$bar", (0, 0).into(), &config);
+        let expected = Err(Error {
+            kind: ErrorKind::UnexpectedCharacter,
+            message: "Unexpected character '$'".to_string(),
+            location: (0, 3).into(),
+            span: Span::new(3, 4),
+            source_line: Some("foo$bar".into()),
+            mismatch: None,
+            label: None,
+            args: Box::new([("char", "$".to_string())]),
+        });
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn ascii_identifiers_reject_non_ascii() {
+        let config = TokenizerConfig {
+            identifiers: IdentifierRules::Ascii,
+            ..Default::default()
+        };
+        let tokens = Tokenizer::tokenize_with_config("_foo Bar9", (0, 0).into(), &config).unwrap();
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[0].kind, TokenKind::Identifier("_foo".into()));
+        assert_eq!(tokens[1].kind, TokenKind::Identifier("Bar9".into()));
+
+        let result = Tokenizer::tokenize_with_config("let caf\u{e9}", (0, 0).into(), &config);
+        let expected = Err(Error {
+            kind: ErrorKind::UnexpectedCharacter,
+            message: "Unexpected character '\u{e9}'".to_string(),
+            location: (0, 7).into(),
+            span: Span::new(7, 9),
+            source_line: Some("let caf\u{e9}".into()),
+            mismatch: None,
+            label: None,
+            args: Box::new([("char", "\u{e9}".to_string())]),
+        });
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn unicode_digits_are_not_numbers_by_default() {
+        let config = TokenizerConfig {
+            identifiers: IdentifierRules::UnicodeXid,
+            ..Default::default()
+        };
+        let result = Tokenizer::tokenize_with_config("\u{00BE}", (0, 0).into(), &config);
+        let expected = Err(Error {
+            kind: ErrorKind::UnexpectedCharacter,
+            message: "Unexpected character '\u{00BE}'".to_string(),
+            location: (0, 0).into(),
+            span: Span::new(0, 2),
+            source_line: Some("\u{00BE}".into()),
+            mismatch: None,
+            label: None,
+            args: Box::new([("char", "\u{00BE}".to_string())]),
+        });
+        assert_eq!(expected, result);
+
+        let tokens = Tokenizer::tokenize("\u{0663}", (0, 0).into()).unwrap();
+        assert_eq!(tokens[0].kind, TokenKind::Identifier("\u{0663}".into()));
+    }
+
+    #[test]
+    fn decimal_fractions() {
+        let config = TokenizerConfig {
+            digits: Digits::Unicode,
+            fractions: Fractions::Decimal,
+            ..Default::default()
+        };
+        let tokens =
+            Tokenizer::tokenize_with_config("19.99 -0.10 \u{0661}.5 7", (0, 0).into(), &config)
+                .unwrap();
+        assert_eq!(tokens.len(), 4);
+        assert_eq!(tokens[0].kind, TokenKind::DecimalLiteral("19.99".into()));
+        assert_eq!(tokens[1].as_decimal(), Some("-0.10"));
+        assert_eq!(tokens[2].as_decimal(), Some("1.5"));
+        assert_eq!(tokens[3].kind, TokenKind::IntegerLiteral(7));
+    }
+
+    #[test]
+    fn unit_suffixes() {
+        let config = TokenizerConfig {
+            units: vec!["s".into(), "ms".into(), "GiB".into()],
+            ..Default::default()
+        };
+        let tokens =
+            Tokenizer::tokenize_with_config("250ms 1.5s 2GiB 3sec 4", (0, 0).into(), &config)
+                .unwrap();
+        let unit = |value: &str, unit: &str| TokenKind::UnitLiteral {
+            value: value.into(),
+            unit: unit.into(),
+        };
+        assert_eq!(tokens.len(), 6);
+        assert_eq!(tokens[0].kind, unit("250", "ms"));
+        assert_eq!(tokens[0].contents, "250ms");
+        assert_eq!(tokens[0].span, Span::new(0, 5));
+        assert_eq!(tokens[1].kind, unit("1.5", "s"));
+        assert_eq!(tokens[2].kind, unit("2", "GiB"));
+        assert_eq!(tokens[3].kind, TokenKind::IntegerLiteral(3));
+        assert_eq!(tokens[4].kind, TokenKind::Identifier("sec".into()));
+        assert_eq!(tokens[5].kind, TokenKind::IntegerLiteral(4));
+    }
+
+    #[test]
+    fn datetimes() {
+        let config = TokenizerConfig {
+            datetimes: true,
+            ..Default::default()
+        };
+        let contents =
+            "2024-05-01 2024-05-01T12:30:00Z 2024-05-01T12:30:00.25-07:00 07:32:00 2024-13-01";
+        let tokens = Tokenizer::tokenize_with_config(contents, (0, 0).into(), &config).unwrap();
+        let datetime = |s: &str| TokenKind::DateTimeLiteral(s.into());
+        assert_eq!(tokens[0].kind, datetime("2024-05-01"));
+        assert_eq!(tokens[1].kind, datetime("2024-05-01T12:30:00Z"));
+        assert_eq!(tokens[1].span, Span::new(11, 31));
+        assert_eq!(tokens[2].kind, datetime("2024-05-01T12:30:00.25-07:00"));
+        assert_eq!(tokens[3].kind, datetime("07:32:00"));
+        assert_eq!(tokens[4].kind, TokenKind::IntegerLiteral(2024));
+        assert_eq!(tokens[5].kind, TokenKind::IntegerLiteral(-13));
+
+        let tokens = Tokenizer::tokenize("2024-05-01", (0, 0).into()).unwrap();
+        assert_eq!(tokens.len(), 3);
+    }
+
+    #[test]
+    fn colors() {
+        let config = TokenizerConfig {
+            colors: true,
+            ..Default::default()
+        };
+        let contents = "fill #fff #AABBCC80\n#abcde is a comment";
+        let tokens = Tokenizer::tokenize_with_config(contents, (0, 0).into(), &config).unwrap();
+        assert_eq!(tokens.len(), 4);
+        assert_eq!(tokens[1].kind, TokenKind::ColorLiteral("fff".into()));
+        assert_eq!(tokens[1].contents, "#fff");
+        assert_eq!(tokens[2].kind, TokenKind::ColorLiteral("AABBCC80".into()));
+        assert_eq!(tokens[2].span, Span::new(10, 19));
+        assert_eq!(tokens[3].kind, comment("abcde is a comment"));
+    }
+
+    #[test]
+    fn percents() {
+        let config = TokenizerConfig {
+            percents: true,
+            ..Default::default()
+        };
+        let tokens =
+            Tokenizer::tokenize_with_config("50% 12.5% 10 % 3", (0, 0).into(), &config).unwrap();
+        assert_eq!(tokens.len(), 5);
+        assert_eq!(tokens[0].kind, TokenKind::PercentLiteral("50".into()));
+        assert_eq!(tokens[0].span, Span::new(0, 3));
+        assert_eq!(tokens[1].kind, TokenKind::PercentLiteral("12.5".into()));
+        assert_eq!(tokens[1].contents, "12.5%");
+        assert_eq!(tokens[3].kind, TokenKind::Symbol("%".into()));
+    }
+
+    #[test]
+    fn percents_and_units_keep_their_numbers_exactly() {
+        let config = TokenizerConfig {
+            percents: true,
+            units: vec!["px".into()],
+            ..Default::default()
+        };
+        let tokens = Tokenizer::tokenize_with_config(
+            "9007199254740993px 0.1000000000000000055511151231257827% -7.5%",
+            (0, 0).into(),
+            &config,
+        )
+        .unwrap();
         assert_eq!(
             tokens[0].kind,
-            TokenKind::Identifier("my_variable".to_string())
+            TokenKind::UnitLiteral {
+                value: "9007199254740993".into(),
+                unit: "px".into(),
+            }
+        );
+        assert_eq!(
+            tokens[1].kind,
+            TokenKind::PercentLiteral("0.1000000000000000055511151231257827".into())
+        );
+        assert_eq!(tokens[2].kind, TokenKind::PercentLiteral("-7.5".into()));
+
+        let config = TokenizerConfig {
+            fractions: Fractions::Decimal,
+            digits: Digits::Unicode,
+            ..config
+        };
+        let tokens =
+            Tokenizer::tokenize_with_config("19.99% \u{0661}\u{0662}px", (0, 0).into(), &config)
+                .unwrap();
+        assert_eq!(tokens[0].kind, TokenKind::PercentLiteral("19.99".into()));
+        assert_eq!(
+            tokens[1].kind,
+            TokenKind::UnitLiteral {
+                value: "12".into(),
+                unit: "px".into(),
+            }
         );
-        assert_eq!(tokens[0].contents, "my_variable");
-        assert_eq!(tokens[0].location, (0, 0).into());
     }
 
+    #[cfg(feature = "bigint")]
     #[test]
-    fn multiple_identifiers_with_space() {
-        let contents = r#"my_variable my_variable2"#;
-        let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
+    fn big_integers_take_percents_and_units() {
+        let config = TokenizerConfig {
+            percents: true,
+            units: vec!["px".into()],
+            ..Default::default()
+        };
+        let big = "170141183460469231731687303715884105728";
+        let contents = format!("{}% {}px", big, big);
+        let tokens = Tokenizer::tokenize_with_config(&contents, (0, 0).into(), &config).unwrap();
         assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[0].kind, TokenKind::PercentLiteral(big.into()));
+        assert_eq!(
+            tokens[1].kind,
+            TokenKind::UnitLiteral {
+                value: big.into(),
+                unit: "px".into(),
+            }
+        );
+        assert_eq!(tokens[1].contents, format!("{}px", big));
+    }
+
+    #[test]
+    fn attributes() {
+        let kinds = |contents: &str, attributes: Attributes| {
+            let config = TokenizerConfig {
+                attributes,
+                ..Default::default()
+            };
+            Tokenizer::tokenize_with_config(contents, (0, 0).into(), &config)
+                .unwrap()
+                .into_iter()
+                .map(|token| token.kind)
+                .collect::<Vec<_>>()
+        };
+        let attribute = |s: &str| TokenKind::Attribute(s.into());
+
+        assert_eq!(
+            kinds("@test fn", Attributes::None),
+            vec![
+                TokenKind::Symbol("@".into()),
+                TokenKind::Identifier("test".into()),
+                TokenKind::Identifier("fn".into())
+            ]
+        );
+        assert_eq!(
+            kinds("@test @ x", Attributes::At),
+            vec![
+                attribute("test"),
+                TokenKind::Symbol("@".into()),
+                TokenKind::Identifier("x".into())
+            ]
+        );
+        assert_eq!(
+            kinds("#[cfg(a[0])] x\n#[ not closed", Attributes::All),
+            vec![
+                attribute("cfg(a[0])"),
+                TokenKind::Identifier("x".into()),
+                comment("[ not closed")
+            ]
+        );
+    }
+
+    #[test]
+    fn sigils() {
+        let config = TokenizerConfig {
+            sigils: vec!['$', '%'],
+            ..Default::default()
+        };
+        let tokens =
+            Tokenizer::tokenize_with_config("$foo %bar $ 5 % 2", (0, 0).into(), &config).unwrap();
+        let variable = |sigil: char, name: &str| TokenKind::Variable {
+            sigil,
+            name: name.into(),
+        };
+        assert_eq!(tokens.len(), 6);
+        assert_eq!(tokens[0].kind, variable('$', "foo"));
+        assert_eq!(tokens[0].contents, "$foo");
+        assert_eq!(tokens[1].kind, variable('%', "bar"));
+        assert_eq!(tokens[1].span, Span::new(5, 9));
+        assert_eq!(tokens[2].kind, TokenKind::Identifier("$".into()));
+        assert_eq!(tokens[4].kind, TokenKind::Symbol("%".into()));
+    }
+
+    #[test]
+    fn path_separators() {
+        let config = TokenizerConfig {
+            path_separators: vec!["::".into(), ".".into()],
+            ..Default::default()
+        };
+        let tokens =
+            Tokenizer::tokenize_with_config("foo::bar.baz x.0 y:: z", (0, 0).into(), &config)
+                .unwrap();
+        assert_eq!(tokens[0].kind, TokenKind::Identifier("foo::bar.baz".into()));
+        assert_eq!(tokens[0].span, Span::new(0, 12));
+        assert_eq!(tokens[1].kind, TokenKind::Identifier("x".into()));
+        assert_eq!(tokens[2].kind, TokenKind::FloatLiteral(0.0));
+        assert_eq!(tokens[3].kind, TokenKind::Identifier("y".into()));
+        assert_eq!(tokens[4].kind, TokenKind::Symbol(":".into()));
+    }
+
+    #[test]
+    fn identifier_case_folding() {
+        let config = TokenizerConfig {
+            identifier_case: IdentifierCase::Lower,
+            sigils: vec!['$'],
+            ..Default::default()
+        };
+        let tokens =
+            Tokenizer::tokenize_with_config("SELECT Name $Row", (0, 0).into(), &config).unwrap();
+        assert_eq!(tokens[0].kind, TokenKind::Identifier("select".into()));
+        assert_eq!(tokens[0].contents, "SELECT");
+        assert_eq!(tokens[1].kind, TokenKind::Identifier("name".into()));
+        assert_eq!(
+            tokens[2].kind,
+            TokenKind::Variable {
+                sigil: '$',
+                name: "row".into()
+            }
+        );
+    }
+
+    #[test]
+    fn custom_identifier_rules() {
+        let config = TokenizerConfig {
+            identifiers: IdentifierRules::Custom {
+                start: |c| c.is_alphabetic(),
+                continuation: |c| c.is_alphanumeric() || c == '-' || c == '\'',
+            },
+            unexpected_characters: UnexpectedCharacters::Token,
+            ..Default::default()
+        };
+        let tokens =
+            Tokenizer::tokenize_with_config("foo' kebab-case -x 'y", (0, 0).into(), &config)
+                .unwrap();
+        let kinds: Vec<_> = tokens.into_iter().map(|token| token.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Identifier("foo'".into()),
+                TokenKind::Identifier("kebab-case".into()),
+                TokenKind::Symbol("-".into()),
+                TokenKind::Identifier("x".into()),
+                TokenKind::Unknown("'".into()),
+                TokenKind::Identifier("y".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn quoted_identifiers() {
+        let config = TokenizerConfig {
+            identifier_quotes: vec![('`', '`'), ('[', ']'), ('"', '"')],
+            ..Default::default()
+        };
+        let tokens = Tokenizer::tokenize_with_config(
+            "`weird name` [order]]s] \"Col\"\"1\"",
+            (0, 0).into(),
+            &config,
+        )
+        .unwrap();
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens[0].kind, TokenKind::Identifier("weird name".into()));
+        assert_eq!(tokens[0].contents, "weird name");
+        assert_eq!(tokens[0].span, Span::new(0, 12));
+        assert_eq!(tokens[1].kind, TokenKind::Identifier("order]s".into()));
+        assert_eq!(tokens[2].kind, TokenKind::Identifier("Col\"1".into()));
+
+        let error = Tokenizer::tokenize_with_config("[order", (0, 0).into(), &config).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::UnclosedIdentifier);
+        assert_eq!(error.span, Span::new(0, 6));
+    }
+
+    #[test]
+    fn directives() {
+        let config = TokenizerConfig {
+            directives: vec!['#', '%'],
+            ..Default::default()
+        };
+        let tokens = Tokenizer::tokenize_with_config(
+            "#include <stdio.h>\n  %pragma once \nx # y\n# comment",
+            (0, 0).into(),
+            &config,
+        )
+        .unwrap();
+        assert_eq!(tokens.len(), 5);
         assert_eq!(
             tokens[0].kind,
-            TokenKind::Identifier("my_variable".to_string())
+            TokenKind::Directive(Box::new(Directive {
+                name: "include".into(),
+                arguments: "<stdio.h>".into(),
+            }))
         );
-        assert_eq!(tokens[0].contents, "my_variable");
-        assert_eq!(tokens[0].location, (0, 0).into());
+        assert_eq!(tokens[0].contents, "#include <stdio.h>");
+        assert_eq!(tokens[0].span, Span::new(0, 18));
         assert_eq!(
             tokens[1].kind,
-            TokenKind::Identifier("my_variable2".to_string())
+            TokenKind::Directive(Box::new(Directive {
+                name: "pragma".into(),
+                arguments: "once".into(),
+            }))
         );
-        assert_eq!(tokens[1].contents, "my_variable2");
-        assert_eq!(tokens[1].location, (0, 12).into());
+        assert_eq!(tokens[1].contents, "%pragma once");
+        assert_eq!(tokens[2].kind, TokenKind::Identifier("x".into()));
+        assert_eq!(tokens[3].kind, comment(" y"));
+        assert_eq!(tokens[4].kind, comment(" comment"));
     }
 
     #[test]
-    fn multiple_identifiers_with_newline() {
-        let contents = "my_variable \nmy_variable2";
+    fn line_directives() {
+        let config = TokenizerConfig {
+            directives: vec!['#'],
+            line_directive: Some("line".to_string()),
+            ..Default::default()
+        };
+        let tokens = Tokenizer::tokenize_with_config(
+            "a\n#line 42 \"orig.file\"\nb c\n#line 7\nd",
+            Location::new(0, 0, Some("gen.file".into())),
+            &config,
+        )
+        .unwrap();
+        assert_eq!(tokens.len(), 6);
+        let generated = |row, column| Location::new(column, row, Some("gen.file".into()));
+        let original = |row, column| Location::new(column, row, Some("orig.file".into()));
+        assert_eq!(tokens[0].location, generated(0, 0));
+        assert_eq!(tokens[1].location, generated(1, 0));
+        assert_eq!(tokens[2].location, original(41, 0));
+        assert_eq!(tokens[3].location, original(41, 2));
+        assert_eq!(tokens[3].span, Span::new(25, 26));
+        assert_eq!(tokens[4].location, original(42, 0));
+        assert_eq!(tokens[5].location, original(6, 0));
+
+        let error = Tokenizer::tokenize_with_config("#line x", (0, 0).into(), &config).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::InvalidDirective);
+        assert_eq!(error.message, "Invalid line directive");
+    }
+
+    #[test]
+    fn fragments_report_positions_in_their_source() {
+        let source = "let s = `a + 1\nb`;\nlet t = `\"x`;";
+        let config = TokenizerConfig::default();
+
+        let tokens = tokenize_fragment(&source[9..16], (0, 9).into(), 9, &config).unwrap();
+        let positions: Vec<_> = tokens
+            .iter()
+            .map(|token| (token.location.row, token.location.column, token.span))
+            .collect();
+        assert_eq!(
+            positions,
+            [
+                (0, 9, Span::new(9, 10)),
+                (0, 11, Span::new(11, 12)),
+                (0, 13, Span::new(13, 14)),
+                (1, 0, Span::new(15, 16)),
+            ]
+        );
+        assert_eq!(&source[tokens[3].span.start..tokens[3].span.end], "b");
+
+        let error = tokenize_fragment(&source[28..30], (2, 9).into(), 28, &config).unwrap_err();
+        assert_eq!(error.location, (2, 9).into());
+        assert_eq!(error.span, Span::new(28, 30));
+        assert!(error
+            .render(source)
+            .contains("3 | let t = `\"x`;\n  |          ^^\n"));
+    }
+
+    #[test]
+    fn lines_continue_what_earlier_lines_left_open() {
+        let config = TokenizerConfig {
+            identifier_quotes: vec![('`', '`')],
+            ..Default::default()
+        };
+        let lines = ["a = \"one\n", "two\n", "three\" + `my\r\n", "name` # done"];
+        let mut state = LineState::Normal;
+        let mut states = Vec::new();
+        let mut tokens = Vec::new();
+        for (row, line) in lines.iter().enumerate() {
+            let (line_tokens, next) = tokenize_line(line, row, state, &config).unwrap();
+            state = next;
+            states.push(state);
+            tokens.extend(line_tokens);
+        }
+
+        assert_eq!(
+            states,
+            [
+                LineState::InString { quote: '"' },
+                LineState::InString { quote: '"' },
+                LineState::InQuotedIdentifier { close: '`' },
+                LineState::Normal,
+            ]
+        );
+        let kinds: Vec<_> = tokens.iter().map(|token| token.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            [
+                TokenKind::Identifier("a".into()),
+                TokenKind::Symbol("=".into()),
+                TokenKind::StringLiteral("one".into()),
+                TokenKind::StringLiteral("two".into()),
+                TokenKind::StringLiteral("three".into()),
+                TokenKind::Symbol("+".into()),
+                TokenKind::Identifier("my".into()),
+                TokenKind::Identifier("name".into()),
+                comment(" done"),
+            ]
+        );
+        assert_eq!(tokens[4].location, (2, 0).into());
+        assert_eq!(tokens[4].span, Span::new(0, 6));
+        assert_eq!(tokens[6].span, Span::new(9, 12));
+
+        let (_, state) = tokenize_line("b = 1", 4, LineState::Normal, &config).unwrap();
+        assert_eq!(state, LineState::Normal);
+        assert!(tokenize_line("1.2.3", 0, LineState::Normal, &config).is_err());
+    }
+
+    #[test]
+    fn comments_keep_their_raw_text() {
+        let contents = "#  indented  \n## doc\n#";
         let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
-        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[0].kind, comment("  indented  "));
+        assert_eq!(tokens[0].contents, "indented");
+        assert_eq!(tokens[0].to_string(), "#  indented  ");
+        assert_eq!(tokens[1].kind, comment("# doc"));
+
+        let config = TokenizerConfig {
+            doc_comments: true,
+            ..Default::default()
+        };
+        let tokens = Tokenizer::tokenize_with_config(contents, (0, 0).into(), &config).unwrap();
+        let TokenKind::Comment(doc) = &tokens[1].kind else {
+            panic!("expected a comment, got {:?}", tokens[1].kind);
+        };
+        assert_eq!(**doc, Comment::new(CommentStyle::Doc, " doc"));
+        assert_eq!(doc.text, "doc");
+        assert_eq!(tokens[1].to_string(), "## doc");
+        assert_eq!(tokens[2].kind, comment(""));
+    }
+
+    #[test]
+    fn strings_keep_their_raw_text_and_decoded_value() {
+        let contents = r#""a\tb\n\"c\" \\" "\q\\""#;
+        let result = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
+        assert_eq!(
+            result[0].kind,
+            TokenKind::StringLiteral("a\tb\n\"c\" \\".into())
+        );
+        assert_eq!(result[0].contents, r#""a\tb\n\"c\" \\""#);
+        assert_eq!(result[0].to_string(), result[0].contents.to_string());
+        assert_eq!(result[1].kind, TokenKind::StringLiteral("\\q\\".into()));
+        assert_eq!(result[1].contents, r#""\q\\""#);
+    }
+
+    #[test]
+    fn newlines_in_strings_error_when_forbidden() {
+        let source = "x = \"ab\ny = 1\"";
+        let tokens = Tokenizer::tokenize(source, (0, 0).into()).unwrap();
+        assert_eq!(tokens[2].kind, TokenKind::StringLiteral("ab\ny = 1".into()));
+
+        let config = TokenizerConfig {
+            string_newlines: StringNewlines::Error,
+            ..Default::default()
+        };
+        let expected = Err(Error {
+            kind: ErrorKind::NewlineInString,
+            message: "Newline in string".to_string(),
+            location: (0, 4).into(),
+            span: Span::new(4, 7),
+            source_line: Some("x = \"ab".into()),
+            mismatch: None,
+            label: None,
+            args: Box::default(),
+        });
+        assert_eq!(
+            Tokenizer::tokenize_with_config(source, (0, 0).into(), &config),
+            expected
+        );
+        let escaped = Tokenizer::tokenize_with_config("x = \"ab\\\n\"", (0, 0).into(), &config);
+        assert_eq!(escaped.unwrap_err().kind, ErrorKind::NewlineInString);
+        let line = tokenize_line("x = \"ab", 0, LineState::Normal, &config);
+        assert_eq!(line.unwrap_err().kind, ErrorKind::NewlineInString);
+    }
+
+    #[test]
+    fn unclosed_strings_recover_at_the_end_of_their_line() {
+        let source = "x = \"abc\ny = 1";
+        let (tokens, errors) = tokenize_recovering(source, (0, 0).into(), &Default::default());
+        let kinds: Vec<_> = tokens.iter().map(|token| token.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            [
+                TokenKind::Identifier("x".into()),
+                TokenKind::Symbol("=".into()),
+                TokenKind::Unknown("\"abc".into()),
+                TokenKind::Identifier("y".into()),
+                TokenKind::Symbol("=".into()),
+                TokenKind::IntegerLiteral(1),
+            ]
+        );
+        assert_eq!(tokens[2].span, Span::new(4, 8));
+        assert_eq!(tokens[3].location, (1, 0).into());
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind, ErrorKind::UnclosedString);
+        assert_eq!(errors[0].location, (0, 8).into());
+        assert_eq!(
+            errors[0].label.as_deref(),
+            Some(&Label {
+                location: (0, 4).into(),
+                span: Span::new(4, 5),
+                message: "string starts here".to_string(),
+            })
+        );
+        assert_eq!(
+            errors[0].render(source),
+            "error[T0001]: Unclosed string\n --> 1:9\n  |\n1 | x = \"abc\n  |         ^\n  |     - string starts here\n"
+        );
+
+        let config = TokenizerConfig {
+            string_newlines: StringNewlines::Error,
+            ..Default::default()
+        };
+        let (tokens, errors) = tokenize_recovering("\"a\nb \"", (0, 0).into(), &config);
+        assert_eq!(tokens[0].kind, TokenKind::Unknown("\"a".into()));
+        assert_eq!(tokens[1].kind, TokenKind::Identifier("b".into()));
+        assert_eq!(tokens[2].kind, TokenKind::Unknown("\"".into()));
+        assert_eq!(errors[0].kind, ErrorKind::NewlineInString);
+        assert_eq!(errors[1].kind, ErrorKind::UnclosedString);
+        assert_eq!(errors[1].location, (1, 3).into());
+    }
+
+    #[test]
+    fn block_comments_can_nest() {
+        let source = "a #| one #| two |# three |# b";
+        let flat = TokenizerConfig {
+            block_comments: BlockComments::Flat,
+            ..Default::default()
+        };
+        let tokens = Tokenizer::tokenize_with_config(source, (0, 0).into(), &flat).unwrap();
+        assert_eq!(
+            tokens[1].kind,
+            TokenKind::Comment(Box::new(Comment::new(CommentStyle::Block, " one #| two ")))
+        );
+        assert_eq!(tokens[2].kind, TokenKind::Identifier("three".into()));
+
+        let nested = TokenizerConfig {
+            block_comments: BlockComments::Nested,
+            ..Default::default()
+        };
+        let tokens = Tokenizer::tokenize_with_config(source, (0, 0).into(), &nested).unwrap();
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens[1].contents, "one #| two |# three");
+        assert_eq!(tokens[1].span, Span::new(2, 27));
+        assert_eq!(tokens[1].to_string(), &source[2..27]);
+        assert_eq!(tokens[2].kind, TokenKind::Identifier("b".into()));
+
+        let tokens = Tokenizer::tokenize(source, (0, 0).into()).unwrap();
+        assert_eq!(tokens[1].kind, comment("| one #| two |# three |# b"));
+    }
+
+    #[test]
+    fn lines_continue_block_comments() {
+        let states_and_kinds = |lines: &[&str], block_comments| {
+            let config = TokenizerConfig {
+                block_comments,
+                ..Default::default()
+            };
+            let mut state = LineState::Normal;
+            let mut states = Vec::new();
+            let mut kinds = Vec::new();
+            for (row, line) in lines.iter().enumerate() {
+                let (tokens, next) = tokenize_line(line, row, state, &config).unwrap();
+                state = next;
+                states.push(state);
+                kinds.extend(tokens.into_iter().map(|token| token.kind));
+            }
+            (states, kinds)
+        };
+        let block =
+            |raw: &str| TokenKind::Comment(Box::new(Comment::new(CommentStyle::Block, raw)));
+
+        let (states, kinds) = states_and_kinds(&["a #| one #|\n", "two |# b"], BlockComments::Flat);
         assert_eq!(
-            tokens[0].kind,
-            TokenKind::Identifier("my_variable".to_string())
+            states,
+            [LineState::InBlockComment { depth: 1 }, LineState::Normal]
         );
-        assert_eq!(tokens[0].contents, "my_variable");
-        assert_eq!(tokens[0].location, (0, 0).into());
         assert_eq!(
-            tokens[1].kind,
-            TokenKind::Identifier("my_variable2".to_string())
+            kinds,
+            [
+                TokenKind::Identifier("a".into()),
+                block(" one #|"),
+                block("two "),
+                TokenKind::Identifier("b".into()),
+            ]
         );
-        assert_eq!(tokens[1].contents, "my_variable2");
-        assert_eq!(tokens[1].location, (1, 0).into());
-    }
 
-    #[test]
-    fn multiple_identifiers_with_comment() {
-        let contents = "my_variable \n# This is a comment\nmy_variable2";
-        let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
-        assert_eq!(tokens.len(), 3);
-        assert_eq!(
-            tokens[0].kind,
-            TokenKind::Identifier("my_variable".to_string())
+        let (states, kinds) = states_and_kinds(
+            &["a #| one #| two\n", "three |# four\n", "five |# b"],
+            BlockComments::Nested,
         );
-        assert_eq!(tokens[0].contents, "my_variable");
-        assert_eq!(tokens[0].location, (0, 0).into());
         assert_eq!(
-            tokens[1].kind,
-            TokenKind::Comment("This is a comment".to_string())
+            states,
+            [
+                LineState::InBlockComment { depth: 2 },
+                LineState::InBlockComment { depth: 1 },
+                LineState::Normal,
+            ]
         );
-        assert_eq!(tokens[1].contents, "This is a comment");
-        assert_eq!(tokens[1].location, (1, 0).into());
         assert_eq!(
-            tokens[2].kind,
-            TokenKind::Identifier("my_variable2".to_string())
+            kinds,
+            [
+                TokenKind::Identifier("a".into()),
+                block(" one #| two"),
+                block("three |# four"),
+                block("five "),
+                TokenKind::Identifier("b".into()),
+            ]
         );
-        assert_eq!(tokens[2].contents, "my_variable2");
-        assert_eq!(tokens[2].location, (2, 0).into());
     }
 
     #[test]
-    fn identifier_splits_on_comment() {
-        let contents = "my_variable# This is a comment\nmy_variable2";
-        let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
-        assert_eq!(tokens.len(), 3);
+    fn unclosed_block_comments_follow_the_policy() {
+        let source = "a\n  #| one #| two |#\nb";
+        let mut config = TokenizerConfig {
+            block_comments: BlockComments::Nested,
+            ..Default::default()
+        };
+        let error = Error {
+            kind: ErrorKind::UnclosedComment,
+            message: "Unclosed block comment".to_string(),
+            location: (1, 2).into(),
+            span: Span::new(4, 6),
+            source_line: Some("  #| one #| two |#".into()),
+            mismatch: None,
+            label: None,
+            args: Box::default(),
+        };
         assert_eq!(
-            tokens[0].kind,
-            TokenKind::Identifier("my_variable".to_string())
+            Tokenizer::tokenize_with_config(source, (0, 0).into(), &config),
+            Err(error.clone())
         );
-        assert_eq!(tokens[0].contents, "my_variable");
-        assert_eq!(tokens[0].location, (0, 0).into());
+
+        config.unclosed_block_comments = UnclosedBlockComments::Warn;
+        let (tokens, warnings) =
+            Tokenizer::tokenize_with_warnings(source, (0, 0).into(), &config).unwrap();
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[1].contents, "one #| two |#\nb");
+        assert_eq!(warnings[0].location, (1, 2).into());
+
+        config.unclosed_block_comments = UnclosedBlockComments::Recover;
+        let (tokens, errors) = tokenize_recovering(source, (0, 0).into(), &config);
         assert_eq!(
             tokens[1].kind,
-            TokenKind::Comment("This is a comment".to_string())
+            TokenKind::Unknown("#| one #| two |#\nb".into())
         );
-        assert_eq!(tokens[1].contents, "This is a comment");
-        assert_eq!(tokens[1].location, (0, 11).into());
+        assert_eq!(errors, [error]);
+    }
+
+    #[test]
+    fn block_comments_nested_too_deep_are_errors() {
+        let config = TokenizerConfig {
+            block_comments: BlockComments::Nested,
+            max_depth: Some(2),
+            ..Default::default()
+        };
+        let source = "#| a #| b |# |#\n#| a #| b #| c |# |# |#";
+        let error = Tokenizer::tokenize_with_config(source, (0, 0).into(), &config).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::TooDeep);
+        assert_eq!(error.message, "Nested more than 2 deep");
+        assert_eq!(error.location, (1, 10).into());
+        assert_eq!(error.span, Span::new(26, 28));
         assert_eq!(
-            tokens[2].kind,
-            TokenKind::Identifier("my_variable2".to_string())
+            error.source_line.as_deref(),
+            Some("#| a #| b #| c |# |# |#")
         );
-        assert_eq!(tokens[2].contents, "my_variable2");
-        assert_eq!(tokens[2].location, (1, 0).into());
     }
 
     #[test]
-    fn string_literal_unclosed_returns_error() {
-        let contents = r#""This is a string"#;
+    fn strict_and_lenient_configs_set_a_posture() {
+        let source = "x\u{00A0}= \u{201C}a\u{201D} \u{200B}";
+        assert!(TokenizerConfig::strict().validate().is_ok());
+        let error =
+            Tokenizer::tokenize_with_config(source, (0, 0).into(), &TokenizerConfig::strict())
+                .unwrap_err();
+        assert_eq!(error.kind, ErrorKind::UnusualWhitespace);
+
+        assert!(TokenizerConfig::lenient().validate().is_ok());
+        let (tokens, warnings) =
+            Tokenizer::tokenize_with_warnings(source, (0, 0).into(), &TokenizerConfig::lenient())
+                .unwrap();
+        let kinds: Vec<_> = tokens.iter().map(|token| token.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            [
+                TokenKind::Identifier("x".into()),
+                TokenKind::Symbol("=".into()),
+                TokenKind::StringLiteral("a".into()),
+                TokenKind::Unknown("\u{200B}".into()),
+            ]
+        );
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn unicode_digits_are_mapped_when_enabled() {
+        let config = TokenizerConfig {
+            digits: Digits::Unicode,
+            ..Default::default()
+        };
+        let tokens =
+            Tokenizer::tokenize_with_config("\u{0661}\u{0662}.\u{0663}", (0, 0).into(), &config)
+                .unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].kind, TokenKind::FloatLiteral(12.3));
+        assert_eq!(tokens[0].contents, "\u{0661}\u{0662}.\u{0663}");
+    }
+
+    #[test]
+    #[cfg(not(feature = "bigint"))]
+    fn integer_overflow_returns_error() {
+        let contents = "1000000000000000000000000000000000000000000";
         let result = Tokenizer::tokenize(contents, (0, 0).into());
         let expected = Err(Error {
-            message: "Unclosed string".to_string(),
+            kind: ErrorKind::InvalidNumber,
+            message: format!("Invalid integer literal '{}'", contents),
             location: (0, 0).into(),
+            span: Span::new(0, 43),
+            source_line: Some(contents.into()),
+            mismatch: None,
+            label: None,
+            args: Box::new([
+                ("type", "integer".to_string()),
+                ("literal", contents.to_string()),
+            ]),
         });
-
         assert_eq!(expected, result);
     }
 
     #[test]
-    fn string_literal() {
-        let contents = r#""This is a string""#;
-        let result = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
-        assert_eq!(result.len(), 1);
+    #[cfg(feature = "bigint")]
+    fn integer_overflow_reads_big_integer() {
+        let contents = "-1000000000000000000000000000000000000000000 5";
+        let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
+        let expected: num_bigint::BigInt = contents[..44].parse().unwrap();
+        assert_eq!(tokens[0].kind, TokenKind::BigIntegerLiteral(expected));
         assert_eq!(
-            result[0].kind,
-            TokenKind::StringLiteral("This is a string".to_string())
+            tokens[0].kind.to_string(),
+            format!("int '{}'", &contents[..44])
         );
-        assert_eq!(result[0].contents, "This is a string");
-        assert_eq!(result[0].location, (0, 0).into());
+        assert_eq!(tokens[1].kind, TokenKind::IntegerLiteral(5));
+        assert!(tokens[0].kind < tokens[1].kind);
     }
 
     #[test]
-    fn string_literal_with_escaping() {
-        let contents = r#""This is a string with \"escaping\" characters""#;
-        let result = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
-        assert_eq!(result.len(), 1);
-        assert_eq!(
-            result[0].kind,
-            TokenKind::StringLiteral("This is a string with \"escaping\" characters".to_string())
-        );
+    fn additional_whitespace_separates_tokens() {
+        let config = TokenizerConfig {
+            additional_whitespace: vec![','],
+            ..Default::default()
+        };
+        let tokens = Tokenizer::tokenize_with_config("[1, 2,3]", (0, 0).into(), &config).unwrap();
+        let kinds: Vec<TokenKind> = tokens.into_iter().map(|t| t.kind).collect();
         assert_eq!(
-            result[0].contents,
-            "This is a string with \"escaping\" characters"
+            kinds,
+            vec![
+                TokenKind::Symbol("[".into()),
+                TokenKind::IntegerLiteral(1),
+                TokenKind::IntegerLiteral(2),
+                TokenKind::IntegerLiteral(3),
+                TokenKind::Symbol("]".into()),
+            ]
         );
-        assert_eq!(result[0].location, (0, 0).into());
     }
 
     #[test]
-    fn string_literal_with_escaped_comment() {
-        let contents = r#""This is a string with # escaped comment""#;
-        let result = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
-        assert_eq!(result.len(), 1);
+    fn ascii_whitespace_does_not_split_on_nbsp() {
+        let config = TokenizerConfig {
+            whitespace: Whitespace::Ascii,
+            ..Default::default()
+        };
+        let tokens = Tokenizer::tokenize_with_config("a\u{a0}b c", (0, 0).into(), &config).unwrap();
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[0].kind, TokenKind::Identifier("a\u{a0}b".into()));
+    }
+
+    #[test]
+    fn unusual_whitespace_returns_error() {
+        let config = TokenizerConfig {
+            unusual_whitespace: UnusualWhitespace::Error,
+            ..Default::default()
+        };
+        let result = Tokenizer::tokenize_with_config("a \n\u{a0}b", (0, 0).into(), &config);
+        let expected = Err(Error {
+            kind: ErrorKind::UnusualWhitespace,
+            message: "Unusual whitespace character U+00A0".to_string(),
+            location: (1, 0).into(),
+            span: Span::new(3, 5),
+            source_line: Some("\u{a0}b".into()),
+            mismatch: None,
+            label: None,
+            args: Box::new([("code", "U+00A0".to_string())]),
+        });
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn normalizes_identifiers() {
+        let config = TokenizerConfig {
+            normalization: Normalization::Identifiers,
+            ..Default::default()
+        };
+        let tokens =
+            Tokenizer::tokenize_with_config("caf\u{e9} cafe\u{301}", (0, 0).into(), &config)
+                .unwrap();
+        assert_eq!(tokens[0].kind, tokens[1].kind);
+        assert_eq!(tokens[1].contents, "cafe\u{301}");
+        assert_eq!(tokens[1].location, (0, 5).into());
+    }
+
+    #[test]
+    fn normalizes_input() {
+        let config = TokenizerConfig {
+            normalization: Normalization::Input,
+            ..Default::default()
+        };
+        let tokens =
+            Tokenizer::tokenize_with_config("\"cafe\u{301}\" x", (0, 0).into(), &config).unwrap();
+        assert_eq!(tokens[0].kind, TokenKind::StringLiteral("caf\u{e9}".into()));
+        assert_eq!(tokens[1].location, (0, 7).into());
+    }
+
+    #[test]
+    fn normalized_input_spans_point_into_the_source() {
+        let config = TokenizerConfig {
+            normalization: Normalization::Input,
+            ..Default::default()
+        };
+        let source = "e\u{301}e\u{301} x\r\n\u{1100}\u{1161}\u{11a8} \"\u{e9}\"";
+        let tokens = Tokenizer::tokenize_with_config(source, (0, 0).into(), &config).unwrap();
+        let texts: Vec<_> = tokens.iter().map(|token| token.text(source)).collect();
         assert_eq!(
-            result[0].kind,
-            TokenKind::StringLiteral("This is a string with # escaped comment".to_string())
+            texts,
+            [
+                "e\u{301}e\u{301}",
+                "x",
+                "\u{1100}\u{1161}\u{11a8}",
+                "\"\u{e9}\""
+            ]
         );
+        assert_eq!(tokens[0].contents, "\u{e9}\u{e9}");
+        assert_eq!(tokens[2].contents, "\u{ac01}");
+        assert_eq!(tokens[2].end_location(source), (1, 3).into());
+    }
+
+    #[test]
+    fn bidi_controls_return_error() {
+        let config = TokenizerConfig {
+            bidi_controls: BidiControls::Error,
+            ..Default::default()
+        };
+        let contents = "x = \"user\u{202E} \u{2066}// admin\u{2069} \u{2066}\"";
+        let result = Tokenizer::tokenize_with_config(contents, (0, 0).into(), &config);
+        let expected = Err(Error {
+            kind: ErrorKind::BidiControl,
+            message: "Bidirectional control character U+202E".to_string(),
+            location: (0, 9).into(),
+            span: Span::new(9, 12),
+            source_line: Some(contents.into()),
+            mismatch: None,
+            label: None,
+            args: Box::new([("code", "U+202E".to_string())]),
+        });
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn bidi_controls_warn() {
+        let config = TokenizerConfig {
+            bidi_controls: BidiControls::Warn,
+            ..Default::default()
+        };
+        let contents = "# \u{202E}comment\nx";
+        let (tokens, warnings) =
+            Tokenizer::tokenize_with_warnings(contents, (0, 0).into(), &config).unwrap();
+        assert_eq!(tokens.len(), 2);
+        let expected = vec![Warning {
+            message: "Bidirectional control character U+202E".to_string(),
+            location: (0, 2).into(),
+        }];
+        assert_eq!(expected, warnings);
+    }
+
+    #[test]
+    fn invisible_characters_policy() {
+        let contents = "foo\u{200B}bar\u{7}";
+
+        let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
+        assert_eq!(tokens.len(), 1);
+
+        let mut config = TokenizerConfig {
+            invisible_characters: InvisibleCharacters::Error,
+            ..Default::default()
+        };
+        let result = Tokenizer::tokenize_with_config(contents, (0, 0).into(), &config);
+        let expected = Err(Error {
+            kind: ErrorKind::InvisibleCharacter,
+            message: "Invisible character U+200B".to_string(),
+            location: (0, 3).into(),
+            span: Span::new(3, 6),
+            source_line: Some(contents.into()),
+            mismatch: None,
+            label: None,
+            args: Box::new([("code", "U+200B".to_string())]),
+        });
+        assert_eq!(expected, result);
+
+        config.invisible_characters = InvisibleCharacters::Skip;
+        let tokens = Tokenizer::tokenize_with_config(contents, (0, 0).into(), &config).unwrap();
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[1].kind, TokenKind::Identifier("bar".into()));
+        assert_eq!(tokens[1].location, (0, 4).into());
+
+        config.invisible_characters = InvisibleCharacters::Token;
+        let tokens = Tokenizer::tokenize_with_config(contents, (0, 0).into(), &config).unwrap();
+        assert_eq!(tokens.len(), 4);
+        assert_eq!(tokens[1].kind, TokenKind::Unknown("\u{200B}".into()));
+        assert_eq!(tokens[3].kind, TokenKind::Unknown("\u{7}".into()));
+        assert_eq!(tokens[3].location, (0, 7).into());
+    }
+
+    #[test]
+    fn smart_quotes_delimit_strings() {
+        let config = TokenizerConfig {
+            smart_quotes: SmartQuotes::Delimit,
+            ..Default::default()
+        };
+        let contents = "say\u{201C}hello world\u{201D} x";
+        let tokens = Tokenizer::tokenize_with_config(contents, (0, 0).into(), &config).unwrap();
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens[0].kind, TokenKind::Identifier("say".into()));
         assert_eq!(
-            result[0].contents,
-            "This is a string with # escaped comment"
+            tokens[1].kind,
+            TokenKind::StringLiteral("hello world".into())
         );
-        assert_eq!(result[0].location, (0, 0).into());
+        assert_eq!(tokens[1].location, (0, 3).into());
+        assert_eq!(tokens[2].location, (0, 17).into());
     }
 
     #[test]
-    fn read_single_symbol() {
-        let symbols = vec![
-            '+', '-', '*', '/', '=', '>', '<', '!', '?', '.', ',', ';', ':', '(', ')', '[', ']',
-            '{', '}', '&', '|', '^', '%', '~',
+    fn smart_quotes_return_error() {
+        let config = TokenizerConfig {
+            smart_quotes: SmartQuotes::Error,
+            ..Default::default()
+        };
+        let result =
+            Tokenizer::tokenize_with_config("x = \u{201C}a\u{201D}", (0, 0).into(), &config);
+        let expected = Err(Error {
+            kind: ErrorKind::SmartQuote,
+            message: "Unexpected smart quote '\u{201C}', use '\"' instead".to_string(),
+            location: (0, 4).into(),
+            span: Span::new(4, 7),
+            source_line: Some("x = \u{201C}a\u{201D}".into()),
+            mismatch: None,
+            label: None,
+            args: Box::new([("char", "\u{201C}".to_string())]),
+        });
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn warnings_do_not_fail_tokenization() {
+        let config = TokenizerConfig {
+            unusual_whitespace: UnusualWhitespace::Warn,
+            deprecated: vec![Deprecated {
+                lexeme: "var".to_string(),
+                message: "'var' is deprecated, use 'let' instead".to_string(),
+            }],
+            ..Default::default()
+        };
+        let contents = "a\u{a0}var b";
+        let (tokens, warnings) =
+            Tokenizer::tokenize_with_warnings(contents, (0, 0).into(), &config).unwrap();
+        assert_eq!(tokens.len(), 3);
+        let expected = vec![
+            Warning {
+                message: "Unusual whitespace character U+00A0".to_string(),
+                location: (0, 1).into(),
+            },
+            Warning {
+                message: "'var' is deprecated, use 'let' instead".to_string(),
+                location: (0, 2).into(),
+            },
         ];
-        for symbol in symbols {
-            let contents = format!("{}", symbol);
-            let tokens = Tokenizer::tokenize(&contents, (0, 0).into()).unwrap();
-            assert_eq!(tokens.len(), 1);
-            assert_eq!(tokens[0].kind, TokenKind::Symbol(symbol.to_string()));
-            assert_eq!(tokens[0].contents, symbol.to_string());
-            assert_eq!(tokens[0].location, (0, 0).into());
-        }
+        assert_eq!(expected, warnings);
     }
 
     #[test]
-    fn read_two_char_symbols() {
-        let symbols = vec!["==", "!=", ">=", "<=", "->", "=>", "*=", "-=", "+=", "/="];
-        for symbol in symbols {
-            let contents = format!("{}", symbol);
-            let tokens = Tokenizer::tokenize(&contents, (0, 0).into()).unwrap();
-            assert_eq!(tokens.len(), 1);
-            assert_eq!(tokens[0].kind, TokenKind::Symbol(symbol.to_string()));
-            assert_eq!(tokens[0].contents, symbol);
-            assert_eq!(tokens[0].location, (0, 0).into());
-        }
+    fn partial_tokens_are_returned_with_error() {
+        let contents = "let x = \"abc";
+        let result = Tokenizer::tokenize_partial(contents, (0, 0).into(), &Default::default());
+        let partial = result.unwrap_err();
+        assert_eq!(partial.error.kind, ErrorKind::UnclosedString);
+        assert_eq!(partial.tokens.len(), 3);
+        assert_eq!(partial.tokens[2].kind, TokenKind::Symbol("=".into()));
+
+        let result = Tokenizer::tokenize_partial("x", (0, 0).into(), &Default::default());
+        assert_eq!(result.unwrap().len(), 1);
     }
 
     #[test]
-    fn read_identifier_with_symbol() {
-        let symbols = vec![
-            ".", ",", ";", ":", "(", ")", "[", "]", "{", "}", "->", "==", "!=", ">=", "<=", "->",
-            "=>", "*=", "-=", "+=", "/=", "=>",
-        ];
+    fn running_out_of_fuel_returns_error() {
+        let config = TokenizerConfig {
+            fuel: Some(10),
+            ..Default::default()
+        };
+        let result = Tokenizer::tokenize_partial("abc def \"ghijkl\"", (0, 0).into(), &config);
+        let partial = result.unwrap_err();
+        assert_eq!(
+            partial.error,
+            Error {
+                kind: ErrorKind::OutOfFuel,
+                message: "Ran out of fuel".to_string(),
+                location: (0, 10).into(),
+                span: Span::new(10, 10),
+                source_line: Some("abc def \"ghijkl\"".into()),
+                mismatch: None,
+                label: None,
+                args: Box::default(),
+            }
+        );
+        assert_eq!(partial.tokens.len(), 2);
 
-        for symbol in symbols {
-            let contents = format!("my_variable{}my_variable2", symbol);
-            let tokens = Tokenizer::tokenize(&contents, (0, 0).into()).unwrap();
-            assert_eq!(tokens.len(), 3);
-            assert_eq!(
-                tokens[0].kind,
-                TokenKind::Identifier("my_variable".to_string())
-            );
-            assert_eq!(tokens[0].contents, "my_variable");
-            assert_eq!(tokens[0].location, (0, 0).into());
-            assert_eq!(tokens[1].kind, TokenKind::Symbol(symbol.to_string()));
-            assert_eq!(tokens[1].contents, symbol.to_string());
-            assert_eq!(tokens[1].location, (0, 11).into());
-            assert_eq!(
-                tokens[2].kind,
-                TokenKind::Identifier("my_variable2".to_string())
-            );
-            assert_eq!(tokens[2].contents, "my_variable2");
-            assert_eq!(tokens[2].location, (0, 11 + symbol.len()).into());
-        }
+        let result = Tokenizer::tokenize_with_config("abc def", (0, 0).into(), &config);
+        assert_eq!(result.unwrap().len(), 2);
     }
 
     #[test]
-    fn read_integer() {
-        let contents = "12345";
-        let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
+    fn reusable_tokenizer() {
+        let mut tokenizer = Tokenizer::with_config(TokenizerConfig::default());
+
+        tokenizer.reset("a b\r\nc", (0, 0).into());
+        let tokens = tokenizer.tokens().unwrap();
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens[2].location, (1, 0).into());
+
+        tokenizer.reset("\"unclosed", (0, 0).into());
+        assert_eq!(
+            tokenizer.tokens().unwrap_err().kind,
+            ErrorKind::UnclosedString
+        );
+
+        tokenizer.reset("d", (4, 2).into());
+        let tokens = tokenizer.tokens().unwrap();
         assert_eq!(tokens.len(), 1);
-        assert_eq!(tokens[0].kind, TokenKind::IntegerLiteral(12345));
-        assert_eq!(tokens[0].contents, "12345");
-        assert_eq!(tokens[0].location, (0, 0).into());
+        assert_eq!(tokens[0].kind, TokenKind::Identifier("d".into()));
+        assert_eq!(tokens[0].location, (4, 2).into());
     }
 
     #[test]
-    fn read_float() {
-        let contents = "12345.6789";
-        let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
-        assert_eq!(tokens.len(), 1);
-        assert_eq!(tokens[0].kind, TokenKind::FloatLiteral(12345.6789));
-        assert_eq!(tokens[0].contents, "12345.6789");
-        assert_eq!(tokens[0].location, (0, 0).into());
+    fn hooks_see_tokens_and_errors_and_can_stop() {
+        use std::sync::{Arc, Mutex};
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let mut tokenizer = Tokenizer::with_config(TokenizerConfig::default());
+        let hook_seen = seen.clone();
+        tokenizer.on_token(move |token, location| {
+            let mut seen = hook_seen.lock().unwrap();
+            seen.push((token.contents.to_string(), location.clone()));
+            match seen.len() {
+                3 => ControlFlow::Break(()),
+                _ => ControlFlow::Continue(()),
+            }
+        });
+        let hook_errors = errors.clone();
+        tokenizer.on_error(move |error, _| hook_errors.lock().unwrap().push(error.kind));
+
+        tokenizer.reset("a b\nc d", (0, 0).into());
+        let error = tokenizer.tokens().unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Stopped);
+        assert_eq!(error.location, (1, 1).into());
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [
+                ("a".to_string(), (0, 1).into()),
+                ("b".to_string(), (0, 3).into()),
+                ("c".to_string(), (1, 1).into()),
+            ]
+        );
+        assert!(errors.lock().unwrap().is_empty());
+
+        seen.lock().unwrap().clear();
+        tokenizer.reset("x \"open", (0, 0).into());
+        assert_eq!(tokenizer.next_token().unwrap().unwrap().contents, "x");
+        assert_eq!(
+            tokenizer.next_token().unwrap().unwrap_err().kind,
+            ErrorKind::UnclosedString
+        );
+        assert_eq!(seen.lock().unwrap().len(), 1);
+        assert_eq!(*errors.lock().unwrap(), [ErrorKind::UnclosedString]);
     }
 
+    #[cfg(feature = "tracing")]
     #[test]
-    fn read_float_with_negative() {
-        let contents = "-12345.6789";
+    fn runs_and_recoveries_are_traced() {
+        use std::sync::{Arc, Mutex};
+
+        use tracing::{
+            field::{Field, Visit},
+            span, Event, Metadata, Subscriber,
+        };
+
+        /// Collects the fields of each event as `name=value` text.
+        struct Events(Arc<Mutex<Vec<String>>>);
+        struct Fields(String);
+        impl Visit for Fields {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                self.0 += &format!("{}={:?} ", field.name(), value);
+            }
+        }
+        impl Subscriber for Events {
+            fn enabled(&self, _: &Metadata) -> bool {
+                true
+            }
+            fn new_span(&self, span: &span::Attributes) -> span::Id {
+                let mut fields = Fields(format!("span {} ", span.metadata().name()));
+                span.record(&mut fields);
+                self.0.lock().unwrap().push(fields.0);
+                span::Id::from_u64(1)
+            }
+            fn record(&self, _: &span::Id, _: &span::Record) {}
+            fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+            fn event(&self, event: &Event) {
+                let mut fields = Fields(String::new());
+                event.record(&mut fields);
+                self.0.lock().unwrap().push(fields.0);
+            }
+            fn enter(&self, _: &span::Id) {}
+            fn exit(&self, _: &span::Id) {}
+        }
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        tracing::subscriber::with_default(Events(events.clone()), || {
+            Tokenizer::tokenize("a = 1", (0, 0).into()).unwrap();
+            let config = TokenizerConfig::default();
+            Tokenizer::tokenize_recovering("x \"open\ny", (0, 0).into(), &config);
+        });
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 5);
+        assert_eq!(events[0], "span tokenize path=None bytes=5 ");
+        assert!(events[1].starts_with("message=tokenized tokens=3 elapsed="));
+        assert_eq!(events[2], "span tokenize path=None bytes=9 ");
+        assert_eq!(
+            events[3],
+            "message=read the rest of the line as an unknown token \
+             code=\"T0001\" row=0 column=2 "
+        );
+        assert!(events[4].starts_with("message=tokenized tokens=3 elapsed="));
+    }
+
+    #[test]
+    fn tokens_have_spans_in_original_source() {
+        let contents = "ab \"\u{e9}\"\r\n  # hi\r\n12";
         let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
-        assert_eq!(tokens.len(), 1);
-        assert_eq!(tokens[0].kind, TokenKind::FloatLiteral(-12345.6789));
-        assert_eq!(tokens[0].contents, "-12345.6789");
-        assert_eq!(tokens[0].location, (0, 0).into());
+        let spans: Vec<Span> = tokens.iter().map(|t| t.span).collect();
+        assert_eq!(
+            spans,
+            vec![
+                Span::new(0, 2),
+                Span::new(3, 7),
+                Span::new(11, 15),
+                Span::new(17, 19)
+            ]
+        );
+        assert_eq!(&contents[3..7], "\"\u{e9}\"");
+        assert_eq!(&contents[17..19], "12");
+        assert_eq!(tokens[1].text(contents), "\"\u{e9}\"");
+        assert_eq!(tokens[2].text(contents), "# hi");
     }
 
     #[test]
-    fn read_float_with_multiple_decimals() {
-        let contents = "-12345.6789.12345";
-        let tokens = Tokenizer::tokenize(contents, (0, 0).into());
-        assert!(tokens.is_err());
+    fn multiline_string_moves_location() {
+        let contents = "\"a\n\u{e9}b\" c # d\ne";
+        let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
+        let locations: Vec<Location> = tokens.iter().map(|t| t.location.clone()).collect();
         assert_eq!(
-            tokens.err().unwrap(),
-            Error {
-                message: "Float literal cannot have multiple decimal points".to_string(),
-                location: (0, 11).into(),
-            }
+            locations,
+            vec![(0, 0).into(), (1, 4).into(), (1, 6).into(), (2, 0).into()]
+        );
+        assert_eq!(
+            tokens[0].kind,
+            TokenKind::StringLiteral("a\n\u{e9}b".into())
         );
+        assert_eq!(tokens[3].span, Span::new(14, 15));
     }
 
     #[test]
-    fn read_integer_with_negative() {
-        let contents = "-12345";
+    fn non_ascii_between_ascii_tokens() {
+        let contents = "\u{e9}t\u{e9} -1 \u{65e5}\u{672c}>=.5";
         let tokens = Tokenizer::tokenize(contents, (0, 0).into()).unwrap();
-        assert_eq!(tokens.len(), 1);
-        assert_eq!(tokens[0].kind, TokenKind::IntegerLiteral(-12345));
-        assert_eq!(tokens[0].contents, "-12345");
-        assert_eq!(tokens[0].location, (0, 0).into());
+        let kinds: Vec<TokenKind> = tokens.into_iter().map(|t| t.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Identifier("\u{e9}t\u{e9}".into()),
+                TokenKind::IntegerLiteral(-1),
+                TokenKind::Identifier("\u{65e5}\u{672c}".into()),
+                TokenKind::Symbol(">=".into()),
+                TokenKind::FloatLiteral(0.5),
+            ]
+        );
     }
 
     #[test]
     fn is_numeric() {
-        assert_eq!('0'.is_numeric(), true);
-        assert_eq!('1'.is_numeric(), true);
-        assert_eq!('2'.is_numeric(), true);
-        assert_eq!('3'.is_numeric(), true);
-        assert_eq!('4'.is_numeric(), true);
-        assert_eq!('5'.is_numeric(), true);
-        assert_eq!('6'.is_numeric(), true);
-        assert_eq!('7'.is_numeric(), true);
-        assert_eq!('8'.is_numeric(), true);
-        assert_eq!('9'.is_numeric(), true);
-        assert_eq!('0'.is_numeric(), true);
+        assert!('0'.is_numeric());
+        assert!('1'.is_numeric());
+        assert!('2'.is_numeric());
+        assert!('3'.is_numeric());
+        assert!('4'.is_numeric());
+        assert!('5'.is_numeric());
+        assert!('6'.is_numeric());
+        assert!('7'.is_numeric());
+        assert!('8'.is_numeric());
+        assert!('9'.is_numeric());
+        assert!('0'.is_numeric());
     }
 }
@@ -0,0 +1,120 @@
+use crate::token::{Token, TokenKind};
+
+/// Which token a comment is attached to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum CommentAssociation {
+    /// A comment on the same line as the end of the token before it trails that token, as
+    /// in `x = 1 # one`, and any other comment leads the token after it. Formatters
+    /// usually want this, so end of line comments stay where they are.
+    #[default]
+    SameLineTrailing,
+    /// Every comment leads the token after it, as doc extractors usually want.
+    Leading,
+    /// Every comment trails the token before it, apart from comments before the first
+    /// token, which lead it.
+    Trailing,
+}
+
+/// A token with the comments attached to it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TriviaToken {
+    pub token: Token,
+    /// The comments before the token, in order.
+    pub leading: Vec<Token>,
+    /// The comments after the token, in order.
+    pub trailing: Vec<Token>,
+}
+impl TriviaToken {
+    pub fn new(token: Token) -> Self {
+        Self {
+            token,
+            leading: Vec::new(),
+            trailing: Vec::new(),
+        }
+    }
+}
+
+/// Tokens with their comments attached, for tools that need comments to move with the code
+/// around them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Trivia {
+    pub tokens: Vec<TriviaToken>,
+    /// The comments after the last token that don't trail it under the association, or
+    /// every comment if there are no other tokens.
+    pub dangling: Vec<Token>,
+}
+impl Trivia {
+    /// Attaches each comment in the tokens to the token before or after it.
+    pub fn attach(tokens: Vec<Token>, association: CommentAssociation) -> Self {
+        let mut trivia = Self::default();
+        let mut pending = Vec::new();
+        for token in tokens {
+            if !matches!(token.kind, TokenKind::Comment(_)) {
+                let mut token = TriviaToken::new(token);
+                token.leading = std::mem::take(&mut pending);
+                trivia.tokens.push(token);
+                continue;
+            }
+
+            let trails = match association {
+                CommentAssociation::SameLineTrailing => trivia
+                    .tokens
+                    .last()
+                    .is_some_and(|previous| end_row(&previous.token) == token.location.row),
+                CommentAssociation::Leading => false,
+                CommentAssociation::Trailing => true,
+            };
+            match trivia.tokens.last_mut() {
+                Some(previous) if trails => previous.trailing.push(token),
+                _ => pending.push(token),
+            }
+        }
+
+        trivia.dangling = pending;
+        trivia
+    }
+}
+
+/// Returns the row a token ends on, counting the line breaks in its contents.
+fn end_row(token: &Token) -> usize {
+    token.location.row + token.contents.matches('\n').count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::tokenize;
+
+    fn comments(tokens: &[Token]) -> Vec<&str> {
+        tokens.iter().map(|token| &*token.contents).collect()
+    }
+
+    #[test]
+    fn comments_attach_by_association() {
+        let source = "# about x\nx = 1 # one\n# about y\ny = \"a\nb\" # two\n# end";
+        let tokens = tokenize(source, (0, 0).into()).unwrap();
+
+        let trivia = Trivia::attach(tokens.clone(), CommentAssociation::SameLineTrailing);
+        assert_eq!(trivia.tokens.len(), 6);
+        assert_eq!(comments(&trivia.tokens[0].leading), ["about x"]);
+        assert_eq!(comments(&trivia.tokens[2].trailing), ["one"]);
+        assert_eq!(comments(&trivia.tokens[3].leading), ["about y"]);
+        assert_eq!(comments(&trivia.tokens[5].trailing), ["two"]);
+        assert_eq!(comments(&trivia.dangling), ["end"]);
+
+        let trivia = Trivia::attach(tokens.clone(), CommentAssociation::Leading);
+        assert_eq!(comments(&trivia.tokens[2].trailing), Vec::<&str>::new());
+        assert_eq!(comments(&trivia.tokens[3].leading), ["one", "about y"]);
+        assert_eq!(comments(&trivia.dangling), ["two", "end"]);
+
+        let trivia = Trivia::attach(tokens, CommentAssociation::Trailing);
+        assert_eq!(comments(&trivia.tokens[0].leading), ["about x"]);
+        assert_eq!(comments(&trivia.tokens[2].trailing), ["one", "about y"]);
+        assert_eq!(comments(&trivia.tokens[5].trailing), ["two", "end"]);
+        assert!(trivia.dangling.is_empty());
+
+        let only_comments = tokenize("# a\n# b", (0, 0).into()).unwrap();
+        let trivia = Trivia::attach(only_comments, CommentAssociation::Trailing);
+        assert_eq!(comments(&trivia.dangling), ["a", "b"]);
+    }
+}
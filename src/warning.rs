@@ -0,0 +1,8 @@
+use crate::location::Location;
+
+/// A non-fatal problem found while tokenizing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub message: String,
+    pub location: Location,
+}
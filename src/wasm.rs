@@ -0,0 +1,13 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{json::tokens_to_json, tokenizer::Tokenizer};
+
+/// Tokenizes the source for JavaScript, returning the tokens as a JSON array in the form
+/// produced by `tokens_to_json`. Throws an `Error` with the rendered diagnostic if
+/// tokenizing fails.
+#[wasm_bindgen(js_name = tokenize)]
+pub fn tokenize_js(source: &str) -> Result<String, JsError> {
+    Tokenizer::tokenize(source, (0, 0).into())
+        .map(|tokens| tokens_to_json(&tokens))
+        .map_err(|error| JsError::new(&error.render(source)))
+}
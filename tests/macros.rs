@@ -0,0 +1,65 @@
+//! `tokenize!` expands to paths under `::tokenizer`, which only resolve outside the crate.
+#![cfg(feature = "macros")]
+
+use tokenizer::{tokenize, Span, StaticToken, StaticTokenKind, Token};
+
+#[test]
+fn tokenize_at_compile_time() {
+    let tokens = tokenize!("x = 1.5 # done");
+    assert_eq!(tokens.len(), 4);
+    assert_eq!(tokens[0].kind, StaticTokenKind::Identifier("x"));
+    assert_eq!(tokens[2].kind, StaticTokenKind::FloatLiteral(1.5));
+    assert_eq!(tokens[3].span, Span::new(8, 14));
+
+    let expected = tokenizer::tokenize("x = 1.5 # done", (0, 0).into()).unwrap();
+    let tokens: Vec<Token> = tokens.iter().map(StaticToken::to_token).collect();
+    assert_eq!(expected, tokens);
+}
+
+#[test]
+fn floats_too_long_for_f64_tokenize_to_infinity() {
+    let tokens = tokenize!("1000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000.5 -1000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000.5");
+    let expected = tokenizer::tokenize("1000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000.5 -1000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000.5", (0, 0).into()).unwrap();
+    assert_eq!(tokens[0].kind, StaticTokenKind::FloatLiteral(f64::INFINITY));
+    assert_eq!(
+        tokens[1].kind,
+        StaticTokenKind::FloatLiteral(f64::NEG_INFINITY)
+    );
+    let tokens: Vec<Token> = tokens.iter().map(StaticToken::to_token).collect();
+    assert_eq!(expected, tokens);
+}
+
+#[derive(Debug, PartialEq, tokenizer::Tokenize)]
+enum Keyword {
+    #[token("let")]
+    Let,
+    #[token("=")]
+    Equals,
+    #[identifier]
+    Name(String),
+    #[integer]
+    Number(u8),
+}
+
+#[test]
+fn derive_tokenize() {
+    use tokenizer::{ErrorKind, Tokenize};
+
+    let tokens = Keyword::tokenize("let x = 7", (0, 0).into()).unwrap();
+    let keywords: Vec<&Keyword> = tokens.iter().map(|(keyword, _)| keyword).collect();
+    assert_eq!(
+        keywords,
+        vec![
+            &Keyword::Let,
+            &Keyword::Name("x".to_string()),
+            &Keyword::Equals,
+            &Keyword::Number(7)
+        ]
+    );
+    assert_eq!(tokens[3].1.location, (0, 8).into());
+
+    let error = Keyword::tokenize("let x = 300", (0, 0).into()).unwrap_err();
+    assert_eq!(error.kind, ErrorKind::UnexpectedToken);
+    assert_eq!(error.message, "Unexpected int '300'");
+    assert_eq!(error.location, (0, 8).into());
+}